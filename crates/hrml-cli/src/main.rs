@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use hrml_codegen::Binding;
 use std::path::Path;
 
 #[derive(Parser)]
@@ -16,21 +17,104 @@ enum Command {
     Build {
         /// Input .hrml file
         path: String,
+
+        /// JavaScript language level for the generated bindings
+        #[arg(long, value_enum, default_value_t = Target::Modern)]
+        target: Target,
+
+        /// Skip the embedded runtime; the page must load a global `hrml` itself
+        #[arg(long)]
+        no_runtime: bool,
+
+        /// Log bindings to the browser console as they fire
+        #[arg(long)]
+        debug: bool,
+
+        /// Print output sizes and binding counts
+        #[arg(long)]
+        stats: bool,
+
+        /// Emit compact HTML and JS instead of indented output
+        #[arg(long)]
+        minify: bool,
+
+        /// Leave out the comment naming the compiler version
+        #[arg(long)]
+        no_banner: bool,
+
+        /// Emit no comments in the generated HTML or JS
+        #[arg(long)]
+        strip_comments: bool,
+
+        /// Don't add type="button" to buttons that have no type
+        #[arg(long)]
+        no_button_type: bool,
     },
 
     /// Check an .hrml file for errors without generating output
     Check {
         /// Input .hrml file
         path: String,
+
+        /// Stop after parsing; skip code generation and its analysis
+        #[arg(long)]
+        syntax_only: bool,
     },
+
+    /// List the reactive bindings found in an .hrml file, without generating JS
+    Inspect {
+        /// Input .hrml file
+        path: String,
+    },
+}
+
+/// JavaScript output target (`--target`).
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+    /// Arrow functions and template literals
+    Modern,
+    /// Function expressions and string concatenation
+    Es5,
+}
+
+impl From<Target> for hrml_codegen::JsTarget {
+    fn from(target: Target) -> Self {
+        match target {
+            Target::Modern => hrml_codegen::JsTarget::Modern,
+            Target::Es5 => hrml_codegen::JsTarget::Es5,
+        }
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Command::Build { path } => cmd_build(&path),
-        Command::Check { path } => cmd_check(&path),
+        Command::Build {
+            path,
+            target,
+            no_runtime,
+            debug,
+            stats,
+            minify,
+            no_banner,
+            strip_comments,
+            no_button_type,
+        } => {
+            let options = hrml_codegen::CodegenOptions {
+                target: target.into(),
+                no_runtime,
+                debug,
+                minify,
+                no_banner,
+                strip_comments,
+                no_button_type,
+                ..Default::default()
+            };
+            cmd_build(&path, &options, stats)
+        }
+        Command::Check { path, syntax_only } => cmd_check(&path, syntax_only),
+        Command::Inspect { path } => cmd_inspect(&path),
     }
 }
 
@@ -49,7 +133,7 @@ fn read_source(path: &str) -> String {
     }
 }
 
-fn cmd_build(path: &str) {
+fn cmd_build(path: &str, options: &hrml_codegen::CodegenOptions, stats: bool) {
     let source = read_source(path);
 
     let doc = match hrml_parser::Parser::parse(&source) {
@@ -60,7 +144,7 @@ fn cmd_build(path: &str) {
         }
     };
 
-    let output = match hrml_codegen::compile(&doc) {
+    let output = match hrml_codegen::compile_with_options(&doc, options) {
         Ok(output) => output,
         Err(e) => {
             eprintln!("Codegen error: {e}");
@@ -68,26 +152,22 @@ fn cmd_build(path: &str) {
         }
     };
 
+    for warning in &output.warnings {
+        eprintln!("Warning: {warning}");
+    }
+
     // Write output files next to the source
-    let stem = Path::new(path).file_stem().unwrap().to_str().unwrap();
+    let Some(stem) = Path::new(path).file_stem().and_then(|stem| stem.to_str()) else {
+        eprintln!("Error: {path} has no file name");
+        std::process::exit(1);
+    };
     let dir = Path::new(path).parent().unwrap_or(Path::new("."));
 
     let html_path = dir.join(format!("{stem}.html"));
     let js_path = dir.join(format!("{stem}.js"));
 
     // Build a standalone HTML file
-    let mut html = String::new();
-    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"UTF-8\">\n");
-    html.push_str(&format!("  <title>{stem}</title>\n"));
-    if !output.css.is_empty() {
-        html.push_str(&format!("  <style>\n{}\n  </style>\n", output.css));
-    }
-    html.push_str("</head>\n<body>\n");
-    html.push_str(&output.html);
-    if !output.js.is_empty() {
-        html.push_str(&format!("<script>\n{}</script>\n", output.js));
-    }
-    html.push_str("</body>\n</html>\n");
+    let html = output.standalone(stem);
 
     if let Err(e) = std::fs::write(&html_path, &html) {
         eprintln!("Error writing {}: {e}", html_path.display());
@@ -103,22 +183,265 @@ fn cmd_build(path: &str) {
     }
 
     eprintln!("Built: {}", html_path.display());
+
+    if stats {
+        eprint!("{}", stats_table(&output));
+    }
 }
 
-fn cmd_check(path: &str) {
+/// The `--stats` summary: output sizes in bytes, then binding and state counts.
+fn stats_table(output: &hrml_codegen::CompilerOutput) -> String {
+    let stats = &output.stats;
+    let rows = [
+        ("HTML", format!("{} B", output.html.len())),
+        ("CSS", format!("{} B", output.css.len())),
+        ("JS", format!("{} B", output.js.len())),
+        ("Bindings", stats.bindings.to_string()),
+        ("State fields", stats.state_fields.to_string()),
+        ("Computed fields", stats.computed_fields.to_string()),
+    ];
+    rows.iter()
+        .map(|(label, value)| format!("  {label:<16}{value:>10}\n"))
+        .collect()
+}
+
+fn cmd_check(path: &str, syntax_only: bool) {
     let source = read_source(path);
 
-    if let Err(e) = hrml_parser::Parser::parse(&source) {
-        eprintln!("Parse error: {e}");
-        std::process::exit(1);
+    match check(&source, syntax_only) {
+        Ok(warnings) => {
+            for warning in &warnings {
+                eprintln!("Warning: {warning}");
+            }
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
     }
 
-    // Also try codegen to catch codegen errors
-    let doc = hrml_parser::Parser::parse(&source).unwrap();
-    if let Err(e) = hrml_codegen::compile(&doc) {
-        eprintln!("Codegen error: {e}");
-        std::process::exit(1);
+    eprintln!("OK: {path}");
+}
+
+/// Parse `source` and, unless `syntax_only`, run codegen to catch its errors
+/// too. Returns the codegen warnings, or the first error as a message.
+fn check(source: &str, syntax_only: bool) -> Result<Vec<String>, String> {
+    let doc = hrml_parser::Parser::parse(source).map_err(|e| format!("Parse error: {e}"))?;
+    if syntax_only {
+        return Ok(Vec::new());
     }
+    let output = hrml_codegen::compile(&doc).map_err(|e| format!("Codegen error: {e}"))?;
+    Ok(output.warnings)
+}
 
-    eprintln!("OK: {path}");
+fn cmd_inspect(path: &str) {
+    let source = read_source(path);
+
+    let doc = match hrml_parser::Parser::parse(&source) {
+        Ok(doc) => doc,
+        Err(e) => {
+            eprintln!("Parse error: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    match hrml_codegen::collect_bindings(&doc, &hrml_codegen::CodegenOptions::default()) {
+        Ok(bindings) => print!("{}", bindings_table(&bindings)),
+        Err(e) => {
+            eprintln!("Codegen error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The `inspect` listing: one binding per line as kind, element id, and what
+/// it evaluates. A list's own bindings follow it, indented.
+fn bindings_table(bindings: &[Binding]) -> String {
+    let mut out = String::new();
+    write_bindings(bindings, 1, &mut out);
+    out
+}
+
+fn write_bindings(bindings: &[Binding], depth: usize, out: &mut String) {
+    for binding in bindings {
+        let (kind, id, detail) = match binding {
+            Binding::Text { id, template } => ("Text", id, template.clone()),
+            Binding::Event {
+                id,
+                event,
+                handler,
+                modifiers,
+                ..
+            } => {
+                let modifiers: String = modifiers
+                    .iter()
+                    .map(|m| match &m.arg {
+                        Some(arg) => format!(".{}.{arg}", m.name),
+                        None => format!(".{}", m.name),
+                    })
+                    .collect();
+                ("Event", id, format!("{event}{modifiers}: {handler}"))
+            }
+            Binding::Show { id, expr } => ("Show", id, expr.clone()),
+            Binding::Keep { id, expr } => ("Keep", id, expr.clone()),
+            Binding::Prop { id, prop, expr } => ("Prop", id, format!("{prop}: {expr}")),
+            Binding::Class { id, class, expr } => ("Class", id, format!("{class}: {expr}")),
+            Binding::Classes { id, expr } => ("Classes", id, expr.clone()),
+            Binding::Html { id, expr } => ("Html", id, expr.clone()),
+            Binding::Spread { id, expr } => ("Spread", id, expr.clone()),
+            Binding::Attr { id, name, template } => ("Attr", id, format!("{name}: {template}")),
+            Binding::Model { id, field } => ("Model", id, field.clone()),
+            Binding::List {
+                id,
+                item,
+                index,
+                items,
+                key,
+                ..
+            } => {
+                let names = match index {
+                    Some(index) => format!("({item}, {index})"),
+                    None => item.clone(),
+                };
+                let key = key
+                    .as_ref()
+                    .map(|k| format!(" key {k}"))
+                    .unwrap_or_default();
+                ("List", id, format!("{names} in {items}{key}"))
+            }
+        };
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!("{indent}{kind:<8}{id:<10}{detail}\n"));
+        if let Binding::List { bindings, .. } = binding {
+            write_bindings(bindings, depth + 1, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `source` to a fresh temp dir as `<name>.hrml` and return its path.
+    fn write_source(name: &str, source: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("hrml-cli-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{name}.hrml"));
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    // =========================================================================
+    // build
+    // =========================================================================
+
+    #[test]
+    fn test_build_honors_page_viewport() {
+        let path = write_source(
+            "viewport",
+            "page\n  viewport: \"width=device-width, initial-scale=1, maximum-scale=1\"\n\np \"Hi\"",
+        );
+        cmd_build(path.to_str().unwrap(), &hrml_codegen::CodegenOptions::default(), false);
+
+        let html = std::fs::read_to_string(path.with_extension("html")).unwrap();
+        assert!(html.contains(
+            "<head>\n  <meta charset=\"UTF-8\">\n  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1, maximum-scale=1\">\n"
+        ));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_build_renders_page_meta_tags() {
+        let path = write_source(
+            "meta",
+            "page\n  description: \"Team dashboard\"\n  \"og:title\": \"Dashboard\"\n\np \"Hi\"",
+        );
+        cmd_build(path.to_str().unwrap(), &hrml_codegen::CodegenOptions::default(), false);
+
+        let html = std::fs::read_to_string(path.with_extension("html")).unwrap();
+        let (head, _) = html.split_once("</head>").unwrap();
+        assert!(head.contains("  <meta name=\"description\" content=\"Team dashboard\">\n"));
+        assert!(head.contains("  <meta property=\"og:title\" content=\"Dashboard\">\n"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_build_injects_head_content() {
+        let path = write_source(
+            "head",
+            "head\n  raw \"<link rel=\\\"stylesheet\\\" href=\\\"https://cdn.example.com/a.css\\\">\"\n\np \"Hi\"",
+        );
+        cmd_build(path.to_str().unwrap(), &hrml_codegen::CodegenOptions::default(), false);
+
+        let html = std::fs::read_to_string(path.with_extension("html")).unwrap();
+        let (head, body) = html.split_once("</head>").unwrap();
+        assert!(head.contains("  <link rel=\"stylesheet\" href=\"https://cdn.example.com/a.css\">\n"));
+        assert!(!body.contains("<link"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_build_minify_is_smaller() {
+        let source = include_str!("../../../examples/counter.hrml");
+        let mut sizes = Vec::new();
+        for minify in [false, true] {
+            let path = write_source(&format!("minify-{minify}"), source);
+            let options = hrml_codegen::CodegenOptions {
+                minify,
+                ..Default::default()
+            };
+            cmd_build(path.to_str().unwrap(), &options, false);
+
+            let html = std::fs::read_to_string(path.with_extension("html")).unwrap();
+            for id in ["hrml-0", "hrml-7"] {
+                assert!(html.contains(&format!("id=\"{id}\"")), "minify={minify}: missing {id}");
+                assert!(html.contains(&format!("'{id}'")), "minify={minify}: no binding for {id}");
+            }
+            sizes.push(html.len());
+            std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+        assert!(sizes[1] < sizes[0], "minified {} >= default {}", sizes[1], sizes[0]);
+    }
+
+    #[test]
+    fn test_stats_table() {
+        let doc = hrml_parser::Parser::parse(include_str!("../../../examples/counter.hrml")).unwrap();
+        let output = hrml_codegen::compile(&doc).unwrap();
+        let table = stats_table(&output);
+        assert!(table.contains(&format!("  HTML{:>22}\n", format!("{} B", output.html.len()))));
+        assert!(table.contains("  Bindings                 8\n"));
+        assert!(table.contains("  State fields             2\n"));
+    }
+
+    // =========================================================================
+    // inspect
+    // =========================================================================
+
+    #[test]
+    fn test_bindings_table_lists_counter_bindings() {
+        let doc = hrml_parser::Parser::parse(include_str!("../../../examples/counter.hrml")).unwrap();
+        let options = hrml_codegen::CodegenOptions::default();
+        let table = bindings_table(&hrml_codegen::collect_bindings(&doc, &options).unwrap());
+        assert!(table.contains("  Text    hrml-0    Current count: ${_s.count}\n"));
+        assert!(table.contains("  Event   hrml-4    click: _s.count = _s.count - _s.step\n"));
+        assert!(table.contains("  Event   hrml-5    click: _s.count = 0\n"));
+        assert!(table.contains("  Model   hrml-7    step\n"));
+        assert_eq!(table.lines().count(), 8);
+    }
+
+    // =========================================================================
+    // check
+    // =========================================================================
+
+    #[test]
+    fn test_check_syntax_only_skips_codegen() {
+        // Parses, but a void element can't have children
+        let source = "img src=\"a.png\"\n  span \"caption\"";
+        let err = check(source, false).unwrap_err();
+        assert!(err.starts_with("Codegen error: "), "{err}");
+        assert_eq!(check(source, true), Ok(Vec::new()));
+
+        let err = check("p :show=", true).unwrap_err();
+        assert!(err.starts_with("Parse error: "), "{err}");
+    }
 }
@@ -4,8 +4,15 @@
 //! During generation, assigns auto-IDs (`hrml-N`) to reactive elements
 //! and collects bindings for the JS generator.
 
-use crate::{expr_to_js, expr_to_js_literal, Binding, CodegenError, CompilationContext};
-use hrml_parser::ast::{AttributePrefix, Document, Element, ExprKind, Node};
+use crate::{
+    expr_to_js, expr_to_js_literal, handler_expr_to_js, Binding, CodegenError, CompilationContext,
+    JsTarget,
+};
+use hrml_lexer::{unescape_braces, ESCAPED_CLOSE_BRACE, ESCAPED_OPEN_BRACE};
+use hrml_parser::ast::{
+    Attribute, AttributePrefix, BinaryOp, Document, Element, ExprKind, Expression, FnDecl, ForLoop,
+    Node,
+};
 
 /// Generate HTML from a document AST, populating bindings in the context.
 pub fn generate(
@@ -15,27 +22,117 @@ pub fn generate(
     let mut html = String::new();
 
     for node in &doc.nodes {
+        // A top-level `head` goes into the standalone page's <head> instead
+        if matches!(node, Node::Element(el) if el.tag == "head") {
+            continue;
+        }
         generate_node(node, ctx, &mut html, 0)?;
     }
 
     Ok(html)
 }
 
+/// Leading whitespace for an element at `depth`; none when minifying.
+fn indentation(depth: usize, ctx: &CompilationContext) -> String {
+    if ctx.options.minify {
+        String::new()
+    } else {
+        "  ".repeat(depth)
+    }
+}
+
+/// The line break after a tag; none when minifying.
+fn line_end(ctx: &CompilationContext) -> &'static str {
+    if ctx.options.minify {
+        ""
+    } else {
+        "\n"
+    }
+}
+
+/// Whether text contains an unescaped `{` that starts an interpolation.
+fn has_interpolation(text: &str) -> bool {
+    raw_segments(text)
+        .iter()
+        .any(|segment| matches!(segment, TextSegment::Expr(_)))
+}
+
 fn generate_node(
     node: &Node,
     ctx: &mut CompilationContext,
     out: &mut String,
     depth: usize,
 ) -> Result<(), CodegenError> {
-    match node {
+    let result = match node {
         Node::Element(el) => generate_element(el, ctx, out, depth),
+        // A fragment's children render in place, with no wrapper element
+        Node::Fragment(children) => {
+            for child in children {
+                generate_node(child, ctx, out, depth)?;
+            }
+            Ok(())
+        }
         Node::Text(text) => {
-            out.push_str(text);
+            out.push_str(&unescape_braces(text));
+            Ok(())
+        }
+        // Raw markup is copied through untouched (no indentation, no escaping)
+        Node::Raw(markup) => {
+            out.push_str(markup);
+            out.push_str(line_end(ctx));
+            Ok(())
+        }
+        Node::FnDecl(decl) => {
+            let place = format!("fn {}", decl.name);
+            for statement in &decl.body {
+                crate::reject_emit(statement, &ctx.reactive_names(), &place)?;
+            }
+            let js = fn_decl_to_js(decl, ctx);
+            ctx.functions.push(js);
             Ok(())
         }
-        // State/Computed/Fn/Component/Comment don't generate HTML
-        _ => Ok(()),
+        Node::Component(component) => Err(CodegenError {
+            message: format!(
+                "Component '{}' can't be compiled yet; inline its markup instead",
+                component.name
+            ),
+        }),
+        // Collected before generation (see `compile_with_options`); no HTML
+        Node::StateBlock(_)
+        | Node::ConstBlock(_)
+        | Node::ComputedBlock(_)
+        | Node::EffectBlock(_)
+        | Node::PageBlock(_)
+        | Node::ConfigBlock(_)
+        | Node::Comment(_) => Ok(()),
+    };
+    // Under `compile_lenient`, a node that failed leaves a marker behind
+    if ctx.recover(result)?.is_none() && !ctx.options.strip_comments {
+        out.push_str(&indentation(depth, ctx));
+        out.push_str("<!-- hrml: compile error -->");
+        out.push_str(line_end(ctx));
     }
+    Ok(())
+}
+
+/// A `fn` as a JS function declaration. Parameters shadow state names.
+fn fn_decl_to_js(decl: &FnDecl, ctx: &CompilationContext) -> String {
+    let scope: Vec<String> = ctx
+        .reactive_names()
+        .into_iter()
+        .filter(|name| !decl.params.contains(name))
+        .collect();
+    let body: String = decl
+        .body
+        .iter()
+        .map(|statement| format!("{}; ", expr_to_js(statement, &scope)))
+        .collect();
+    let keyword = if decl.is_async { "async " } else { "" };
+    format!(
+        "{keyword}function {}({}) {{ {body}}}",
+        decl.name,
+        decl.params.join(", ")
+    )
 }
 
 fn generate_element(
@@ -44,7 +141,23 @@ fn generate_element(
     out: &mut String,
     depth: usize,
 ) -> Result<(), CodegenError> {
-    let indent = "  ".repeat(depth);
+    // Children of a void element would be silently dropped
+    if is_void_element(&el.tag) && el.children.iter().any(|c| !matches!(c, Node::Comment(_))) {
+        return Err(CodegenError {
+            message: format!(
+                "<{}> is a void element and can't have children; move them to a sibling",
+                el.tag
+            ),
+        });
+    }
+
+    check_emit(el, ctx)?;
+
+    if let Some(for_loop) = &el.for_loop {
+        return generate_list(el, for_loop, ctx, out, depth);
+    }
+
+    let indent = indentation(depth, ctx);
 
     // Determine if this element needs an auto-generated ID
     let needs_id = element_needs_id(el);
@@ -66,7 +179,7 @@ fn generate_element(
 
     // ID attribute
     if let Some(ref id) = id {
-        out.push_str(&format!(" id=\"{id}\""));
+        out.push_str(&id_attr(id, ctx));
     }
 
     // Class attribute
@@ -74,9 +187,10 @@ fn generate_element(
         out.push_str(&format!(" class=\"{}\"", el.classes.join(" ")));
     }
 
-    // Plain attributes (no prefix) — rendered as HTML attributes
+    // Plain attributes (no prefix) — rendered as HTML attributes.
+    // Interpolated values are set by JS via hrml.attr instead.
     for attr in &el.attributes {
-        if attr.prefix.is_none() {
+        if attr.prefix.is_none() && interpolated_attr(attr).is_none() {
             out.push(' ');
             out.push_str(&attr.name);
             if let Some(ref value) = attr.value {
@@ -86,11 +200,19 @@ fn generate_element(
         }
     }
 
+    // A <button> without a type submits its form
+    if el.tag == "button"
+        && !ctx.options.no_button_type
+        && !el.attributes.iter().any(|a| a.name == "type")
+    {
+        out.push_str(" type=\"button\"");
+    }
+
     out.push('>');
 
     // Void elements — no closing tag
     if is_void_element(&el.tag) {
-        out.push('\n');
+        out.push_str(line_end(ctx));
         return Ok(());
     }
 
@@ -98,35 +220,155 @@ fn generate_element(
     let has_element_children = el
         .children
         .iter()
-        .any(|c| matches!(c, Node::Element(_)));
+        .any(|c| matches!(c, Node::Element(_) | Node::Fragment(_) | Node::Raw(_)));
     let has_interpolated_text = el
         .children
         .iter()
-        .any(|c| matches!(c, Node::Text(t) if t.contains('{')));
+        .any(|c| matches!(c, Node::Text(t) if has_interpolation(t)));
+
+    let has_text_children = el.children.iter().any(|c| matches!(c, Node::Text(_)));
 
-    if has_element_children {
-        out.push('\n');
+    if has_element_children && has_text_children {
+        // Mixed content: whitespace between text and elements would show up
+        // on the page, so the children are rendered inline. Interpolated text
+        // gets a `<span>` of its own, since binding the parent's text would
+        // replace its element children.
+        for child in &el.children {
+            let mut child_out = String::new();
+            match child {
+                Node::Text(text) if has_interpolation(text) => {
+                    let id = ctx.assign_id();
+                    check_interpolation(text, el, ctx);
+                    let template = interpolate(text, &scope_names(ctx), ctx.options.target);
+                    child_out.push_str(&format!("<span{}></span>", id_attr(&id, ctx)));
+                    ctx.bindings.push(Binding::Text { id, template });
+                }
+                _ => generate_node(child, ctx, &mut child_out, 0)?,
+            }
+            out.push_str(child_out.strip_suffix('\n').unwrap_or(&child_out));
+        }
+    } else if has_element_children {
+        out.push_str(line_end(ctx));
+        let mut previous_inline = false;
         for child in &el.children {
+            // The line break between inline siblings renders as a space, so
+            // minified output keeps one (`<b>a</b> <i>b</i>`)
+            if matches!(child, Node::Element(_) | Node::Fragment(_) | Node::Raw(_)) {
+                let inline = !matches!(child, Node::Element(el) if is_block_element(&el.tag));
+                if ctx.options.minify && previous_inline && inline {
+                    out.push(' ');
+                }
+                previous_inline = inline;
+            }
             generate_node(child, ctx, out, depth + 1)?;
         }
         out.push_str(&indent);
     } else if !has_interpolated_text {
         // Static text children — inline
-        for child in &el.children {
-            if let Node::Text(text) = child {
-                out.push_str(text);
-            }
+        let text: String = el
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                Node::Text(text) => Some(unescape_braces(text)),
+                _ => None,
+            })
+            .collect();
+        if WHITESPACE_TAGS.contains(&el.tag.as_str()) {
+            out.push_str(&text);
+        } else {
+            out.push_str(&ctx.options.whitespace.apply(&text));
         }
     }
     // If has interpolated text, leave element empty (JS fills it via hrml.text)
 
     // Closing tag
     out.push_str(&format!("</{}>", el.tag));
-    out.push('\n');
+    out.push_str(line_end(ctx));
+
+    Ok(())
+}
 
+/// Elements whose text keeps its whitespace under any
+/// [`crate::Whitespace`] policy: it is rendered as written (`code` usually
+/// sits in a `pre`).
+const WHITESPACE_TAGS: &[&str] = &["code", "pre", "textarea"];
+
+/// Render a `:for` element as a `<template>` that the runtime clones per item.
+/// Bindings inside the item are collected separately and attached to the list.
+/// The runtime also iterates objects (value, key) and numbers (0..n).
+fn generate_list(
+    el: &Element,
+    for_loop: &ForLoop,
+    ctx: &mut CompilationContext,
+    out: &mut String,
+    depth: usize,
+) -> Result<(), CodegenError> {
+    // A number literal is a range: `:for="n in 5"` counts 0 to 4
+    if let ExprKind::Number(n) = for_loop.iterable.kind {
+        if n < 0.0 || n.fract() != 0.0 {
+            return Err(CodegenError {
+                message: format!("`:for` range {n} must be a whole number of at least 0"),
+            });
+        }
+    }
+    let indent = indentation(depth, ctx);
+    let id = ctx.assign_id();
+    let items = expr_to_js(&for_loop.iterable, &scope_names(ctx));
+
+    out.push_str(&format!("{indent}<template{}>{}", id_attr(&id, ctx), line_end(ctx)));
+
+    let locals = 1 + usize::from(for_loop.index.is_some());
+    ctx.loop_vars.push(for_loop.item.clone());
+    ctx.loop_vars.extend(for_loop.index.clone());
+    let key = el
+        .attributes
+        .iter()
+        .find(|a| a.prefix == Some(AttributePrefix::State) && a.name == "key")
+        .and_then(|a| a.value.as_ref())
+        .map(|v| expr_to_js(v, &scope_names(ctx)));
+    let outer = std::mem::take(&mut ctx.bindings);
+    let item_el = Element {
+        for_loop: None,
+        ..el.clone()
+    };
+    let result = generate_element(&item_el, ctx, out, depth + 1);
+    let bindings = std::mem::replace(&mut ctx.bindings, outer);
+    ctx.loop_vars.truncate(ctx.loop_vars.len() - locals);
+    result?;
+
+    out.push_str(&format!("{indent}</template>{}", line_end(ctx)));
+
+    ctx.bindings.push(Binding::List {
+        id,
+        item: for_loop.item.clone(),
+        index: for_loop.index.clone(),
+        items,
+        key,
+        bindings,
+    });
     Ok(())
 }
 
+/// The attribute the runtime finds a bound element by. Elements inside a
+/// `:for` item are cloned per item, so they get a `data-hrml` marker instead
+/// of a (duplicated) `id`.
+fn id_attr(id: &str, ctx: &CompilationContext) -> String {
+    if ctx.loop_vars.is_empty() {
+        format!(" id=\"{id}\"")
+    } else {
+        format!(" data-hrml=\"{id}\"")
+    }
+}
+
+/// State and computed names that resolve to `_s` here — loop item names
+/// shadow them.
+fn scope_names(ctx: &CompilationContext) -> Vec<String> {
+    ctx.reactive_names()
+        .into_iter()
+        .filter(|name| !ctx.loop_vars.contains(name))
+        .collect()
+}
+
 /// Check if an element needs an auto-generated ID for reactive bindings.
 fn element_needs_id(el: &Element) -> bool {
     // Has event handlers
@@ -138,43 +380,167 @@ fn element_needs_id(el: &Element) -> bool {
         a.prefix == Some(AttributePrefix::State)
             && matches!(
                 a.name.as_str(),
-                "show" | "if" | "model" | "class" | "text"
+                "show" | "if" | "model" | "class" | "text" | "html" | "disabled" | "readonly"
+                    | "checked"
             )
     })
-    // Has text interpolation in children
-    || el
+    // Has interpolation in a plain attribute value, or an attribute spread
+    || el.attributes.iter().any(|a| interpolated_attr(a).is_some())
+    || !el.spreads.is_empty()
+    || !el.class_toggles.is_empty()
+    // Has text interpolation in children, unless it sits beside elements
+    || has_text_binding(el)
+}
+
+/// Whether the element's own text is bound. Interpolated text mixed with
+/// element children is bound on a `<span>` of its own instead.
+fn has_text_binding(el: &Element) -> bool {
+    let mixed = el
         .children
         .iter()
-        .any(|c| matches!(c, Node::Text(t) if t.contains('{')))
+        .any(|c| matches!(c, Node::Element(_) | Node::Fragment(_) | Node::Raw(_)));
+    !mixed
+        && el
+            .children
+            .iter()
+            .any(|c| matches!(c, Node::Text(t) if has_interpolation(t)))
 }
 
 /// Collect reactive bindings from an element into the compilation context.
 fn collect_bindings(el: &Element, id: &str, ctx: &mut CompilationContext) {
-    let state_names = ctx.state_names();
+    let state_names = scope_names(ctx);
+    check_binding_targets(el, ctx);
+
+    // Several `:if`/`:show` (e.g. one from an enclosing fragment) must all
+    // hold, so they share a single binding
+    let shown: Vec<String> = el
+        .attributes
+        .iter()
+        .filter(|a| {
+            a.prefix == Some(AttributePrefix::State) && matches!(a.name.as_str(), "show" | "if")
+        })
+        .map(|a| {
+            a.value
+                .as_ref()
+                .map(|v| expr_to_js(v, &state_names))
+                .unwrap_or_default()
+        })
+        .collect();
+    let mut conditions = match shown.as_slice() {
+        [single] => single.clone(),
+        _ => shown
+            .iter()
+            .map(|c| format!("({c})"))
+            .collect::<Vec<_>>()
+            .join(" && "),
+    };
+    // `:show.hidden` toggles the `hidden` attribute, leaving `display` to CSS
+    let use_hidden = el.attributes.iter().any(|a| {
+        a.prefix == Some(AttributePrefix::State)
+            && a.name == "show"
+            && a.modifiers.iter().any(|m| m.name == "hidden")
+    });
+    // `:if.keepalive` takes the element out of the page instead of hiding it
+    let keepalive = el.attributes.iter().any(|a| {
+        a.prefix == Some(AttributePrefix::State)
+            && a.name == "if"
+            && a.modifiers.iter().any(|m| m.name == "keepalive")
+    });
 
     for attr in &el.attributes {
+        if let (Some(_), Some(value)) = (attr.prefix, &attr.value) {
+            crate::check_chained_comparisons(value, &mut ctx.warnings);
+        }
         match attr.prefix {
             Some(AttributePrefix::Event) => {
+                if let Some(ExprKind::Identifier(name)) = attr.value.as_ref().map(|v| &v.kind) {
+                    if !ctx.fn_names.contains(name) {
+                        ctx.warnings.push(format!(
+                            "@{}=\"{name}\" on <{}> only reads '{name}'; call it as {name}($event) or declare fn {name}",
+                            attr.name, el.tag
+                        ));
+                    }
+                }
                 let handler = attr
                     .value
                     .as_ref()
-                    .map(|v| expr_to_js(v, &state_names))
+                    .map(|v| handler_to_js(v, &state_names, &ctx.fn_names, ctx.options.target))
                     .unwrap_or_default();
+                let listener = attr
+                    .value
+                    .as_ref()
+                    .is_some_and(|v| matches!(v.kind, ExprKind::Arrow { .. }));
+                let is_async = attr.value.as_ref().is_some_and(crate::contains_await);
+                let emits = attr
+                    .value
+                    .as_ref()
+                    .is_some_and(|v| crate::calls_emit(v, &state_names));
                 ctx.bindings.push(Binding::Event {
                     id: id.to_string(),
                     event: attr.name.clone(),
                     handler,
+                    listener,
+                    is_async,
+                    emits,
                     modifiers: attr.modifiers.clone(),
                 });
             }
             Some(AttributePrefix::State) => match attr.name.as_str() {
-                "show" | "if" => {
+                "show" | "if" if !conditions.is_empty() => {
+                    let expr = std::mem::take(&mut conditions);
+                    if keepalive {
+                        ctx.bindings.push(Binding::Keep {
+                            id: id.to_string(),
+                            expr,
+                        });
+                    } else if use_hidden {
+                        ctx.bindings.push(Binding::Prop {
+                            id: id.to_string(),
+                            prop: "hidden".to_string(),
+                            expr: format!("!({expr})"),
+                        });
+                    } else {
+                        ctx.bindings.push(Binding::Show {
+                            id: id.to_string(),
+                            expr,
+                        });
+                    }
+                }
+                "disabled" | "readonly" | "checked" => {
+                    let expr = attr
+                        .value
+                        .as_ref()
+                        .map(|v| expr_to_js(v, &state_names))
+                        .unwrap_or_else(|| "true".to_string());
+                    // DOM property names; `readonly` is camel-cased
+                    let prop = match attr.name.as_str() {
+                        "readonly" => "readOnly",
+                        name => name,
+                    };
+                    ctx.bindings.push(Binding::Prop {
+                        id: id.to_string(),
+                        prop: prop.to_string(),
+                        expr,
+                    });
+                }
+                "class" => {
                     let expr = attr
                         .value
                         .as_ref()
                         .map(|v| expr_to_js(v, &state_names))
                         .unwrap_or_default();
-                    ctx.bindings.push(Binding::Show {
+                    ctx.bindings.push(Binding::Classes {
+                        id: id.to_string(),
+                        expr,
+                    });
+                }
+                "html" => {
+                    let expr = attr
+                        .value
+                        .as_ref()
+                        .map(|v| expr_to_js(v, &state_names))
+                        .unwrap_or_default();
+                    ctx.bindings.push(Binding::Html {
                         id: id.to_string(),
                         expr,
                     });
@@ -195,16 +561,45 @@ fn collect_bindings(el: &Element, id: &str, ctx: &mut CompilationContext) {
                 }
                 _ => {}
             },
+            None => {
+                if let Some(text) = interpolated_attr(attr) {
+                    check_interpolation(text, el, ctx);
+                    // The binding replaces the whole attribute, so a dynamic
+                    // `class` carries the `.shorthand` classes along
+                    let text = if attr.name == "class" && !el.classes.is_empty() {
+                        format!("{} {text}", el.classes.join(" "))
+                    } else {
+                        text.to_string()
+                    };
+                    ctx.bindings.push(Binding::Attr {
+                        id: id.to_string(),
+                        name: attr.name.clone(),
+                        template: interpolate(&text, &state_names, ctx.options.target),
+                    });
+                }
+            }
             _ => {}
         }
     }
 
+    for (class, condition) in &el.class_toggles {
+        crate::check_chained_comparisons(condition, &mut ctx.warnings);
+        ctx.bindings.push(Binding::Class {
+            id: id.to_string(),
+            class: class.clone(),
+            expr: expr_to_js(condition, &state_names),
+        });
+    }
+
+    for spread in &el.spreads {
+        ctx.bindings.push(Binding::Spread {
+            id: id.to_string(),
+            expr: expr_to_js(spread, &state_names),
+        });
+    }
+
     // Text interpolation binding
-    let has_interpolation = el
-        .children
-        .iter()
-        .any(|c| matches!(c, Node::Text(t) if t.contains('{')));
-    if has_interpolation {
+    if has_text_binding(el) {
         let text: String = el
             .children
             .iter()
@@ -216,7 +611,8 @@ fn collect_bindings(el: &Element, id: &str, ctx: &mut CompilationContext) {
                 }
             })
             .collect();
-        let template = interpolate_text(&text, &state_names);
+        check_interpolation(&text, el, ctx);
+        let template = interpolate(&text, &state_names, ctx.options.target);
         ctx.bindings.push(Binding::Text {
             id: id.to_string(),
             template,
@@ -224,11 +620,208 @@ fn collect_bindings(el: &Element, id: &str, ctx: &mut CompilationContext) {
     }
 }
 
+/// Browser globals that interpolations may use without declaring them.
+const JS_GLOBALS: &[&str] = &[
+    "Array", "Boolean", "Date", "Infinity", "Intl", "JSON", "Math", "NaN", "Number", "Object",
+    "String", "console", "document", "encodeURIComponent", "isNaN", "parseFloat", "parseInt",
+    "window",
+];
+
+/// Warn about `{expr}` holes that read a name that is neither reactive, a loop
+/// item, nor a browser global — it would throw a ReferenceError at runtime.
+fn check_interpolation(text: &str, el: &Element, ctx: &mut CompilationContext) {
+    let mut known = ctx.reactive_names();
+    known.extend(ctx.const_fields.iter().map(|(name, _)| name.clone()));
+    known.extend(ctx.loop_vars.iter().cloned());
+
+    for segment in raw_segments(text) {
+        let TextSegment::Expr(source) = segment else {
+            continue;
+        };
+        // Unparseable holes are emitted as-is; only names are checked here
+        let Ok(expr) = hrml_parser::expr_parser::ExprParser::parse_interpolation(&source) else {
+            continue;
+        };
+        crate::check_chained_comparisons(&expr, &mut ctx.warnings);
+        let mut names = Vec::new();
+        crate::collect_identifiers(&expr, &mut names);
+        for name in names {
+            if !known.contains(&name) && !JS_GLOBALS.contains(&name.as_str()) {
+                ctx.warnings.push(format!(
+                    "Unknown name '{name}' in interpolation on <{}>; declare it in state or computed",
+                    el.tag
+                ));
+            }
+        }
+    }
+}
+
+/// `emit` is only lowered in `@event` handlers; reject it in an element's
+/// other expressions.
+fn check_emit(el: &Element, ctx: &CompilationContext) -> Result<(), CodegenError> {
+    let names = scope_names(ctx);
+    let place = |what: &str| format!("{what} on <{}>", el.tag);
+    if let Some(for_loop) = &el.for_loop {
+        crate::reject_emit(&for_loop.iterable, &names, &place(":for"))?;
+    }
+    for (class, condition) in &el.class_toggles {
+        crate::reject_emit(condition, &names, &place(&format!("'.{class}'")))?;
+    }
+    for attr in &el.attributes {
+        match (attr.prefix, &attr.value) {
+            (Some(AttributePrefix::Event), _) | (_, None) => {}
+            (Some(_), Some(value)) => {
+                crate::reject_emit(value, &names, &place(&format!("'{}'", attr.name)))?;
+            }
+            (None, Some(_)) => {
+                if let Some(text) = interpolated_attr(attr) {
+                    reject_emit_in_text(text, &names, &place(&format!("'{}'", attr.name)))?;
+                }
+            }
+        }
+    }
+    for child in &el.children {
+        if let Node::Text(text) = child {
+            reject_emit_in_text(text, &names, &place("text"))?;
+        }
+    }
+    Ok(())
+}
+
+fn reject_emit_in_text(text: &str, names: &[String], place: &str) -> Result<(), CodegenError> {
+    for segment in raw_segments(text) {
+        if let TextSegment::Expr(source) = segment {
+            if let Ok(expr) = hrml_parser::expr_parser::ExprParser::parse_interpolation(&source) {
+                crate::reject_emit(&expr, names, place)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Elements with a value that `:model` can read and write.
+const MODEL_TAGS: &[&str] = &["input", "select", "textarea"];
+
+/// Events dispatched only on a `<form>` (they bubble from it to ancestors).
+const FORM_EVENTS: &[&str] = &["formdata", "reset", "submit"];
+
+/// Warn about bindings that can never take effect on their element: `:model`
+/// on something without a value, or a form event with no form to fire it.
+/// Custom elements may implement `value` themselves and are not checked.
+fn check_binding_targets(el: &Element, ctx: &mut CompilationContext) {
+    for attr in &el.attributes {
+        match attr.prefix {
+            Some(AttributePrefix::State)
+                if attr.name == "model"
+                    && !MODEL_TAGS.contains(&el.tag.as_str())
+                    && !el.tag.contains('-') =>
+            {
+                ctx.warnings.push(format!(
+                    ":model on <{}> has no effect; use it on <input>, <select>, or <textarea>",
+                    el.tag
+                ));
+            }
+            Some(AttributePrefix::Event)
+                if FORM_EVENTS.contains(&attr.name.as_str()) && !contains_form(el) =>
+            {
+                ctx.warnings.push(format!(
+                    "@{} on <{}> never fires; bind it on a <form>",
+                    attr.name, el.tag
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether `el` is a `<form>` or has one among its descendants.
+fn contains_form(el: &Element) -> bool {
+    fn in_nodes(nodes: &[Node]) -> bool {
+        nodes.iter().any(|node| match node {
+            Node::Element(child) => contains_form(child),
+            Node::Fragment(children) => in_nodes(children),
+            _ => false,
+        })
+    }
+    el.tag == "form" || in_nodes(&el.children)
+}
+
+/// The text of a plain attribute value that contains `{expr}` interpolation
+/// (`href="/user/{id}"`).
+fn interpolated_attr(attr: &Attribute) -> Option<&str> {
+    match (&attr.prefix, attr.value.as_ref().map(|v| &v.kind)) {
+        (None, Some(ExprKind::String(text))) if has_interpolation(text) => Some(text),
+        _ => None,
+    }
+}
+
+/// Render interpolated text as a binding template for the JS target.
+fn interpolate(text: &str, state_names: &[String], target: JsTarget) -> String {
+    match target {
+        JsTarget::Modern => interpolate_text(text, state_names),
+        JsTarget::Es5 => interpolate_concat(text, state_names),
+    }
+}
+
+/// Convert an event handler to JS. Multi-statement handlers are wrapped in
+/// `hrml.batch` so their writes notify effects once, and the bare name of a
+/// declared `fn` (`@click="save"`) is called with the event. An arrow
+/// (`@click="(e) => save(e)"`) is the listener itself.
+fn handler_to_js(
+    expr: &Expression,
+    state_names: &[String],
+    functions: &[String],
+    target: JsTarget,
+) -> String {
+    match &expr.kind {
+        ExprKind::Identifier(name) if functions.contains(name) && !state_names.contains(name) => {
+            format!("{name}($event)")
+        }
+        // An arrow is the listener itself; its parameters shadow state fields
+        ExprKind::Arrow {
+            params,
+            body,
+            is_async,
+        } => {
+            let scope: Vec<String> = state_names
+                .iter()
+                .filter(|name| !params.contains(name))
+                .cloned()
+                .collect();
+            let body = handler_expr_to_js(body, &scope);
+            let params = params.join(", ");
+            let keyword = if *is_async { "async " } else { "" };
+            match target {
+                JsTarget::Modern => format!("{keyword}({params}) => {body}"),
+                JsTarget::Es5 => format!("{keyword}function ({params}) {{ return {body}; }}"),
+            }
+        }
+        // `hrml.batch` runs its callback synchronously, so awaiting handlers
+        // run their statements directly
+        ExprKind::Sequence(stmts) if stmts.iter().any(crate::contains_await) => stmts
+            .iter()
+            .map(|s| handler_expr_to_js(s, state_names))
+            .collect::<Vec<_>>()
+            .join("; "),
+        ExprKind::Sequence(stmts) => {
+            let body: String = stmts
+                .iter()
+                .map(|s| format!("{}; ", handler_expr_to_js(s, state_names)))
+                .collect();
+            match target {
+                JsTarget::Modern => format!("hrml.batch(() => {{ {body}}})"),
+                JsTarget::Es5 => format!("hrml.batch(function () {{ {body}}})"),
+            }
+        }
+        _ => handler_expr_to_js(expr, state_names),
+    }
+}
+
 /// Convert an expression to an HTML attribute value string.
 /// For strings, returns the raw content (no JS quoting).
-fn expr_to_html_attr(expr: &hrml_parser::ast::Expression) -> String {
+fn expr_to_html_attr(expr: &Expression) -> String {
     match &expr.kind {
-        ExprKind::String(s) => s.clone(),
+        ExprKind::String(s) => unescape_braces(s),
         ExprKind::Number(n) => crate::format_number(*n),
         ExprKind::Boolean(b) => b.to_string(),
         ExprKind::Identifier(s) => s.clone(),
@@ -236,21 +829,88 @@ fn expr_to_html_attr(expr: &hrml_parser::ast::Expression) -> String {
     }
 }
 
-/// Transform text with `{expr}` into a JS template literal body.
-/// `"Count: {count}"` → `Count: ${_s.count}`
-fn interpolate_text(text: &str, state_names: &[String]) -> String {
-    let mut result = String::new();
+/// A piece of interpolated text: literal text or a `{expr}` hole.
+enum TextSegment {
+    Static(String),
+    Expr(String),
+}
+
+/// Split text with `{expr}` markers into static and expression segments.
+/// Expression segments are already converted to JS.
+fn text_segments(text: &str, state_names: &[String]) -> Vec<TextSegment> {
+    raw_segments(text)
+        .into_iter()
+        .map(|segment| match segment {
+            TextSegment::Expr(expr) => {
+                if let Ok(parsed) = hrml_parser::expr_parser::ExprParser::parse_interpolation(&expr)
+                {
+                    return TextSegment::Expr(text_expr_to_js(&parsed, state_names));
+                }
+                // Unparseable: prefix the first identifier if it's a state variable
+                let first_ident: String = expr
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                if state_names.iter().any(|s| s == &first_ident) {
+                    TextSegment::Expr(format!("_s.{expr}"))
+                } else {
+                    TextSegment::Expr(expr)
+                }
+            }
+            segment => segment,
+        })
+        .collect()
+}
+
+/// Convert an interpolation hole to JS. A top-level `a && b` renders `b` or
+/// nothing, rather than the text `false` when `a` is falsy.
+fn text_expr_to_js(expr: &Expression, state_names: &[String]) -> String {
+    match &expr.kind {
+        ExprKind::Binary {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => format!(
+            "{} ? {} : ''",
+            expr_to_js(left, state_names),
+            expr_to_js(right, state_names)
+        ),
+        _ => expr_to_js(expr, state_names),
+    }
+}
+
+/// Split text with `{expr}` markers into static text and expression source.
+fn raw_segments(text: &str) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
     let mut chars = text.chars().peekable();
 
     while let Some(ch) = chars.next() {
-        if ch == '{' {
+        // `\{` and `\}` (kept apart by the scanner) are literal braces
+        if ch == ESCAPED_OPEN_BRACE {
+            current.push('{');
+        } else if ch == ESCAPED_CLOSE_BRACE {
+            current.push('}');
+        } else if ch == '{' {
             let mut expr = String::new();
             let mut depth = 1;
+            // Braces inside a string literal (`{label('}')}`) don't count
+            let mut quote = None;
+            let mut escaped = false;
             for next in chars.by_ref() {
-                if next == '{' {
+                if let Some(q) = quote {
+                    if escaped {
+                        escaped = false;
+                    } else if next == '\\' {
+                        escaped = true;
+                    } else if next == q {
+                        quote = None;
+                    }
+                } else if matches!(next, '\'' | '"' | '`') {
+                    quote = Some(next);
+                } else if next == '{' {
                     depth += 1;
-                }
-                if next == '}' {
+                } else if next == '}' {
                     depth -= 1;
                     if depth == 0 {
                         break;
@@ -258,22 +918,47 @@ fn interpolate_text(text: &str, state_names: &[String]) -> String {
                 }
                 expr.push(next);
             }
-            // Prefix first identifier with _s. if it's a state variable
-            let first_ident: String = expr
-                .chars()
-                .take_while(|c| c.is_alphanumeric() || *c == '_')
-                .collect();
-            if state_names.iter().any(|s| s == &first_ident) {
-                result.push_str(&format!("${{_s.{expr}}}"));
-            } else {
-                result.push_str(&format!("${{{expr}}}"));
+            if !current.is_empty() {
+                segments.push(TextSegment::Static(std::mem::take(&mut current)));
             }
+            segments.push(TextSegment::Expr(unescape_braces(&expr)));
         } else {
-            result.push(ch);
+            current.push(ch);
         }
     }
 
-    result
+    if !current.is_empty() {
+        segments.push(TextSegment::Static(current));
+    }
+    segments
+}
+
+/// Transform text with `{expr}` into a JS template literal body.
+/// `"Count: {count}"` → `Count: ${_s.count}`
+fn interpolate_text(text: &str, state_names: &[String]) -> String {
+    text_segments(text, state_names)
+        .into_iter()
+        .map(|segment| match segment {
+            TextSegment::Static(s) => crate::js_template_text(&s),
+            TextSegment::Expr(e) => format!("${{{e}}}"),
+        })
+        .collect()
+}
+
+/// Transform text with `{expr}` into an ES5 string concatenation.
+/// `"Count: {count}"` → `'Count: ' + (_s.count)`
+fn interpolate_concat(text: &str, state_names: &[String]) -> String {
+    let segments = text_segments(text, state_names);
+    let mut parts = Vec::with_capacity(segments.len() + 1);
+    // Keep the result a string even when the text starts with an expression
+    if !matches!(segments.first(), Some(TextSegment::Static(_))) {
+        parts.push("''".to_string());
+    }
+    parts.extend(segments.into_iter().map(|segment| match segment {
+        TextSegment::Static(s) => crate::js_string(&s),
+        TextSegment::Expr(e) => format!("({e})"),
+    }));
+    parts.join(" + ")
 }
 
 /// Check if an HTML tag is a void element (self-closing, no children).
@@ -297,6 +982,64 @@ fn is_void_element(tag: &str) -> bool {
     )
 }
 
+/// Check if an HTML tag is laid out as a block, so minified output needs no
+/// space between it and a sibling. Anything else, custom elements included,
+/// is treated as inline.
+fn is_block_element(tag: &str) -> bool {
+    matches!(
+        tag,
+        "address"
+            | "article"
+            | "aside"
+            | "blockquote"
+            | "body"
+            | "dd"
+            | "details"
+            | "dialog"
+            | "div"
+            | "dl"
+            | "dt"
+            | "fieldset"
+            | "figcaption"
+            | "figure"
+            | "footer"
+            | "form"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "head"
+            | "header"
+            | "hgroup"
+            | "hr"
+            | "li"
+            | "link"
+            | "main"
+            | "meta"
+            | "nav"
+            | "ol"
+            | "option"
+            | "p"
+            | "pre"
+            | "script"
+            | "section"
+            | "style"
+            | "summary"
+            | "table"
+            | "tbody"
+            | "td"
+            | "template"
+            | "tfoot"
+            | "th"
+            | "thead"
+            | "title"
+            | "tr"
+            | "ul"
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -375,6 +1118,79 @@ mod tests {
         assert_eq!(html, "<input type=\"text\" name=\"email\">\n");
     }
 
+    #[test]
+    fn test_boolean_attribute_rendered_bare() {
+        let (html, _) = gen("input required .field type=email");
+        assert_eq!(html, "<input class=\"field\" required type=\"email\">\n");
+    }
+
+    #[test]
+    fn test_button_defaults_to_type_button() {
+        let (html, _) = gen("button \"x\"");
+        assert_eq!(html, "<button type=\"button\">x</button>\n");
+
+        let (html, _) = gen("button type=submit \"Save\"");
+        assert_eq!(html, "<button type=\"submit\">Save</button>\n");
+
+        let mut ctx = CompilationContext::new();
+        ctx.options.no_button_type = true;
+        let html = generate(&parse("button \"x\""), &mut ctx).unwrap();
+        assert_eq!(html, "<button>x</button>\n");
+    }
+
+    #[test]
+    fn test_void_element_with_children_is_error() {
+        let doc = parse("img src=\"a.png\"\n  span \"x\"");
+        let err = generate(&doc, &mut CompilationContext::new()).unwrap_err();
+        assert!(
+            err.message.contains("<img> is a void element"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_void_element_with_comment_child_is_fine() {
+        let (html, _) = gen("br\n  // line break");
+        assert_eq!(html, "<br>\n");
+    }
+
+    #[test]
+    fn test_whitespace_preserved_by_default() {
+        let (html, _) = gen("p \"  two   spaces\n    here  \"");
+        assert_eq!(html, "<p>  two   spaces\n    here  </p>\n");
+    }
+
+    #[test]
+    fn test_whitespace_collapse() {
+        let doc = parse("p \"  two   spaces\n    here  \"");
+        let mut ctx = CompilationContext::new();
+        ctx.options.whitespace = crate::Whitespace::Collapse;
+        assert_eq!(
+            generate(&doc, &mut ctx).unwrap(),
+            "<p> two spaces here </p>\n"
+        );
+    }
+
+    #[test]
+    fn test_whitespace_kept_in_pre_and_textarea() {
+        let doc = parse("pre \"  a   b\"\ntextarea \" x  \"\np \"  a   b\"");
+        let mut ctx = CompilationContext::new();
+        ctx.options.whitespace = crate::Whitespace::Collapse;
+        assert_eq!(
+            generate(&doc, &mut ctx).unwrap(),
+            "<pre>  a   b</pre>\n<textarea> x  </textarea>\n<p> a b</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_whitespace_trim() {
+        let doc = parse("p \"  two   spaces  \"");
+        let mut ctx = CompilationContext::new();
+        ctx.options.whitespace = crate::Whitespace::Trim;
+        assert_eq!(generate(&doc, &mut ctx).unwrap(), "<p>two   spaces</p>\n");
+    }
+
     #[test]
     fn test_implicit_div() {
         let (html, _) = gen(".container");
@@ -400,6 +1216,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_text_after_child_element() {
+        let (html, _) = gen("div\n  span \"a\"\n  \"tail text\"");
+        assert_eq!(html, "<div><span>a</span>tail text</div>\n");
+    }
+
+    #[test]
+    fn test_text_between_child_elements() {
+        let (html, _) = gen("p\n  \"Read the \"\n  a href=\"/docs\" \"docs\"\n  \" first.\"");
+        assert_eq!(html, "<p>Read the <a href=\"/docs\">docs</a> first.</p>\n");
+    }
+
+    #[test]
+    fn test_interpolated_text_beside_child_element_gets_own_span() {
+        let (html, ctx) = gen("state\n  count: 0\n\ndiv\n  span \"a\"\n  \"n={count}\"");
+        assert_eq!(
+            html,
+            "<div><span>a</span><span id=\"hrml-0\"></span></div>\n"
+        );
+        assert_eq!(ctx.bindings.len(), 1);
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Text { id, template } if id == "hrml-0" && template == "n=${_s.count}"
+        ));
+    }
+
     #[test]
     fn test_multiple_children() {
         let (html, _) = gen("div\n  span \"A\"\n  span \"B\"");
@@ -409,6 +1251,126 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_namespaced_attribute_rendered_literally() {
+        let (html, ctx) = gen("svg xmlns:xlink=\"http://www.w3.org/1999/xlink\"\n  use xlink:href=\"#icon\"");
+        assert_eq!(
+            html,
+            "<svg xmlns:xlink=\"http://www.w3.org/1999/xlink\">\n  <use xlink:href=\"#icon\"></use>\n</svg>\n"
+        );
+        assert!(ctx.bindings.is_empty());
+    }
+
+    #[test]
+    fn test_global_attributes_rendered() {
+        let (html, ctx) = gen(
+            "div role=button tabindex=-1 title=\"Save (Ctrl+S)\" lang=en dir=rtl style=\"color:red\"",
+        );
+        assert_eq!(
+            html,
+            "<div role=\"button\" tabindex=\"-1\" title=\"Save (Ctrl+S)\" lang=\"en\" dir=\"rtl\" style=\"color:red\"></div>\n"
+        );
+        assert!(ctx.bindings.is_empty());
+    }
+
+    // =========================================================================
+    // Raw blocks
+    // =========================================================================
+
+    #[test]
+    fn test_raw_block_top_level() {
+        let (html, _) = gen("raw\n  <svg viewBox=\"0 0 24 24\">\n    <path d=\"M12 2L2 22h20z\"/>\n  </svg>");
+        assert_eq!(
+            html,
+            "<svg viewBox=\"0 0 24 24\">\n  <path d=\"M12 2L2 22h20z\"/>\n</svg>\n"
+        );
+    }
+
+    #[test]
+    fn test_raw_block_inside_element() {
+        let (html, _) = gen("button .icon\n  raw\n    <svg><use href=\"#x\"/></svg>");
+        assert_eq!(
+            html,
+            "<button class=\"icon\" type=\"button\">\n<svg><use href=\"#x\"/></svg>\n</button>\n"
+        );
+    }
+
+    #[test]
+    fn test_raw_block_not_interpolated() {
+        let (html, ctx) = gen("state\n  x: 1\n\nraw\n  <b>{x}</b>");
+        assert_eq!(html, "<b>{x}</b>\n");
+        assert!(ctx.bindings.is_empty());
+    }
+
+    #[test]
+    fn test_raw_block_keeps_comments_doctype_and_whitespace() {
+        let (html, _) = gen("raw\n  <!DOCTYPE html>\n  <!-- keep {me} -->\n  <p>a < b {c}</p>\n     <pre>  x</pre>  ");
+        assert_eq!(
+            html,
+            "<!DOCTYPE html>\n<!-- keep {me} -->\n<p>a < b {c}</p>\n   <pre>  x</pre>  \n"
+        );
+    }
+
+    #[test]
+    fn test_minify_drops_indentation_and_newlines() {
+        let doc = parse("state\n  items: null\n\nul\n  li :for=\"item in items\" \"{item}\"\n  li\n    raw \"<hr>\"");
+        let mut ctx = CompilationContext::new();
+        ctx.options.minify = true;
+        let html = generate(&doc, &mut ctx).unwrap();
+        assert_eq!(
+            html,
+            "<ul><template id=\"hrml-0\"><li data-hrml=\"hrml-1\"></li></template><li><hr></li></ul>"
+        );
+    }
+
+    #[test]
+    fn test_minify_keeps_space_between_inline_siblings() {
+        let doc =
+            parse("p\n  b \"a\"\n  i \"b\"\n  div \"c\"\n  span \"d\"\nul\n  li \"x\"\n  li \"y\"");
+        let mut ctx = CompilationContext::new();
+        ctx.options.minify = true;
+        let html = generate(&doc, &mut ctx).unwrap();
+        assert_eq!(
+            html,
+            "<p><b>a</b> <i>b</i><div>c</div><span>d</span></p><ul><li>x</li><li>y</li></ul>"
+        );
+    }
+
+    // =========================================================================
+    // Fragments
+    // =========================================================================
+
+    #[test]
+    fn test_fragment_has_no_wrapper() {
+        let (html, _) = gen("div\n  fragment\n    h1 \"A\"\n    p \"B\"");
+        assert_eq!(html, "<div>\n  <h1>A</h1>\n  <p>B</p>\n</div>\n");
+    }
+
+    #[test]
+    fn test_conditional_fragment_binds_each_child() {
+        let (html, ctx) = gen("state\n  show: true\n\nfragment :if=\"show\"\n  h1 \"A\"\n  p \"B\"");
+        assert_eq!(html, "<h1 id=\"hrml-0\">A</h1>\n<p id=\"hrml-1\">B</p>\n");
+        assert_eq!(ctx.bindings.len(), 2);
+        for (binding, expected) in ctx.bindings.iter().zip(["hrml-0", "hrml-1"]) {
+            assert!(matches!(
+                binding,
+                Binding::Show { id, expr } if id == expected && expr == "_s.show"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_fragment_condition_combines_with_child_condition() {
+        let (_, ctx) = gen(
+            "state\n  a: true\n  b: true\n\nfragment :if=\"a\"\n  p :show=\"b\" \"B\"",
+        );
+        assert_eq!(ctx.bindings.len(), 1);
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Show { expr, .. } if expr == "(_s.b) && (_s.a)"
+        ));
+    }
+
     // =========================================================================
     // Reactive elements (ID assignment)
     // =========================================================================
@@ -416,7 +1378,7 @@ mod tests {
     #[test]
     fn test_event_handler_gets_id() {
         let (html, ctx) = gen("state\n  count: 0\n\nbutton @click=\"count++\" \"Click\"");
-        assert!(html.contains("<button id=\"hrml-0\">Click</button>"));
+        assert!(html.contains("<button id=\"hrml-0\" type=\"button\">Click</button>"));
         assert_eq!(ctx.bindings.len(), 1);
     }
 
@@ -427,6 +1389,18 @@ mod tests {
         assert_eq!(ctx.bindings.len(), 1);
     }
 
+    #[test]
+    fn test_show_hidden_toggles_hidden_property() {
+        let (html, ctx) = gen("state\n  visible: true\n\ndiv :show.hidden=\"visible\" \"Content\"");
+        assert!(html.contains("<div id=\"hrml-0\">Content</div>"));
+        assert_eq!(ctx.bindings.len(), 1);
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Prop { id, prop, expr }
+                if id == "hrml-0" && prop == "hidden" && expr == "!(_s.visible)"
+        ));
+    }
+
     #[test]
     fn test_model_directive_gets_id() {
         let (html, ctx) = gen("state\n  name: \"\"\n\ninput :model=\"name\"");
@@ -441,12 +1415,225 @@ mod tests {
         assert_eq!(ctx.bindings.len(), 1);
     }
 
+    #[test]
+    fn test_bare_interpolation_child() {
+        let (html, ctx) = gen("state\n  count: 0\n\ndiv\n  {count}");
+        assert_eq!(html, "<div id=\"hrml-0\"></div>\n");
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Text { id, template } if id == "hrml-0" && template == "${_s.count}"
+        ));
+    }
+
+    #[test]
+    fn test_attribute_spread_binding() {
+        let (html, ctx) = gen("state\n  attrs: {}\n\ndiv {...attrs}");
+        assert_eq!(html, "<div id=\"hrml-0\"></div>\n");
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Spread { id, expr } if id == "hrml-0" && expr == "_s.attrs"
+        ));
+        let js = crate::js::generate(&ctx).unwrap();
+        assert!(js.contains("hrml.spread('hrml-0', () => _s.attrs);"));
+    }
+
+    #[test]
+    fn test_text_children_concatenate_in_order() {
+        let (html, ctx) = gen("state\n  name: ''\n\np \"Hello, \"\n  {name}\n  \"! Bye, \"\n  \"{name}.\"");
+        assert_eq!(html, "<p id=\"hrml-0\"></p>\n");
+        assert_eq!(ctx.bindings.len(), 1);
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Text { id, template }
+                if id == "hrml-0" && template == "Hello, ${_s.name}! Bye, ${_s.name}."
+        ));
+    }
+
+    #[test]
+    fn test_and_interpolation_renders_empty_when_falsy() {
+        let (_, ctx) = gen("state\n  ok: false\n\nspan \"{ok && 'yes'}\"");
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Text { template, .. } if template == "${_s.ok ? 'yes' : ''}"
+        ));
+    }
+
+    #[test]
+    fn test_or_interpolation_unchanged() {
+        let (_, ctx) = gen("state\n  name: ''\n\nspan \"{name || 'anonymous'}\"");
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Text { template, .. } if template == "${_s.name || 'anonymous'}"
+        ));
+    }
+
+    #[test]
+    fn test_interpolated_attribute_binding() {
+        let (html, ctx) = gen("state\n  id: 7\n\na href=\"/user/{id}\" title=\"Profile\" \"Profile\"");
+        assert_eq!(html, "<a id=\"hrml-0\" title=\"Profile\">Profile</a>\n");
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Attr { id, name, template }
+                if id == "hrml-0" && name == "href" && template == "/user/${_s.id}"
+        ));
+    }
+
+    #[test]
+    fn test_interpolated_class_keeps_shorthand_classes() {
+        let (html, ctx) = gen("state\n  variant: \"primary\"\n\ndiv .card .shadow class=\"{variant}\"");
+        assert_eq!(html, "<div id=\"hrml-0\" class=\"card shadow\"></div>\n");
+        assert!(matches!(
+            &ctx.bindings[0],
+            Binding::Attr { id, name, template }
+                if id == "hrml-0" && name == "class" && template == "card shadow ${_s.variant}"
+        ));
+    }
+
+    #[test]
+    fn test_static_attribute_stays_static() {
+        let (html, ctx) = gen("a href=\"/about\" \"About\"");
+        assert_eq!(html, "<a href=\"/about\">About</a>\n");
+        assert!(ctx.bindings.is_empty());
+    }
+
     #[test]
     fn test_static_element_no_id() {
         let (html, _) = gen("div .container\n  span \"Hello\"");
         assert!(!html.contains("id="));
     }
 
+    // =========================================================================
+    // Binding target lints
+    // =========================================================================
+
+    #[test]
+    fn test_model_on_non_form_control_warns() {
+        let (_, ctx) = gen("state\n  x: ''\n\ndiv :model=\"x\"\ninput :model=\"x\"\ntext-field :model=\"x\"");
+        assert_eq!(
+            ctx.warnings,
+            vec![":model on <div> has no effect; use it on <input>, <select>, or <textarea>"]
+        );
+    }
+
+    #[test]
+    fn test_submit_without_form_warns() {
+        let (_, ctx) =
+            gen("span @submit=\"y()\"\nform @submit.prevent=\"y()\"\ndiv @reset=\"y()\"\n  form");
+        assert_eq!(
+            ctx.warnings,
+            vec!["@submit on <span> never fires; bind it on a <form>"]
+        );
+    }
+
+    // =========================================================================
+    // Lists
+    // =========================================================================
+
+    #[test]
+    fn test_for_renders_template() {
+        let (html, _) = gen("state\n  todos: null\n\nul\n  li :for=\"t in todos\" \"{t.title}\"");
+        assert_eq!(
+            html,
+            "<ul>\n  <template id=\"hrml-0\">\n    <li data-hrml=\"hrml-1\"></li>\n  </template>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_for_with_key_binding() {
+        let (_, ctx) = gen(
+            "state\n  todos: null\n\nul\n  li :for=\"t in todos\" :key=\"t.id\" @click=\"t.done = true\" \"{t.title}\"",
+        );
+        assert_eq!(ctx.bindings.len(), 1);
+        let Binding::List {
+            id,
+            item,
+            index,
+            items,
+            key,
+            bindings,
+        } = &ctx.bindings[0]
+        else {
+            panic!("expected a list binding");
+        };
+        assert_eq!(id, "hrml-0");
+        assert_eq!(item, "t");
+        assert_eq!(*index, None);
+        assert_eq!(items, "_s.todos");
+        assert_eq!(key.as_deref(), Some("t.id"));
+        assert_eq!(bindings.len(), 2);
+        assert!(matches!(
+            &bindings[0],
+            Binding::Event { id, handler, .. } if id == "hrml-1" && handler == "t.done = true"
+        ));
+        assert!(matches!(
+            &bindings[1],
+            Binding::Text { template, .. } if template == "${t.title}"
+        ));
+    }
+
+    #[test]
+    fn test_for_item_shadows_state() {
+        let (_, ctx) = gen("state\n  item: 1\n  items: null\n\nli :for=\"item in items\" \"{item}\"");
+        let Binding::List { bindings, .. } = &ctx.bindings[0] else {
+            panic!("expected a list binding");
+        };
+        assert!(matches!(
+            &bindings[0],
+            Binding::Text { template, .. } if template == "${item}"
+        ));
+    }
+
+    #[test]
+    fn test_for_index_shadows_state() {
+        let (_, ctx) =
+            gen("state\n  i: 0\n  items: null\n\nli :for=\"(item, i) in items\" \"{i}: {item}\"");
+        let Binding::List {
+            index, bindings, ..
+        } = &ctx.bindings[0]
+        else {
+            panic!("expected a list binding");
+        };
+        assert_eq!(index.as_deref(), Some("i"));
+        assert!(matches!(
+            &bindings[0],
+            Binding::Text { template, .. } if template == "${i}: ${item}"
+        ));
+    }
+
+    #[test]
+    fn test_nested_for_resolves_innermost_item() {
+        let (html, ctx) = gen(
+            "state\n  grid: null\n  cell: 0\n\ndiv :for=\"row in grid\"\n  span :for=\"cell in row.cells\" \"{row.name}: {cell}\"",
+        );
+        assert_eq!(
+            html,
+            "<template id=\"hrml-0\">\n  <div>\n    <template data-hrml=\"hrml-1\">\n      <span data-hrml=\"hrml-2\"></span>\n    </template>\n  </div>\n</template>\n"
+        );
+        let Binding::List {
+            items, bindings, ..
+        } = &ctx.bindings[0]
+        else {
+            panic!("expected a list binding");
+        };
+        assert_eq!(items, "_s.grid");
+        let Binding::List {
+            item,
+            items,
+            bindings,
+            ..
+        } = &bindings[0]
+        else {
+            panic!("expected a nested list binding");
+        };
+        assert_eq!(item, "cell");
+        assert_eq!(items, "row.cells");
+        assert!(matches!(
+            &bindings[0],
+            Binding::Text { template, .. } if template == "${row.name}: ${cell}"
+        ));
+        assert!(ctx.warnings.is_empty());
+    }
+
     // =========================================================================
     // Text interpolation
     // =========================================================================
@@ -457,6 +1644,12 @@ mod tests {
         assert_eq!(result, "${_s.count}");
     }
 
+    #[test]
+    fn test_interpolate_brace_in_string() {
+        let result = interpolate_text("[{label('}')}]", &["label".into()]);
+        assert_eq!(result, "[${_s.label('}')}]");
+    }
+
     #[test]
     fn test_interpolate_with_text() {
         let result = interpolate_text("Hello {name}!", &["name".into()]);
@@ -476,6 +1669,24 @@ mod tests {
         assert_eq!(result, "${foo}");
     }
 
+    #[test]
+    fn test_interpolate_concat() {
+        let result = interpolate_concat("Count: {count}", &["count".into()]);
+        assert_eq!(result, "'Count: ' + (_s.count)");
+    }
+
+    #[test]
+    fn test_interpolate_concat_expression_only() {
+        let result = interpolate_concat("{first} {last}", &["first".into(), "last".into()]);
+        assert_eq!(result, "'' + (_s.first) + ' ' + (_s.last)");
+    }
+
+    #[test]
+    fn test_interpolate_concat_quotes_apostrophes() {
+        let result = interpolate_concat("It's {n}", &["n".into()]);
+        assert_eq!(result, "\"It's \" + (_s.n)");
+    }
+
     // =========================================================================
     // Full examples
     // =========================================================================
@@ -486,9 +1697,9 @@ mod tests {
             "state\n  count: 0\n\ndiv .counter\n  button @click=\"count++\" \"-\"\n  span \"{count}\"\n  button @click=\"count--\" \"+\"",
         );
         assert!(html.contains("<div class=\"counter\">"));
-        assert!(html.contains("<button id=\"hrml-0\">-</button>"));
+        assert!(html.contains("<button id=\"hrml-0\" type=\"button\">-</button>"));
         assert!(html.contains("<span id=\"hrml-1\"></span>"));
-        assert!(html.contains("<button id=\"hrml-2\">+</button>"));
+        assert!(html.contains("<button id=\"hrml-2\" type=\"button\">+</button>"));
         assert!(html.contains("</div>"));
     }
 
@@ -497,7 +1708,7 @@ mod tests {
         let (html, _) = gen(
             "state\n  visible: true\n\nbutton @click=\"visible = !visible\" \"Toggle\"\ndiv :show=\"visible\" \"Content\"",
         );
-        assert!(html.contains("<button id=\"hrml-0\">Toggle</button>"));
+        assert!(html.contains("<button id=\"hrml-0\" type=\"button\">Toggle</button>"));
         assert!(html.contains("<div id=\"hrml-1\">Content</div>"));
     }
 
@@ -1,81 +1,366 @@
 //! JavaScript code generator.
 //!
 //! Generates reactive JavaScript from the compilation context.
-//! Includes the HRML runtime (~50 lines) and compiled bindings.
+//! Includes the HRML runtime (~150 lines) and compiled bindings.
 //! No eval(), no new Function() — fully CSP-safe.
 
-use crate::{Binding, CodegenError, CompilationContext};
+use crate::{Binding, CodegenError, CodegenOptions, CompilationContext, JsTarget};
+use hrml_parser::ast::Modifier;
 
 /// The HRML reactive runtime.
-/// Proxy-based state with batched effects via queueMicrotask.
-const RUNTIME: &str = r#"const hrml = (() => {
-  const _effects = [];
+/// Proxy-based state with batched effects via queueMicrotask. Lists iterate
+/// arrays in order, plain objects as (value, key), and a number n as 0..n-1.
+/// A keyed row is kept while its item stays the same, unless its render takes
+/// the index and the row has moved.
+pub(crate) const RUNTIME: &str = r#"const hrml = (() => {
+  const _effects = new Set();
   let _queued = false;
+  let _batching = 0;
+  let _pending = false;
+  let _owner = null;
+  let _version = 0;
   function _flush() {
     _queued = false;
-    _effects.forEach(fn => fn());
+    [..._effects].forEach(fn => { if (_effects.has(fn)) fn(); });
   }
   function _notify() {
+    if (_batching > 0) {
+      _pending = true;
+      return;
+    }
     if (!_queued) {
       _queued = true;
       queueMicrotask(_flush);
     }
   }
+  function batch(fn) {
+    _batching++;
+    try {
+      return fn();
+    } finally {
+      if (--_batching === 0 && _pending) {
+        _pending = false;
+        _notify();
+      }
+    }
+  }
   function state(init) {
     return new Proxy(init, {
       set(target, key, value) {
+        const desc = Object.getOwnPropertyDescriptor(target, key);
+        if (desc && desc.get) {
+          console.warn(`hrml: '${String(key)}' is computed and read-only`);
+          return true;
+        }
         if (target[key] === value) return true;
         target[key] = value;
+        _version++;
         _notify();
         return true;
       }
     });
   }
-  function effect(fn) { _effects.push(fn); fn(); }
+  function effect(fn) {
+    _effects.add(fn);
+    if (_owner) _owner.push(() => _effects.delete(fn));
+    fn();
+  }
+  function computed(fn) {
+    let seen = -1, value;
+    return () => {
+      if (seen !== _version) {
+        value = fn();
+        seen = _version;
+      }
+      return value;
+    };
+  }
+  let _root = document;
+  function _el(id) {
+    if (_root.getElementById) return _root.getElementById(id);
+    const sel = `[data-hrml="${id}"]`;
+    return _root.matches(sel) ? _root : _root.querySelector(sel);
+  }
+  function scope(root, fn) {
+    const prev = _root;
+    _root = root;
+    try {
+      fn();
+    } finally {
+      _root = prev;
+    }
+  }
   function text(id, fn) {
-    effect(() => {
-      const el = document.getElementById(id);
-      if (el) el.textContent = fn();
+    const els = [].concat(id).map(_el).filter(Boolean);
+    if (els.length) effect(() => {
+      const value = fn();
+      els.forEach(el => { el.textContent = value; });
     });
   }
   function show(id, fn) {
+    const el = _el(id);
+    if (el) effect(() => { el.style.display = fn() ? '' : 'none'; });
+  }
+  function keep(id, fn) {
+    const el = _el(id);
+    if (!el) return;
+    const mark = document.createComment('');
+    effect(() => {
+      if (fn()) {
+        if (mark.parentNode) mark.replaceWith(el);
+      } else if (el.parentNode) {
+        el.replaceWith(mark);
+      }
+    });
+  }
+  function prop(id, name, fn) {
+    const el = _el(id);
+    if (el) effect(() => { el[name] = fn(); });
+  }
+  function toggle(id, name, fn) {
+    const el = _el(id);
+    if (el) effect(() => { el.classList.toggle(name, !!fn()); });
+  }
+  function classes(id, fn) {
+    const el = _el(id);
+    if (!el) return;
+    let prev = [];
+    effect(() => {
+      const value = fn();
+      const next = typeof value === 'string' ? value.split(/\s+/).filter(Boolean)
+        : Array.isArray(value) ? value.filter(Boolean)
+        : Object.keys(value || {}).filter(k => value[k]);
+      prev.forEach(c => { if (!next.includes(c)) el.classList.remove(c); });
+      next.forEach(c => el.classList.add(c));
+      prev = next;
+    });
+  }
+  function html(id, fn) {
+    const el = _el(id);
+    if (el) effect(() => { el.innerHTML = fn(); });
+  }
+  function attr(id, name, fn) {
+    const el = _el(id);
+    if (el) effect(() => { el.setAttribute(name, fn()); });
+  }
+  function spread(id, fn) {
+    const el = _el(id);
+    if (!el) return;
+    let prev = {};
     effect(() => {
-      const el = document.getElementById(id);
-      if (el) el.style.display = fn() ? '' : 'none';
+      const next = fn() || {};
+      Object.keys(prev).forEach(k => { if (!(k in next)) el.removeAttribute(k); });
+      Object.entries(next).forEach(([k, v]) => {
+        if (k in el) el[k] = v;
+        else if (v === false || v == null) el.removeAttribute(k);
+        else el.setAttribute(k, v === true ? '' : v);
+      });
+      prev = next;
     });
   }
   function model(id, s, key) {
-    const el = document.getElementById(id);
+    const el = _el(id);
     if (!el) return;
     el.addEventListener('input', e => { s[key] = e.target.value; });
     effect(() => { if (el.value !== String(s[key])) el.value = s[key]; });
   }
   function on(id, event, handler) {
-    const el = document.getElementById(id);
-    if (el) el.addEventListener(event, handler);
+    const el = _el(id);
+    if (el) el.addEventListener(event, e => handler(e, el));
   }
-  return { state, effect, text, show, model, on };
+  function debounce(fn, ms) {
+    let timer;
+    return function (...args) {
+      clearTimeout(timer);
+      timer = setTimeout(() => fn.apply(this, args), ms);
+    };
+  }
+  function throttle(fn, ms) {
+    let last = -Infinity;
+    return function (...args) {
+      const now = Date.now();
+      if (now - last < ms) return;
+      last = now;
+      return fn.apply(this, args);
+    };
+  }
+  function _row(tpl, item, i, render) {
+    const row = { item, i, node: tpl.content.firstElementChild.cloneNode(true), fx: [] };
+    const owner = _owner;
+    _owner = row.fx;
+    try {
+      scope(row.node, () => render(item, i));
+    } finally {
+      _owner = owner;
+    }
+    return row;
+  }
+  function _drop(row) {
+    row.node.remove();
+    row.fx.forEach(dispose => dispose());
+  }
+  function _entries(items) {
+    if (typeof items === 'number') return Array.from({ length: items }, (_, i) => [i, i]);
+    if (items && typeof items === 'object' && !(Symbol.iterator in items)) {
+      return Object.entries(items);
+    }
+    return Array.from(items || [], (item, i) => [i, item]);
+  }
+  function list(id, items, key, render) {
+    const tpl = _el(id);
+    if (!tpl) return;
+    let rows = new Map();
+    if (_owner) _owner.push(() => rows.forEach(_drop));
+    effect(() => {
+      const next = new Map();
+      let prev = tpl;
+      _entries(items()).forEach(([i, item]) => {
+        const k = key ? key(item, i) : i;
+        let row = rows.get(k);
+        rows.delete(k);
+        if (row && (row.item !== item || (render.length > 1 && row.i !== i))) {
+          _drop(row);
+          row = null;
+        }
+        if (!row) row = _row(tpl, item, i, render);
+        next.set(k, row);
+        if (prev.nextSibling !== row.node) prev.after(row.node);
+        prev = row.node;
+      });
+      rows.forEach(_drop);
+      rows = next;
+    });
+  }
+  const filters = {
+    upper: v => String(v).toUpperCase(),
+    lower: v => String(v).toLowerCase(),
+    round: v => Math.round(v),
+    json: v => JSON.stringify(v),
+  };
+  return { state, effect, computed, batch, scope, text, show, keep, prop, html, attr, spread, model, on, list, toggle, classes, filters, debounce, throttle };
 })();"#;
 
 /// Generate JavaScript from the compilation context.
 pub fn generate(ctx: &CompilationContext) -> Result<String, CodegenError> {
-    // No state, no bindings → no JS needed
-    if ctx.state_fields.is_empty() && ctx.bindings.is_empty() {
+    // No state, bindings, functions, or effects → no JS needed
+    if ctx.state_fields.is_empty()
+        && ctx.bindings.is_empty()
+        && ctx.functions.is_empty()
+        && ctx.effects.is_empty()
+    {
         return Ok(String::new());
     }
 
-    let mut js = String::new();
+    let mut js = banner(&ctx.options);
 
     // Runtime
-    js.push_str(RUNTIME);
-    js.push_str("\n\n");
+    if !ctx.options.no_runtime {
+        js.push_str(&runtime_source(&ctx.options));
+    }
 
     // User code in IIFE
     js.push_str("(function() {\n");
+    js.push_str(&user_code(ctx));
+    js.push_str("})();\n");
+
+    Ok(js)
+}
+
+/// Generate JavaScript that defines a custom element rendering `html` into
+/// its shadow root, with all bindings scoped to that root. Page styles don't
+/// reach into a shadow root, so `css` goes into a `<style>` inside it.
+pub fn generate_custom_element(
+    ctx: &CompilationContext,
+    tag: &str,
+    html: &str,
+    css: &str,
+) -> Result<String, CodegenError> {
+    let mut js = banner(&ctx.options);
+
+    if !ctx.options.no_runtime {
+        js.push_str(&runtime_source(&ctx.options));
+    }
+
+    let (i1, i2) = if ctx.options.minify {
+        ("", "")
+    } else {
+        ("  ", "    ")
+    };
+    let markup = if css.trim().is_empty() {
+        crate::js_string(html.trim_end())
+    } else {
+        crate::js_string(&format!("<style>{}</style>{}", css.trim(), html.trim_end()))
+    };
+    js.push_str(&format!(
+        "customElements.define('{tag}', class extends HTMLElement {{\n"
+    ));
+    js.push_str(&format!("{i1}connectedCallback() {{\n"));
+    js.push_str(&format!("{i2}if (this.shadowRoot) return;\n"));
+    js.push_str(&format!("{i2}const root = this.attachShadow({{ mode: 'open' }});\n"));
+    js.push_str(&format!("{i2}root.innerHTML = {markup};\n"));
+    js.push_str(&format!("{i2}hrml.scope(root, () => {{\n"));
+    for line in user_code(ctx).lines() {
+        js.push_str(i2);
+        js.push_str(line);
+        js.push('\n');
+    }
+    js.push_str(&format!("{i2}}});\n"));
+    js.push_str(&format!("{i1}}}\n"));
+    js.push_str("});\n");
+
+    Ok(js)
+}
+
+/// The comment naming the compiler version that produced the output.
+fn banner(options: &CodegenOptions) -> String {
+    if options.no_banner || !options.comments() {
+        String::new()
+    } else {
+        format!("/* hrml v{} */\n", env!("CARGO_PKG_VERSION"))
+    }
+}
+
+/// The runtime followed by a blank line; with `minify`, without indentation.
+fn runtime_source(options: &CodegenOptions) -> String {
+    if options.minify {
+        let lines: Vec<&str> = RUNTIME.lines().map(str::trim).collect();
+        format!("{}\n", lines.join("\n"))
+    } else {
+        format!("{RUNTIME}\n\n")
+    }
+}
+
+/// One level of indentation in emitted JS; none when minifying.
+fn indent_step(options: &CodegenOptions) -> &'static str {
+    if options.minify {
+        ""
+    } else {
+        "  "
+    }
+}
+
+/// State setup, bindings, and computeds — one statement per line, indented
+/// for the enclosing function body.
+fn user_code(ctx: &CompilationContext) -> String {
+    let mut js = String::new();
+
+    let es5 = ctx.options.target == JsTarget::Es5;
+    let indent = indent_step(&ctx.options);
+
+    // Constants, which the state and everything after it may read
+    for (name, value) in &ctx.const_fields {
+        let keyword = if es5 { "var" } else { "const" };
+        js.push_str(&format!("{indent}{keyword} {name} = {value};\n"));
+    }
 
     // State initialization
-    if !ctx.state_fields.is_empty() {
-        js.push_str("  const _s = hrml.state({ ");
+    if !ctx.state_fields.is_empty() || !ctx.computed_fields.is_empty() {
+        js.push_str(indent);
+        js.push_str(if es5 {
+            "var _s = hrml.state({ "
+        } else {
+            "const _s = hrml.state({ "
+        });
         let fields: Vec<String> = ctx
             .state_fields
             .iter()
@@ -85,49 +370,383 @@ pub fn generate(ctx: &CompilationContext) -> Result<String, CodegenError> {
         js.push_str(" });\n");
     }
 
+    // Computed fields as read-only getters on the state, memoized until the
+    // next state change; defined before the bindings that read them
+    for (name, expr) in &ctx.computed_fields {
+        let getter = if es5 {
+            format!("hrml.computed(function () {{ return {expr}; }})")
+        } else {
+            format!("hrml.computed(() => {expr})")
+        };
+        js.push_str(&format!(
+            "{indent}Object.defineProperty(_s, '{name}', {{ get: {getter}, configurable: true }});\n"
+        ));
+    }
+
+    // Functions are hoisted, so handlers and computeds may call them
+    for function in &ctx.functions {
+        js.push_str(&format!("{indent}{function}\n"));
+    }
+
     // Bindings
-    for binding in &ctx.bindings {
-        match binding {
-            Binding::Text { id, template } => {
-                js.push_str(&format!(
-                    "  hrml.text('{id}', () => `{template}`);\n"
-                ));
+    push_bindings(&mut js, &ctx.bindings, &ctx.options, indent);
+
+    // Effects re-run whenever state changes, like bindings
+    for body in &ctx.effects {
+        if es5 {
+            js.push_str(&format!("{indent}hrml.effect(function () {{ {body}}});\n"));
+        } else {
+            js.push_str(&format!("{indent}hrml.effect(() => {{ {body}}});\n"));
+        }
+    }
+
+    js
+}
+
+/// Bindings grouped by kind — events, text, attributes, show, properties,
+/// classes, html, model, lists, keepalive —
+/// keeping collection order within each group. Each binding targets its own
+/// element, so apart from keepalive coming last the order doesn't affect
+/// behavior; grouping keeps diffs stable.
+fn grouped(bindings: &[Binding]) -> Vec<&Binding> {
+    let mut ordered: Vec<&Binding> = bindings.iter().collect();
+    ordered.sort_by_key(|binding| match binding {
+        Binding::Event { .. } => 0,
+        Binding::Text { .. } => 1,
+        Binding::Attr { .. } | Binding::Spread { .. } => 2,
+        Binding::Show { .. } => 3,
+        Binding::Prop { .. } => 4,
+        Binding::Class { .. } | Binding::Classes { .. } => 5,
+        Binding::Html { .. } => 6,
+        Binding::Model { .. } => 7,
+        Binding::List { .. } => 8,
+        // Last, so the other bindings find their elements before any is detached
+        Binding::Keep { .. } => 9,
+    });
+    ordered
+}
+
+/// Modifiers that change how a listener runs rather than which events it
+/// accepts.
+const EVENT_OPTIONS: &[&str] = &[
+    "prevent", "stop", "once", "self", "capture", "passive", "debounce", "throttle",
+];
+
+/// Delay for `.debounce` and `.throttle` without milliseconds.
+const DEFAULT_DELAY_MS: u64 = 250;
+
+/// A `.debounce` or `.throttle` modifier and its delay in milliseconds,
+/// given by its argument (`.debounce.300`).
+fn timing(modifiers: &[Modifier]) -> Option<(&str, u64)> {
+    let modifier = modifiers
+        .iter()
+        .find(|m| matches!(m.name.as_str(), "debounce" | "throttle"))?;
+    let ms = modifier
+        .arg
+        .as_deref()
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_DELAY_MS);
+    Some((&modifier.name, ms))
+}
+
+/// The condition an event must meet for its handler to run: held modifier
+/// keys (`.ctrl`, `.shift`, `.alt`, `.meta`) and, on keyboard events, the key
+/// itself (`@keydown.ctrl.s`, `@keydown.page-down`).
+fn event_guard(event: &str, modifiers: &[Modifier]) -> Option<String> {
+    let is_key_event = matches!(event, "keydown" | "keyup" | "keypress");
+    let mut checks = Vec::new();
+    for modifier in modifiers {
+        match modifier.name.as_str() {
+            name @ ("ctrl" | "shift" | "alt" | "meta") => checks.push(format!("$event.{name}Key")),
+            name if EVENT_OPTIONS.contains(&name) || !is_key_event => {}
+            // A letter or digit, whatever the case (Shift turns `s` into `S`)
+            name if name.chars().count() == 1 => {
+                checks.push(format!("$event.key.toLowerCase() === '{name}'"));
+            }
+            name => checks.push(format!("$event.key === '{}'", key_name(name))),
+        }
+    }
+    (!checks.is_empty()).then(|| checks.join(" && "))
+}
+
+/// The `KeyboardEvent.key` value for a key modifier: a few short aliases,
+/// otherwise each hyphenated part capitalized (`page-down` → `PageDown`).
+fn key_name(modifier: &str) -> String {
+    match modifier {
+        "esc" => "Escape".to_string(),
+        "space" => " ".to_string(),
+        "up" | "down" | "left" | "right" => format!("Arrow{}", capitalize(modifier)),
+        _ => modifier.split('-').map(capitalize).collect(),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Emit bindings in [`grouped`] order. With [`CodegenOptions::dedupe_text`],
+/// text bindings sharing a template become one `hrml.text([ids], ...)` call,
+/// and so one effect, in place of the first of them.
+fn push_bindings(js: &mut String, bindings: &[Binding], options: &CodegenOptions, indent: &str) {
+    let ordered = grouped(bindings);
+    let mut shared: Vec<&str> = Vec::new();
+    for binding in &ordered {
+        if let (Binding::Text { template, .. }, true) = (binding, options.dedupe_text) {
+            if shared.contains(&template.as_str()) {
+                continue;
+            }
+            let ids: Vec<String> = ordered
+                .iter()
+                .filter_map(|b| match b {
+                    Binding::Text { id, template: t } if t == template => Some(format!("'{id}'")),
+                    _ => None,
+                })
+                .collect();
+            if ids.len() > 1 {
+                shared.push(template);
+                let es5 = options.target == JsTarget::Es5;
+                if options.debug {
+                    if let Some(line) = debug_line(binding, es5) {
+                        js.push_str(&format!("{indent}{line}\n"));
+                    }
+                }
+                let target = format!("[{}]", ids.join(", "));
+                push_text(js, &target, template, es5, indent);
+                continue;
+            }
+        }
+        push_binding(js, binding, options, indent);
+    }
+}
+
+/// Emit one binding call at `indent`; list bindings recurse into their item body.
+fn push_binding(js: &mut String, binding: &Binding, options: &CodegenOptions, indent: &str) {
+    let es5 = options.target == JsTarget::Es5;
+    if options.debug {
+        if let Some(line) = debug_line(binding, es5) {
+            js.push_str(&format!("{indent}{line}\n"));
+        }
+    }
+    match binding {
+        Binding::Text { id, template } => push_text(js, &format!("'{id}'"), template, es5, indent),
+        Binding::Event {
+            id,
+            event,
+            handler,
+            listener,
+            is_async,
+            emits,
+            modifiers,
+        } => {
+            // Handlers always receive the DOM event as `$event`, and the
+            // element as `$el` when they emit
+            let params = if *emits { "$event, $el" } else { "$event" };
+            let call = if *listener {
+                format!("({handler})($event);")
+            } else {
+                format!("{handler};")
+            };
+            // Checks run on every event, even when the handler is delayed
+            let mut checks = String::new();
+            if let Some(guard) = event_guard(event, modifiers) {
+                checks.push_str(&format!("if (!({guard})) return; "));
             }
-            Binding::Event {
-                id,
-                event,
-                handler,
-                modifiers,
-            } => {
-                if modifiers.contains(&"prevent".to_string()) {
-                    js.push_str(&format!(
-                        "  hrml.on('{id}', '{event}', (e) => {{ e.preventDefault(); {handler}; }});\n"
-                    ));
+            if modifiers.iter().any(|m| m.name == "prevent") {
+                checks.push_str("$event.preventDefault(); ");
+            }
+            let keyword = if *is_async { "async " } else { "" };
+            let function = |body: &str| {
+                if es5 {
+                    format!("{keyword}function ({params}) {{ {body} }}")
                 } else {
-                    js.push_str(&format!(
-                        "  hrml.on('{id}', '{event}', () => {{ {handler}; }});\n"
-                    ));
+                    format!("{keyword}({params}) => {{ {body} }}")
+                }
+            };
+            let handler_fn = match timing(modifiers) {
+                Some((kind, ms)) => {
+                    let inner = if *listener && !emits {
+                        handler.clone()
+                    } else {
+                        function(&call)
+                    };
+                    let timed = format!("hrml.{kind}({inner}, {ms})");
+                    if checks.is_empty() {
+                        timed
+                    } else if es5 {
+                        format!("(function (run) {{ return function ({params}) {{ {checks}run({params}); }}; }})({timed})")
+                    } else {
+                        format!("((run) => ({params}) => {{ {checks}run({params}); }})({timed})")
+                    }
                 }
+                None if *listener && !emits && checks.is_empty() => handler.clone(),
+                None => function(&format!("{checks}{call}")),
+            };
+            js.push_str(&format!(
+                "{indent}hrml.on('{id}', '{event}', {handler_fn});\n"
+            ));
+        }
+        Binding::Show { id, expr } => {
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.show('{id}', function () {{ return {expr}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!("{indent}hrml.show('{id}', () => {expr});\n"));
+            }
+        }
+        Binding::Keep { id, expr } => {
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.keep('{id}', function () {{ return {expr}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!("{indent}hrml.keep('{id}', () => {expr});\n"));
+            }
+        }
+        Binding::Attr { id, name, template } => {
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.attr('{id}', '{name}', function () {{ return {template}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!(
+                    "{indent}hrml.attr('{id}', '{name}', () => `{template}`);\n"
+                ));
             }
-            Binding::Show { id, expr } => {
-                js.push_str(&format!("  hrml.show('{id}', () => {expr});\n"));
+        }
+        Binding::Spread { id, expr } => {
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.spread('{id}', function () {{ return {expr}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!("{indent}hrml.spread('{id}', () => {expr});\n"));
             }
-            Binding::Model { id, field } => {
-                js.push_str(&format!("  hrml.model('{id}', _s, '{field}');\n"));
+        }
+        Binding::Prop { id, prop, expr } => {
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.prop('{id}', '{prop}', function () {{ return {expr}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!(
+                    "{indent}hrml.prop('{id}', '{prop}', () => {expr});\n"
+                ));
             }
         }
+        Binding::Class { id, class, expr } => {
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.toggle('{id}', '{class}', function () {{ return {expr}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!(
+                    "{indent}hrml.toggle('{id}', '{class}', () => {expr});\n"
+                ));
+            }
+        }
+        Binding::Classes { id, expr } => {
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.classes('{id}', function () {{ return {expr}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!("{indent}hrml.classes('{id}', () => {expr});\n"));
+            }
+        }
+        Binding::Html { id, expr } => {
+            if options.comments() {
+                js.push_str(&format!(
+                    "{indent}// :html inserts markup unescaped; bind only trusted, sanitized HTML\n"
+                ));
+            }
+            if es5 {
+                js.push_str(&format!(
+                    "{indent}hrml.html('{id}', function () {{ return {expr}; }});\n"
+                ));
+            } else {
+                js.push_str(&format!("{indent}hrml.html('{id}', () => {expr});\n"));
+            }
+        }
+        Binding::Model { id, field } => {
+            js.push_str(&format!("{indent}hrml.model('{id}', _s, '{field}');\n"));
+        }
+        Binding::List {
+            id,
+            item,
+            index,
+            items,
+            key,
+            bindings,
+        } => {
+            // The runtime passes each item's position as the second argument
+            let params = match index {
+                Some(index) => format!("{item}, {index}"),
+                None => item.clone(),
+            };
+            let (items_fn, key_fn, render_fn) = if es5 {
+                (
+                    format!("function () {{ return {items}; }}"),
+                    key.as_ref()
+                        .map(|k| format!("function ({params}) {{ return {k}; }}")),
+                    format!("function ({params}) {{"),
+                )
+            } else {
+                (
+                    format!("() => {items}"),
+                    key.as_ref().map(|k| format!("({params}) => {k}")),
+                    format!("({params}) => {{"),
+                )
+            };
+            let key_fn = key_fn.unwrap_or_else(|| "null".to_string());
+            js.push_str(&format!(
+                "{indent}hrml.list('{id}', {items_fn}, {key_fn}, {render_fn}\n"
+            ));
+            let inner = format!("{indent}{}", indent_step(options));
+            push_bindings(js, bindings, options, &inner);
+            js.push_str(&format!("{indent}}});\n"));
+        }
     }
+}
 
-    // Computed fields as effects
-    for (name, expr) in &ctx.computed_fields {
+/// `hrml.text` for `target`, a quoted id or an array of them.
+fn push_text(js: &mut String, target: &str, template: &str, es5: bool, indent: &str) {
+    if es5 {
+        js.push_str(&format!(
+            "{indent}hrml.text({target}, function () {{ return {template}; }});\n"
+        ));
+    } else {
         js.push_str(&format!(
-            "  hrml.effect(() => {{ _s.{name} = {expr}; }});\n"
+            "{indent}hrml.text({target}, () => `{template}`);\n"
         ));
     }
+}
 
-    js.push_str("})();\n");
-
-    Ok(js)
+/// With [`crate::CodegenOptions::debug`], a standalone `console.debug` line
+/// that logs whenever the binding fires. Each sits on its own line so it can
+/// be stripped without touching the binding itself.
+fn debug_line(binding: &Binding, es5: bool) -> Option<String> {
+    let (call, args) = match binding {
+        Binding::Text { id, template } if es5 => (None, format!("'hrml: text {id}', {template}")),
+        Binding::Text { id, template } => (None, format!("'hrml: text {id}', `{template}`")),
+        Binding::Show { id, expr } => (None, format!("'hrml: show {id}', {expr}")),
+        Binding::Model { id, field } => (None, format!("'hrml: model {id}', _s.{field}")),
+        Binding::Event { id, event, .. } => (
+            Some(format!("hrml.on('{id}', '{event}', ")),
+            format!("'hrml: on {id} {event}', $event"),
+        ),
+        _ => return None,
+    };
+    Some(match (call, es5) {
+        (Some(on), true) => format!("{on}function ($event) {{ console.debug({args}); }});"),
+        (Some(on), false) => format!("{on}($event) => console.debug({args}));"),
+        (None, true) => format!("hrml.effect(function () {{ console.debug({args}); }});"),
+        (None, false) => format!("hrml.effect(() => console.debug({args}));"),
+    })
 }
 
 #[cfg(test)]
@@ -200,10 +819,13 @@ mod tests {
             id: "hrml-0".into(),
             event: "click".into(),
             handler: "_s.count++".into(),
+            listener: false,
+            is_async: false,
+            emits: false,
             modifiers: Vec::new(),
         });
         let js = generate(&ctx).unwrap();
-        assert!(js.contains("hrml.on('hrml-0', 'click', () => { _s.count++; })"));
+        assert!(js.contains("hrml.on('hrml-0', 'click', ($event) => { _s.count++; })"));
     }
 
     #[test]
@@ -214,13 +836,110 @@ mod tests {
             id: "hrml-0".into(),
             event: "submit".into(),
             handler: "save()".into(),
-            modifiers: vec!["prevent".into()],
+            listener: false,
+            is_async: false,
+            emits: false,
+            modifiers: vec![Modifier::new("prevent")],
         });
         let js = generate(&ctx).unwrap();
-        assert!(js.contains("e.preventDefault()"));
+        assert!(js.contains("$event.preventDefault()"));
         assert!(js.contains("save()"));
     }
 
+    #[test]
+    fn test_event_binding_with_key_combo() {
+        let mut ctx = CompilationContext::new();
+        ctx.bindings.push(Binding::Event {
+            id: "hrml-0".into(),
+            event: "keydown".into(),
+            handler: "save()".into(),
+            listener: false,
+            is_async: false,
+            emits: false,
+            modifiers: vec![
+                Modifier::new("ctrl"),
+                Modifier::new("s"),
+                Modifier::new("prevent"),
+            ],
+        });
+        let js = generate(&ctx).unwrap();
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'keydown', ($event) => { if (!($event.ctrlKey && $event.key.toLowerCase() === 's')) return; $event.preventDefault(); save(); })"
+        ));
+    }
+
+    #[test]
+    fn test_event_binding_with_debounce() {
+        let doc = parse("state\n  q: ''\n\ninput @input.debounce.300=\"search(q)\"");
+        let js = crate::compile(&doc).unwrap().js;
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'input', hrml.debounce(($event) => { search(_s.q); }, 300));"
+        ));
+    }
+
+    #[test]
+    fn test_event_binding_with_throttle_default_and_checks() {
+        let doc = parse("state\n  n: 0\n\ndiv @keydown.enter.throttle.prevent=\"n++\"");
+        let js = crate::compile(&doc).unwrap().js;
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'keydown', ((run) => ($event) => { if (!($event.key === 'Enter')) return; $event.preventDefault(); run($event); })(hrml.throttle(($event) => { _s.n++; }, 250)));"
+        ));
+    }
+
+    #[test]
+    fn test_event_binding_with_named_key() {
+        let doc =
+            parse("state\n  row: 0\n\ndiv @keydown.page-down=\"row++\" @keyup.esc=\"row = 0\"");
+        let js = crate::compile(&doc).unwrap().js;
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'keydown', ($event) => { if (!($event.key === 'PageDown')) return; _s.row++; })"
+        ));
+        assert!(js.contains("if (!($event.key === 'Escape')) return;"));
+    }
+
+    #[test]
+    fn test_modifier_key_on_click_and_listener() {
+        let doc = parse("state\n  n: 0\n\nli @click.shift=\"e => n++\" @click.enter=\"e => n--\"");
+        let js = crate::compile(&doc).unwrap().js;
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'click', ($event) => { if (!($event.shiftKey)) return; ((e) => _s.n++)($event); })"
+        ));
+        // Key names only apply to keyboard events
+        assert!(js.contains("hrml.on('hrml-0', 'click', (e) => _s.n--);"));
+    }
+
+    #[test]
+    fn test_emit_dispatches_custom_event() {
+        let doc = parse(
+            "state\n  v: 1\n\nbutton @click=\"emit('x', v)\" @focus.debounce=\"e => emit('seen')\"",
+        );
+        let js = crate::compile(&doc).unwrap().js;
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'click', ($event, $el) => { $el.dispatchEvent(new CustomEvent('x', { detail: _s.v, bubbles: true })); });"
+        ));
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'focus', hrml.debounce(($event, $el) => { ((e) => $el.dispatchEvent(new CustomEvent('seen', { bubbles: true })))($event); }, 250));"
+        ));
+        assert!(RUNTIME.contains("el.addEventListener(event, e => handler(e, el));"));
+    }
+
+    #[test]
+    fn test_emit_outside_event_handler_is_error() {
+        for (source, place) in [
+            ("p :show=\"emit('x')\" \"Hi\"", "'show' on <p>"),
+            ("p \"{emit('x')}\"", "text on <p>"),
+            ("a title=\"{emit('x')}\" \"Hi\"", "'title' on <a>"),
+            ("computed\n  c: emit('x')", "computed 'c'"),
+            ("effect\n  emit('x')", "an effect"),
+        ] {
+            let err = crate::compile(&parse(source)).unwrap_err();
+            assert_eq!(
+                err.message,
+                format!("`emit` can only be called in an event handler, not in {place}")
+            );
+        }
+    }
+
     #[test]
     fn test_show_binding() {
         let mut ctx = CompilationContext::new();
@@ -234,6 +953,193 @@ mod tests {
         assert!(js.contains("hrml.show('hrml-0', () => _s.visible)"));
     }
 
+    #[test]
+    fn test_if_keepalive_binding() {
+        let doc = parse(
+            "state\n  open: true\n  draft: ''\n\ndiv :if.keepalive=\"open\"\n  input :model=\"draft\"\np :if=\"open\" \"Shown\"",
+        );
+        let output = crate::compile(&doc).unwrap();
+        assert!(output.js.contains("hrml.keep('hrml-0', () => _s.open);"));
+        assert!(output.js.contains("hrml.show('hrml-2', () => _s.open);"));
+        // The detaching binding runs after the rest have found their elements
+        let model = output.js.find("hrml.model('hrml-1'").unwrap();
+        assert!(output.js.find("hrml.keep(").unwrap() > model);
+    }
+
+    #[test]
+    #[ignore = "requires node"]
+    fn test_runtime_keep_reinserts_same_node() {
+        let script = format!(
+            r#"class N {{
+  constructor() {{ this.parentNode = null; }}
+  replaceWith(n) {{
+    const c = this.parentNode.children;
+    c[c.indexOf(this)] = n;
+    n.parentNode = this.parentNode;
+    this.parentNode = null;
+  }}
+}}
+const root = {{ children: [] }};
+const input = new N();
+input.parentNode = root;
+root.children.push(input);
+const document = {{ getElementById: id => (id === 'hrml-0' ? input : null), createComment: () => new N() }};
+{RUNTIME}
+const s = hrml.state({{ open: true }});
+hrml.keep('hrml-0', () => s.open);
+input.value = 'draft';
+s.open = false;
+queueMicrotask(() => {{
+  const detached = input.parentNode === null && root.children[0] !== input;
+  s.open = true;
+  queueMicrotask(() => console.log(detached, root.children[0] === input, input.value));
+}});
+"#
+        );
+        assert_eq!(run_node(&script), "true true draft");
+    }
+
+    #[test]
+    fn test_dedupe_text_shares_one_effect() {
+        let source = "state\n  count: 0\n\nh1 \"{count}\"\np \"Total: {count}\"\nspan \"{count}\"";
+        let output = crate::compile(&parse(source)).unwrap();
+        assert_eq!(output.js.matches("hrml.text(").count(), 3);
+
+        let output =
+            crate::compile(&parse(&format!("config\n  dedupe-text: true\n\n{source}"))).unwrap();
+        assert!(output
+            .js
+            .contains("  hrml.text(['hrml-0', 'hrml-2'], () => `${_s.count}`);\n  hrml.text('hrml-1', () => `Total: ${_s.count}`);\n"));
+        assert_eq!(output.js.matches("hrml.text(").count(), 2);
+    }
+
+    #[test]
+    fn test_attr_binding() {
+        let mut ctx = CompilationContext::new();
+        ctx.state_fields.push(("id".into(), "7".into()));
+        ctx.bindings.push(Binding::Attr {
+            id: "hrml-0".into(),
+            name: "href".into(),
+            template: "/user/${_s.id}".into(),
+        });
+        let js = generate(&ctx).unwrap();
+        assert!(js.contains("hrml.attr('hrml-0', 'href', () => `/user/${_s.id}`)"));
+    }
+
+    fn compile_js(source: &str) -> String {
+        let doc = hrml_parser::Parser::parse(source).unwrap();
+        crate::compile(&doc).unwrap().js
+    }
+
+    #[test]
+    fn test_bare_handler_name_is_called_with_event() {
+        use hrml_parser::ast::{FnDecl, Node};
+
+        let mut doc =
+            parse("state\n  n: 0\n\nbutton @click=\"inc\" \"+\"\nbutton @click=\"inc()\" \"+\"");
+        doc.nodes.push(Node::FnDecl(FnDecl {
+            name: "inc".into(),
+            params: Vec::new(),
+            body: Vec::new(),
+            is_async: false,
+        }));
+        let output = crate::compile(&doc).unwrap();
+        assert!(output
+            .js
+            .contains("hrml.on('hrml-0', 'click', ($event) => { inc($event); });"));
+        assert!(output
+            .js
+            .contains("hrml.on('hrml-1', 'click', ($event) => { inc(); });"));
+        assert!(output.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_bare_handler_name_without_fn_is_left_alone() {
+        let output =
+            crate::compile(&parse("li :for=\"item in items\" @click=\"item\" \"x\"")).unwrap();
+        assert!(output.js.contains("($event) => { item; }"));
+        assert_eq!(
+            output.warnings,
+            vec!["@click=\"item\" on <li> only reads 'item'; call it as item($event) or declare fn item"]
+        );
+    }
+
+    #[test]
+    fn test_arrow_handler_is_the_listener() {
+        let js = compile_js(
+            "state\n  e: 0\n\nbutton @click=\"(e) => handleClick(e, e)\" \"+\"\nform @submit.prevent=\"(e) => save(e)\"",
+        );
+        assert!(js.contains("hrml.on('hrml-0', 'click', (e) => handleClick(e, e));"));
+        assert!(js.contains(
+            "hrml.on('hrml-1', 'submit', ($event) => { $event.preventDefault(); ((e) => save(e))($event); });"
+        ));
+    }
+
+    #[test]
+    fn test_awaiting_handler_is_async() {
+        let js = compile_js(
+            "state\n  data: null\n\nbutton @click=\"data = await fetch(url)\" \"Load\"\nbutton @click=\"async (e) => await save(e)\" \"Save\"\nbutton @click=\"n = await next(); done()\" \"Next\"",
+        );
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'click', async ($event) => { _s.data = await fetch(url); });"
+        ));
+        assert!(js.contains("hrml.on('hrml-1', 'click', async (e) => await save(e));"));
+        assert!(js.contains(
+            "hrml.on('hrml-2', 'click', async ($event) => { n = await next(); done(); });"
+        ));
+    }
+
+    #[test]
+    fn test_debug_option_logs_bindings() {
+        let source = "state\n  name: ''\n\ninput :model=\"name\"\np :show=\"name\" \"Hi {name}\"\nbutton @click=\"name = ''\" \"Clear\"";
+        let doc = hrml_parser::Parser::parse(source).unwrap();
+        let options = crate::CodegenOptions {
+            debug: true,
+            ..Default::default()
+        };
+        let js = crate::compile_with_options(&doc, &options).unwrap().js;
+        assert!(js.contains("hrml.effect(() => console.debug('hrml: text hrml-1', `Hi ${_s.name}`));"));
+        assert!(js.contains("hrml.effect(() => console.debug('hrml: show hrml-1', _s.name));"));
+        assert!(js.contains("hrml.effect(() => console.debug('hrml: model hrml-0', _s.name));"));
+        assert!(js.contains(
+            "hrml.on('hrml-2', 'click', ($event) => console.debug('hrml: on hrml-2 click', $event));"
+        ));
+
+        assert!(!compile_js(source).contains("console.debug"));
+    }
+
+    #[test]
+    fn test_disabled_binds_property() {
+        let js = compile_js("state\n  loading: false\n\nbutton :disabled=\"loading\" \"Save\"");
+        assert!(js.contains("hrml.prop('hrml-0', 'disabled', () => _s.loading);"));
+        assert!(js.contains("el[name] = fn();"));
+        assert!(!js.contains("setAttribute('disabled'"));
+    }
+
+    #[test]
+    fn test_readonly_binds_camel_case_property() {
+        let js = compile_js("state\n  locked: true\n\ninput :readonly=\"locked\"");
+        assert!(js.contains("hrml.prop('hrml-0', 'readOnly', () => _s.locked);"));
+    }
+
+    #[test]
+    fn test_checked_binds_property() {
+        let js = compile_js("state\n  done: false\n\ninput type=checkbox :checked=\"done\"");
+        assert!(js.contains("hrml.prop('hrml-0', 'checked', () => _s.done);"));
+    }
+
+    #[test]
+    fn test_html_binding() {
+        let doc = hrml_parser::Parser::parse("state\n  content: ''\n\narticle :html=\"content\"").unwrap();
+        let output = crate::compile(&doc).unwrap();
+        assert_eq!(output.html, "<article id=\"hrml-0\"></article>\n");
+        // The value is assigned to innerHTML as-is, so the output says so
+        assert!(output.js.contains(
+            "  // :html inserts markup unescaped; bind only trusted, sanitized HTML\n  hrml.html('hrml-0', () => _s.content);\n"
+        ));
+        assert!(output.js.contains("el.innerHTML = fn();"));
+    }
+
     #[test]
     fn test_model_binding() {
         let mut ctx = CompilationContext::new();
@@ -246,6 +1152,80 @@ mod tests {
         assert!(js.contains("hrml.model('hrml-0', _s, 'name')"));
     }
 
+    #[test]
+    fn test_list_binding() {
+        let mut ctx = CompilationContext::new();
+        ctx.state_fields.push(("todos".into(), "[]".into()));
+        ctx.bindings.push(Binding::List {
+            id: "hrml-0".into(),
+            item: "t".into(),
+            index: None,
+            items: "_s.todos".into(),
+            key: Some("t.id".into()),
+            bindings: vec![Binding::Text {
+                id: "hrml-1".into(),
+                template: "${t.title}".into(),
+            }],
+        });
+        let js = generate(&ctx).unwrap();
+        assert!(js.contains(
+            "  hrml.list('hrml-0', () => _s.todos, (t) => t.id, (t) => {\n    hrml.text('hrml-1', () => `${t.title}`);\n  });\n"
+        ));
+    }
+
+    #[test]
+    fn test_list_binding_without_key() {
+        let mut ctx = CompilationContext::new();
+        ctx.options.target = JsTarget::Es5;
+        ctx.bindings.push(Binding::List {
+            id: "hrml-0".into(),
+            item: "t".into(),
+            index: None,
+            items: "_s.todos".into(),
+            key: None,
+            bindings: Vec::new(),
+        });
+        let js = generate(&ctx).unwrap();
+        assert!(js.contains(
+            "hrml.list('hrml-0', function () { return _s.todos; }, null, function (t) {\n  });"
+        ));
+    }
+
+    #[test]
+    fn test_list_index() {
+        let doc = parse(
+            "state\n  items: [\"a\", \"b\"]\n\nli :for=\"(item, i) in items\" \"{i}: {item}\"",
+        );
+        let output = crate::compile(&doc).unwrap();
+        assert!(output.js.contains(
+            "  hrml.list('hrml-0', () => _s.items, null, (item, i) => {\n    hrml.text('hrml-1', () => `${i}: ${item}`);\n  });\n"
+        ));
+    }
+
+    #[test]
+    fn test_list_over_object_entries() {
+        let doc = parse(
+            "state\n  prices: {apple: 1, pear: 2}\n\nli :for=\"(price, fruit) in prices\" \"{fruit}: {price}\"",
+        );
+        let output = crate::compile(&doc).unwrap();
+        assert!(output.js.contains(
+            "  hrml.list('hrml-0', () => _s.prices, null, (price, fruit) => {\n    hrml.text('hrml-1', () => `${fruit}: ${price}`);\n  });\n"
+        ));
+        assert!(output.js.contains("return Object.entries(items);"));
+    }
+
+    #[test]
+    fn test_list_over_numeric_range() {
+        let doc = parse("span :for=\"n in 5\" \"{n}\"");
+        let output = crate::compile(&doc).unwrap();
+        assert!(output
+            .js
+            .contains("  hrml.list('hrml-0', () => 5, null, (n) => {\n"));
+
+        let err = crate::compile(&parse("span :for=\"n in 2.5\" \"{n}\"")).unwrap_err();
+        assert!(err.message.contains("whole number"), "{}", err.message);
+    }
+
     // =========================================================================
     // Runtime
     // =========================================================================
@@ -258,7 +1238,235 @@ mod tests {
         assert!(js.contains("const hrml = (() => {"));
         assert!(js.contains("new Proxy("));
         assert!(js.contains("queueMicrotask"));
-        assert!(js.contains("return { state, effect, text, show, model, on };"));
+        assert!(js.contains("return { state, effect, computed, batch, scope, text, show, keep, prop, html, attr, spread, model, on, list, toggle, classes, filters, debounce, throttle };"));
+        assert!(js.contains("function batch(fn) {"));
+    }
+
+    /// Runs `script` under Node and returns its trimmed stdout. Tests that
+    /// use it exercise the runtime itself and are ignored by default; run
+    /// them with `cargo test -- --ignored` where Node is installed.
+    fn run_node(script: &str) -> String {
+        use std::process::Command;
+
+        let output = Command::new("node")
+            .arg("-e")
+            .arg(script)
+            .output()
+            .expect("node must be installed to run the runtime tests");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Minimal DOM for exercising `hrml.list` under Node: a `<ul>` holding the
+    /// item `<template id="hrml-0">`, plus a counter of created nodes.
+    const LIST_DOM_STUB: &str = r#"let created = 0;
+class El {
+  constructor(attrs = {}) { this.attrs = attrs; this.children = []; this.parent = null; created++; }
+  get nextSibling() { const c = this.parent.children; return c[c.indexOf(this) + 1] || null; }
+  get firstElementChild() { return this.children[0]; }
+  append(n) { n.parent = this; this.children.push(n); }
+  after(n) { if (n.parent) n.remove(); const c = this.parent.children; c.splice(c.indexOf(this) + 1, 0, n); n.parent = this.parent; }
+  remove() { const c = this.parent.children; c.splice(c.indexOf(this), 1); this.parent = null; }
+  cloneNode() { const n = new El({ ...this.attrs }); this.children.forEach(ch => n.append(ch.cloneNode())); return n; }
+  matches(sel) { return sel === `[data-hrml="${this.attrs['data-hrml']}"]`; }
+  querySelector(sel) { return this.children.find(c => c.matches(sel)) || null; }
+}
+const ul = new El();
+const tpl = new El({ id: 'hrml-0' });
+tpl.content = new El();
+tpl.content.append(new El({ 'data-hrml': 'hrml-1' }));
+ul.append(tpl);
+const document = { getElementById: id => (id === 'hrml-0' ? tpl : null) };
+"#;
+
+    #[test]
+    #[ignore = "requires node"]
+    fn test_runtime_list_reorders_without_recreation() {
+        let script = format!(
+            r#"{LIST_DOM_STUB}{RUNTIME}
+const s = hrml.state({{ todos: [] }});
+hrml.list('hrml-0', () => s.todos, t => t.id, t => {{
+  hrml.text('hrml-1', () => t.title);
+}});
+const a = {{ id: 1, title: 'a' }}, b = {{ id: 2, title: 'b' }}, c = {{ id: 3, title: 'c' }};
+const rows = () => ul.children.slice(1);
+s.todos = [a, b, c];
+queueMicrotask(() => {{
+  const before = rows();
+  const count = created;
+  s.todos = [c, a, b];
+  queueMicrotask(() => {{
+    const order = rows().map(n => n.textContent).join(',');
+    const moved = rows().every(n => before.includes(n));
+    console.log(order, moved, created === count);
+  }});
+}});
+"#
+        );
+        assert_eq!(run_node(&script), "c,a,b true true");
+    }
+
+    #[test]
+    #[ignore = "requires node"]
+    fn test_runtime_keyed_list_updates_index_when_head_is_removed() {
+        let script = format!(
+            r#"{LIST_DOM_STUB}{RUNTIME}
+const s = hrml.state({{ todos: [] }});
+hrml.list('hrml-0', () => s.todos, t => t.id, (t, i) => {{
+  hrml.text('hrml-1', () => i + ':' + t.title);
+}});
+const a = {{ id: 1, title: 'a' }}, b = {{ id: 2, title: 'b' }}, c = {{ id: 3, title: 'c' }};
+const rows = () => ul.children.slice(1).map(n => n.textContent).join(',');
+s.todos = [a, b, c];
+queueMicrotask(() => {{
+  const before = rows();
+  s.todos = [b, c];
+  queueMicrotask(() => console.log(before, rows()));
+}});
+"#
+        );
+        assert_eq!(run_node(&script), "0:a,1:b,2:c 0:b,1:c");
+    }
+
+    #[test]
+    #[ignore = "requires node"]
+    fn test_runtime_list_iterates_objects_and_ranges() {
+        let script = format!(
+            r#"{LIST_DOM_STUB}{RUNTIME}
+const s = hrml.state({{ source: {{ a: 1, b: 2 }} }});
+hrml.list('hrml-0', () => s.source, null, (value, key) => {{
+  hrml.text('hrml-1', () => key + '=' + value);
+}});
+const rows = () => ul.children.slice(1).map(n => n.textContent).join(',');
+queueMicrotask(() => {{
+  const entries = rows();
+  s.source = 3;
+  queueMicrotask(() => console.log(entries, rows()));
+}});
+"#
+        );
+        assert_eq!(run_node(&script), "a=1,b=2 0=0,1=1,2=2");
+    }
+
+    #[test]
+    #[ignore = "requires node"]
+    fn test_runtime_computed_memoizes_until_state_changes() {
+        let script = format!(
+            r#"{LIST_DOM_STUB}{RUNTIME}
+const s = hrml.state({{ count: 1 }});
+let runs = 0;
+Object.defineProperty(s, 'double', {{ get: hrml.computed(() => {{ runs++; return s.count * 2; }}), configurable: true }});
+const first = [s.double, s.double];
+s.count = 5;
+s.double = 0;
+console.log(first.join(','), s.double, runs);
+"#
+        );
+        assert_eq!(run_node(&script), "2,2 10 2");
+    }
+
+    #[test]
+    #[ignore = "requires node"]
+    fn test_runtime_json_filter_renders_array_state() {
+        let doc = parse("state\n  tags: ['a', 'b']\n\np \"Tags: {tags | json}\"");
+        let output = crate::compile(&doc).unwrap();
+        let script = format!(
+            r#"const el = {{ textContent: '' }};
+const document = {{ getElementById: id => (id === 'hrml-0' ? el : null) }};
+{}
+console.log(el.textContent);
+"#,
+            output.js
+        );
+        assert_eq!(run_node(&script), r#"Tags: ["a","b"]"#);
+    }
+
+    #[test]
+    #[ignore = "requires node"]
+    fn test_runtime_debounce_waits_for_quiet() {
+        let script = format!(
+            r#"const document = {{}};
+{RUNTIME}
+let calls = [];
+const f = hrml.debounce(x => calls.push(x), 20);
+f(1); f(2); f(3);
+setTimeout(() => console.log(JSON.stringify(calls)), 60);"#
+        );
+        assert_eq!(run_node(&script), "[3]");
+    }
+
+    #[test]
+    fn test_minify_strips_indentation() {
+        let mut ctx = CompilationContext::new();
+        ctx.options.minify = true;
+        ctx.state_fields.push(("count".into(), "0".into()));
+        ctx.bindings.push(Binding::Html {
+            id: "hrml-0".into(),
+            expr: "_s.count".into(),
+        });
+        let js = generate(&ctx).unwrap();
+        assert!(js.lines().all(|line| !line.starts_with(' ')));
+        assert!(!js.contains("//"));
+        assert!(js.ends_with(
+            "(function() {\nconst _s = hrml.state({ count: 0 });\nhrml.html('hrml-0', () => _s.count);\n})();\n"
+        ));
+    }
+
+    #[test]
+    fn test_no_runtime_option() {
+        let mut ctx = CompilationContext::new();
+        ctx.options.no_runtime = true;
+        ctx.options.no_banner = true;
+        ctx.state_fields.push(("count".into(), "0".into()));
+        ctx.bindings.push(Binding::Event {
+            id: "hrml-0".into(),
+            event: "click".into(),
+            handler: "_s.count++".into(),
+            listener: false,
+            is_async: false,
+            emits: false,
+            modifiers: Vec::new(),
+        });
+        let js = generate(&ctx).unwrap();
+        assert!(js.starts_with("(function() {\n"));
+        assert!(!js.contains("const hrml ="));
+        assert!(js.contains("hrml.state({ count: 0 })"));
+        assert!(js.contains("hrml.on('hrml-0', 'click'"));
+    }
+
+    #[test]
+    fn test_public_runtime_matches_embedded() {
+        let mut ctx = CompilationContext::new();
+        ctx.options.no_banner = true;
+        ctx.state_fields.push(("x".into(), "0".into()));
+        let js = generate(&ctx).unwrap();
+        assert!(crate::runtime().starts_with("const hrml = (() => {"));
+        assert!(js.starts_with(crate::runtime()));
+    }
+
+    #[test]
+    fn test_version_banner() {
+        let mut ctx = CompilationContext::new();
+        ctx.state_fields.push(("x".into(), "0".into()));
+        let banner = format!("/* hrml v{} */\n", env!("CARGO_PKG_VERSION"));
+        assert!(generate(&ctx).unwrap().starts_with(&banner));
+
+        ctx.options.minify = true;
+        assert!(!generate(&ctx).unwrap().contains("/* hrml v"));
+
+        ctx.options.minify = false;
+        ctx.options.no_banner = true;
+        assert!(!generate(&ctx).unwrap().contains("/* hrml v"));
+    }
+
+    #[test]
+    fn test_no_banner_without_js() {
+        let ctx = CompilationContext::new();
+        assert_eq!(generate(&ctx).unwrap(), "");
     }
 
     #[test]
@@ -281,7 +1489,15 @@ mod tests {
         ctx.computed_fields
             .push(("double".into(), "_s.count * 2".into()));
         let js = generate(&ctx).unwrap();
-        assert!(js.contains("hrml.effect(() => { _s.double = _s.count * 2; })"));
+        assert!(js.contains(
+            "Object.defineProperty(_s, 'double', { get: hrml.computed(() => _s.count * 2), configurable: true });"
+        ));
+    }
+
+    #[test]
+    fn test_runtime_exports_computed() {
+        assert!(RUNTIME.contains("function computed(fn)"));
+        assert!(RUNTIME.contains("return { state, effect, computed, batch,"));
     }
 
     // =========================================================================
@@ -315,6 +1531,211 @@ mod tests {
         assert!(output.js.contains("hrml.show("));
     }
 
+    #[test]
+    fn test_effect_block_js() {
+        let doc = parse("state\n  count: 0\n\neffect\n  document.title = count + \" items\"");
+        let output = crate::compile(&doc).unwrap();
+        assert!(output
+            .js
+            .contains("  hrml.effect(() => { document.title = _s.count + ' items'; });\n"));
+    }
+
+    #[test]
+    fn test_fn_decl_reaches_js() {
+        use hrml_parser::ast::{FnDecl, Node};
+        use hrml_parser::expr_parser::ExprParser;
+
+        let mut doc = parse("state\n  count: 0\n\nbutton @click=\"bump(2)\" \"+2\"");
+        doc.nodes.push(Node::FnDecl(FnDecl {
+            name: "bump".into(),
+            params: vec!["step".into()],
+            body: vec![ExprParser::parse("count += step").unwrap()],
+            is_async: false,
+        }));
+        let output = crate::compile(&doc).unwrap();
+        assert!(output
+            .js
+            .contains("  function bump(step) { _s.count += step; }\n"));
+        assert!(output
+            .js
+            .contains("hrml.on('hrml-0', 'click', ($event) => { bump(2); });"));
+    }
+
+    #[test]
+    fn test_component_is_an_error_not_dropped() {
+        use hrml_parser::ast::{Component, Node};
+
+        let mut doc = parse("p \"Hi\"");
+        doc.nodes.push(Node::Component(Component {
+            name: "Card".into(),
+            props: Vec::new(),
+            children: Vec::new(),
+        }));
+        let err = crate::compile(&doc).unwrap_err();
+        assert!(err.message.contains("Component 'Card'"));
+    }
+
+    #[test]
+    fn test_conditional_class_toggles() {
+        let doc = parse("state\n  selected: true\n\nli .item .active:selected \"One\"");
+        let output = crate::compile(&doc).unwrap();
+        assert_eq!(output.html, "<li id=\"hrml-0\" class=\"item\">One</li>\n");
+        assert!(output
+            .js
+            .contains("hrml.toggle('hrml-0', 'active', () => _s.selected);"));
+        assert!(RUNTIME.contains("el.classList.toggle(name, !!fn());"));
+    }
+
+    #[test]
+    fn test_conditional_class_beside_class_directive() {
+        let doc = parse("state\n  x: 'a'\n  on: true\n\ndiv .active:on :class=\"x\"");
+        let output = crate::compile(&doc).unwrap();
+        assert_eq!(output.html, "<div id=\"hrml-0\"></div>\n");
+        assert!(output
+            .js
+            .contains("hrml.toggle('hrml-0', 'active', () => _s.on);"));
+        assert!(output.js.contains("hrml.classes('hrml-0', () => _s.x);"));
+    }
+
+    #[test]
+    fn test_interpolation_filters() {
+        let doc = parse("state\n  name: 'ada'\n  price: 9.5\n\nh1 \"{name | upper}\"\np \"{price | round | currency}\"");
+        let output = crate::compile(&doc).unwrap();
+        assert!(output.js.contains("`${hrml.filters.upper(_s.name)}`"));
+        assert!(output
+            .js
+            .contains("`${currency(hrml.filters.round(_s.price))}`"));
+        assert!(output.js.contains("upper: v => String(v).toUpperCase(),"));
+        assert!(output.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_es5_target_uses_concatenation() {
+        let doc = parse("state\n  count: 0\n\nspan \"Count: {count}\"\nbutton @click=\"count++\" \"+\"");
+        let options = crate::CodegenOptions {
+            target: JsTarget::Es5,
+            ..Default::default()
+        };
+        let output = crate::compile_with_options(&doc, &options).unwrap();
+        assert!(output
+            .js
+            .contains("hrml.text('hrml-0', function () { return 'Count: ' + (_s.count); });"));
+        assert!(output
+            .js
+            .contains("hrml.on('hrml-1', 'click', function ($event) { _s.count++; });"));
+        assert!(output.js.contains("var _s = hrml.state({ count: 0 });"));
+        let user_code = &output.js[output.js.find("(function() {").unwrap()..];
+        assert!(!user_code.contains('`'));
+        assert!(!user_code.contains("=>"));
+    }
+
+    #[test]
+    fn test_handler_reads_event() {
+        let js = compile_js("state\n  query: ''\n\ninput @input=\"query = $event.target.value\"");
+        assert!(js.contains(
+            "hrml.on('hrml-0', 'input', ($event) => { _s.query = $event.target.value; });"
+        ));
+    }
+
+    #[test]
+    fn test_multiple_events_on_one_element() {
+        let doc = parse(
+            "state\n  clicks: 0\n  hovers: 0\n\nbutton @click=\"clicks++\" @mouseenter=\"hovers++\" \"Go\"",
+        );
+        let output = crate::compile(&doc).unwrap();
+        assert_eq!(
+            output.html,
+            "<button id=\"hrml-0\" type=\"button\">Go</button>\n"
+        );
+        assert!(output
+            .js
+            .contains("  hrml.on('hrml-0', 'click', ($event) => { _s.clicks++; });\n  hrml.on('hrml-0', 'mouseenter', ($event) => { _s.hovers++; });\n"));
+    }
+
+    #[test]
+    fn test_bindings_grouped_by_kind() {
+        let doc = parse(
+            "state\n  count: 0\n\nbutton @click=\"count--\" \"-\"\nspan \"{count}\"\nbutton @click=\"count++\" \"+\"\np :show=\"count\" \"{count}\"",
+        );
+        let js = crate::compile(&doc).unwrap().js;
+        let calls: Vec<&str> = js
+            .lines()
+            .map(str::trim_start)
+            .filter(|line| line.starts_with("hrml.") && !line.starts_with("hrml.state"))
+            .map(|line| &line[..line.find('(').unwrap()])
+            .collect();
+        assert_eq!(
+            calls,
+            vec!["hrml.on", "hrml.on", "hrml.text", "hrml.text", "hrml.show"]
+        );
+        // Within a group, collection order is kept
+        assert!(js.find("'hrml-0', 'click'").unwrap() < js.find("'hrml-2', 'click'").unwrap());
+    }
+
+    #[test]
+    fn test_multi_statement_handler_batched() {
+        let doc = parse("state\n  a: 0\n  b: 0\n\nbutton @click=\"a = 1; b = 2\" \"Set\"");
+        let output = crate::compile(&doc).unwrap();
+        assert!(output.js.contains(
+            "hrml.on('hrml-0', 'click', ($event) => { hrml.batch(() => { _s.a = 1; _s.b = 2; }); });"
+        ));
+    }
+
+    #[test]
+    fn test_runtime_lookups_are_root_scoped() {
+        let mut ctx = CompilationContext::new();
+        ctx.state_fields.push(("x".into(), "0".into()));
+        let js = generate(&ctx).unwrap();
+        assert!(js.contains("return _root.getElementById(id);"));
+        assert!(!js.contains("document.getElementById"));
+    }
+
+    #[test]
+    fn test_custom_element_wrapper() {
+        let doc = parse("state\n  count: 0\n\ndiv .counter\n  button @click=\"count++\" \"+\"\n  span \"{count}\"");
+        let options = crate::CodegenOptions {
+            wrap_as_custom_element: Some("my-counter".into()),
+            ..Default::default()
+        };
+        let output = crate::compile_with_options(&doc, &options).unwrap();
+
+        assert_eq!(output.html, "<my-counter></my-counter>\n");
+        assert!(output
+            .js
+            .contains("customElements.define('my-counter', class extends HTMLElement {"));
+        assert!(output
+            .js
+            .contains("const root = this.attachShadow({ mode: 'open' });"));
+        assert!(output.js.contains(
+            "root.innerHTML = '<div class=\"counter\">\\n  <button id=\"hrml-0\" type=\"button\">+</button>\\n  <span id=\"hrml-1\"></span>\\n</div>';"
+        ));
+        assert!(output.js.contains("    hrml.scope(root, () => {\n      const _s = hrml.state({ count: 0 });"));
+        assert!(output.js.contains("      hrml.text('hrml-1', () => `${_s.count}`);"));
+        assert!(!output.js.contains("(function() {"));
+    }
+
+    #[test]
+    fn test_custom_element_styles_go_in_shadow_root() {
+        let ctx = CompilationContext::new();
+        let js = generate_custom_element(&ctx, "my-card", "<p>Hi</p>\n", ".card { color: red; }\n")
+            .unwrap();
+        assert!(js.contains("root.innerHTML = '<style>.card { color: red; }</style><p>Hi</p>';"));
+
+        let js = generate_custom_element(&ctx, "my-card", "<p>Hi</p>\n", "").unwrap();
+        assert!(js.contains("root.innerHTML = '<p>Hi</p>';"));
+    }
+
+    #[test]
+    fn test_custom_element_name_must_be_valid() {
+        let doc = parse("div");
+        let options = crate::CodegenOptions {
+            wrap_as_custom_element: Some("Widget".into()),
+            ..Default::default()
+        };
+        let err = crate::compile_with_options(&doc, &options).unwrap_err();
+        assert!(err.message.contains("Invalid custom element name"));
+    }
+
     #[test]
     fn test_input_binding_js() {
         let doc = parse(
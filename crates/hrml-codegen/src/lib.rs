@@ -13,15 +13,241 @@ pub mod html;
 pub mod js;
 
 use hrml_parser::ast::{
-    AssignOp, BinaryOp, Document, ExprKind, Expression, Node, PostfixOp, UnaryOp,
+    AssignOp, BinaryOp, ComputedField, ConfigBlock, Document, Element, ExprKind, Expression,
+    Modifier, Node, PageBlock, PostfixOp, UnaryOp,
 };
 
 /// The compiled output from an HRML document.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct CompilerOutput {
     pub html: String,
     pub css: String,
     pub js: String,
+    /// Non-fatal diagnostics (e.g. interpolation of an unknown name).
+    pub warnings: Vec<String>,
+    /// Document settings from the `page` block.
+    pub page: PageMeta,
+    /// Counts of what was emitted, for size reporting.
+    pub stats: CompileStats,
+}
+
+/// Counters from a compilation, e.g. for `hrml build --stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompileStats {
+    /// Reactive bindings, including those inside `:for` items.
+    pub bindings: usize,
+    pub state_fields: usize,
+    pub computed_fields: usize,
+}
+
+/// Document-level settings from a `page` block, applied by
+/// [`CompilerOutput::standalone`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMeta {
+    pub title: Option<String>,
+    pub lang: Option<String>,
+    pub charset: Option<String>,
+    pub viewport: Option<String>,
+    /// Any other fields, emitted as `<meta name="..." content="...">`, or
+    /// with `property` for Open Graph names such as `og:title`.
+    pub meta: Vec<(String, String)>,
+    /// Markup from `raw` blocks inside a top-level `head`, emitted verbatim.
+    pub head: Vec<String>,
+}
+
+impl PageMeta {
+    /// Apply the fields of a `page` block; later fields win.
+    fn apply(&mut self, block: &PageBlock) {
+        for field in &block.fields {
+            let value = Some(field.value.clone());
+            match field.name.as_str() {
+                "title" => self.title = value,
+                "lang" => self.lang = value,
+                "charset" => self.charset = value,
+                "viewport" => self.viewport = value,
+                _ => self.meta.push((field.name.clone(), field.value.clone())),
+            }
+        }
+    }
+
+    /// Collect the `raw` content of a top-level `head` element. Anything else
+    /// inside it, or markup that would close `<head>` early, is an error.
+    fn add_head(&mut self, el: &Element) -> Result<(), CodegenError> {
+        for child in &el.children {
+            match child {
+                Node::Raw(markup) => {
+                    if markup.to_ascii_lowercase().contains("</head") {
+                        return Err(CodegenError {
+                            message: "Head content must not contain </head>".into(),
+                        });
+                    }
+                    self.head.push(markup.clone());
+                }
+                Node::Comment(_) => {}
+                _ => {
+                    return Err(CodegenError {
+                        message: "A head block may only contain raw content".into(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CompilerOutput {
+    /// Assemble a standalone HTML document: doctype, head with optional
+    /// `<style>`, the body markup, and an inline `<script>` when JS is present.
+    /// Raw `head` content follows the title, unescaped.
+    /// `title` is used unless the `page` block sets one; charset defaults to
+    /// UTF-8 and the viewport to `width=device-width, initial-scale=1`.
+    ///
+    /// Markup that already starts with a doctype (a whole page imported as
+    /// `raw`) is kept as the document instead; see [`Self::imported_document`].
+    pub fn standalone(&self, title: &str) -> String {
+        let is_document = self
+            .html
+            .trim_start()
+            .get(..9)
+            .is_some_and(|start| start.eq_ignore_ascii_case("<!doctype"));
+        if is_document {
+            return self.imported_document();
+        }
+
+        let page = &self.page;
+        let title = page.title.as_deref().unwrap_or(title);
+        let charset = page.charset.as_deref().unwrap_or("UTF-8");
+        let viewport = page
+            .viewport
+            .as_deref()
+            .unwrap_or("width=device-width, initial-scale=1");
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n");
+        match &page.lang {
+            Some(lang) => html.push_str(&format!("<html lang=\"{}\">\n", escape_html(lang))),
+            None => html.push_str("<html>\n"),
+        }
+        html.push_str("<head>\n");
+        html.push_str(&format!("  <meta charset=\"{}\">\n", escape_html(charset)));
+        html.push_str(&format!(
+            "  <meta name=\"viewport\" content=\"{}\">\n",
+            escape_html(viewport)
+        ));
+        html.push_str(&self.head_tags(Some(title)));
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&self.html);
+        if !self.html.is_empty() && !self.html.ends_with('\n') {
+            html.push('\n');
+        }
+        html.push_str("</body>\n</html>\n");
+        self.with_script(html)
+    }
+
+    /// A page imported whole in a `raw` block, kept as the document. The
+    /// `page` meta tags, a `page` title when it has none, raw `head` content,
+    /// and the CSS go before its `</head>`; markup after its `</html>` and the
+    /// script go before its `</body>`. Its own `<html>`, charset, and viewport
+    /// are left as they are.
+    fn imported_document(&self) -> String {
+        let mut html = self.html.clone();
+        if let Some(end) = html.to_ascii_lowercase().rfind("</html>") {
+            let rest = html.split_off(end + "</html>".len());
+            if rest.trim().is_empty() {
+                html.push_str(&rest);
+            } else {
+                insert_before(&mut html, "</body", rest.trim_start_matches('\n'));
+                html.push('\n');
+            }
+        }
+
+        let has_title = html.to_ascii_lowercase().contains("<title");
+        let title = self.page.title.as_deref().filter(|_| !has_title);
+        let head = self.head_tags(title);
+        if !head.is_empty() && !insert_before(&mut html, "</head", &head) {
+            insert_before(&mut html, "<body", &format!("<head>\n{head}</head>\n"));
+        }
+        self.with_script(html)
+    }
+
+    /// `<head>` tags for the `page` meta fields, the title, raw `head`
+    /// content, and the CSS, one per line.
+    fn head_tags(&self, title: Option<&str>) -> String {
+        let mut html = String::new();
+        for (name, content) in &self.page.meta {
+            let key = if is_open_graph(name) {
+                "property"
+            } else {
+                "name"
+            };
+            html.push_str(&format!(
+                "  <meta {key}=\"{}\" content=\"{}\">\n",
+                escape_html(name),
+                escape_html(content)
+            ));
+        }
+        if let Some(title) = title {
+            html.push_str(&format!("  <title>{}</title>\n", escape_html(title)));
+        }
+        for markup in &self.page.head {
+            for line in markup.lines() {
+                html.push_str(&format!("  {line}\n"));
+            }
+        }
+        if !self.css.is_empty() {
+            html.push_str(&format!("  <style>\n{}\n  </style>\n", self.css));
+        }
+        html
+    }
+
+    /// Insert the inline `<script>` before the last `</body>`, or at the end
+    /// when there is none.
+    fn with_script(&self, mut html: String) -> String {
+        if self.js.is_empty() {
+            return html;
+        }
+        let script = format!("<script>\n{}</script>\n", self.js);
+        if !insert_before(&mut html, "</body", &script) {
+            if !html.is_empty() && !html.ends_with('\n') {
+                html.push('\n');
+            }
+            html.push_str(&script);
+        }
+        html
+    }
+}
+
+/// Insert `content` on its own line before the last `tag` (matched without
+/// regard to case). Returns `false`, leaving `html` alone, when there is none.
+fn insert_before(html: &mut String, tag: &str, content: &str) -> bool {
+    let Some(at) = html.to_ascii_lowercase().rfind(tag) else {
+        return false;
+    };
+    if at > 0 && !html[..at].ends_with('\n') {
+        html.insert(at, '\n');
+        html.insert_str(at + 1, content);
+    } else {
+        html.insert_str(at, content);
+    }
+    true
+}
+
+/// Escape text for use in HTML content or a double-quoted attribute.
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Open Graph names (`og:title`, `article:author`, ...) go in a `property`
+/// attribute rather than `name`.
+fn is_open_graph(name: &str) -> bool {
+    let prefix = name.split_once(':').map_or("", |(prefix, _)| prefix);
+    matches!(
+        prefix,
+        "og" | "fb" | "article" | "book" | "profile" | "music" | "video"
+    )
 }
 
 /// Code generation error.
@@ -31,14 +257,217 @@ pub struct CodegenError {
     pub message: String,
 }
 
+/// JavaScript language level for generated binding code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsTarget {
+    /// Arrow functions and template literals.
+    #[default]
+    Modern,
+    /// `function` expressions and string concatenation for older engines.
+    /// The runtime itself still requires `Proxy`.
+    Es5,
+}
+
+/// How whitespace in an element's static text is emitted. Text in `pre`,
+/// `textarea`, and `code` is always kept as written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Whitespace {
+    /// Exactly as written.
+    #[default]
+    Preserve,
+    /// Runs of whitespace become a single space, as browsers render them.
+    Collapse,
+    /// Leading and trailing whitespace is dropped.
+    Trim,
+}
+
+impl Whitespace {
+    /// Apply the policy to `text`.
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Whitespace::Preserve => text.to_string(),
+            Whitespace::Collapse => {
+                let mut out = String::with_capacity(text.len());
+                let mut in_space = false;
+                for c in text.chars() {
+                    if c.is_whitespace() {
+                        if !in_space {
+                            out.push(' ');
+                        }
+                        in_space = true;
+                    } else {
+                        out.push(c);
+                        in_space = false;
+                    }
+                }
+                out
+            }
+            Whitespace::Trim => text.trim().to_string(),
+        }
+    }
+}
+
+/// Options controlling code generation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodegenOptions {
+    /// Language level of the emitted binding code.
+    pub target: JsTarget,
+    /// Wrap the output in a custom element with this tag name. The markup and
+    /// CSS are rendered into the element's shadow root and bindings are
+    /// scoped to it.
+    pub wrap_as_custom_element: Option<String>,
+    /// Omit the runtime and assume a global `hrml` is already loaded
+    /// (see [`runtime`]).
+    pub no_runtime: bool,
+    /// Emit state fields in alphabetical order instead of source order.
+    pub sort_state: bool,
+    /// Bind text interpolations with identical templates (`"{count}"` in
+    /// several places) through one shared effect instead of one each.
+    pub dedupe_text: bool,
+    /// Log each text, show, model, and event binding with `console.debug`
+    /// as it fires.
+    pub debug: bool,
+    /// Emit compact output: HTML without indentation or line breaks (one
+    /// space stays between inline siblings), JS without indentation or
+    /// comments. The JS is compacted, not minified: names and expressions
+    /// are left as written.
+    pub minify: bool,
+    /// Leave out the `/* hrml vX.Y.Z */` comment that opens the JS, naming
+    /// the compiler version. It is always left out under `minify`.
+    pub no_banner: bool,
+    /// Emit no comments at all: no version banner, no notes in the JS, and
+    /// no `<!-- -->` markers in the HTML. `raw` content is left as written.
+    pub strip_comments: bool,
+    /// Whitespace handling for the static text of text-only elements.
+    pub whitespace: Whitespace,
+    /// Leave a `<button>` without a `type` as the browser default, `submit`,
+    /// instead of emitting `type="button"`.
+    pub no_button_type: bool,
+    /// Prefix of the generated element ids (`app-0`); `hrml` when `None`.
+    /// Documents compiled with different prefixes can share a page.
+    pub id_prefix: Option<String>,
+}
+
+impl CodegenOptions {
+    /// Whether generated comments are emitted; `minify` and `strip_comments`
+    /// both turn them off.
+    fn comments(&self) -> bool {
+        !self.minify && !self.strip_comments
+    }
+
+    /// The prefix of generated element ids.
+    fn id_prefix(&self) -> &str {
+        self.id_prefix.as_deref().unwrap_or("hrml")
+    }
+
+    /// The current setting of a `config` key, written as in a `config`
+    /// block; `None` for an unknown key.
+    fn config_value(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "minify" => self.minify.to_string(),
+            "debug" => self.debug.to_string(),
+            "sort-state" => self.sort_state.to_string(),
+            "dedupe-text" => self.dedupe_text.to_string(),
+            "strip-comments" => self.strip_comments.to_string(),
+            "runtime" if self.no_runtime => "external".to_string(),
+            "runtime" => "inline".to_string(),
+            "target" => match self.target {
+                JsTarget::Modern => "modern".to_string(),
+                JsTarget::Es5 => "es5".to_string(),
+            },
+            "whitespace" => match self.whitespace {
+                Whitespace::Preserve => "preserve".to_string(),
+                Whitespace::Collapse => "collapse".to_string(),
+                Whitespace::Trim => "trim".to_string(),
+            },
+            "id-prefix" => self.id_prefix().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Apply the fields of a `config` block; they take precedence over the
+    /// options passed to [`compile_with_options`], with a warning when they
+    /// change one the caller set. Unknown keys and values are reported as
+    /// warnings and otherwise ignored.
+    fn apply_config(&mut self, block: &ConfigBlock, warnings: &mut Vec<String>) {
+        let defaults = Self::default();
+        for field in &block.fields {
+            let key = field.name.as_str();
+            let value = field.value.as_str();
+            let Some(passed) = self.config_value(key) else {
+                warnings.push(format!("Unknown config key '{key}'"));
+                continue;
+            };
+            let flag = match value {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            };
+            match (key, value, flag) {
+                ("minify", _, Some(flag)) => self.minify = flag,
+                ("debug", _, Some(flag)) => self.debug = flag,
+                ("sort-state", _, Some(flag)) => self.sort_state = flag,
+                ("dedupe-text", _, Some(flag)) => self.dedupe_text = flag,
+                ("strip-comments", _, Some(flag)) => self.strip_comments = flag,
+                ("runtime", "inline", _) => self.no_runtime = false,
+                ("runtime", "external", _) => self.no_runtime = true,
+                ("target", "modern", _) => self.target = JsTarget::Modern,
+                ("target", "es5", _) => self.target = JsTarget::Es5,
+                ("whitespace", "preserve", _) => self.whitespace = Whitespace::Preserve,
+                ("whitespace", "collapse", _) => self.whitespace = Whitespace::Collapse,
+                ("whitespace", "trim", _) => self.whitespace = Whitespace::Trim,
+                ("id-prefix", prefix, _) if is_id_prefix(prefix) => {
+                    self.id_prefix = Some(prefix.to_string());
+                }
+                _ => {
+                    warnings.push(format!("Invalid value '{value}' for config key '{key}'"));
+                    continue;
+                }
+            }
+            if passed != value && Some(&passed) != defaults.config_value(key).as_ref() {
+                warnings.push(format!(
+                    "config sets '{key}: {value}', overriding '{key}: {passed}' passed to the compiler"
+                ));
+            }
+        }
+    }
+}
+
+/// Whether `prefix` can start an element id: a letter, then letters, digits,
+/// `-`, or `_`.
+fn is_id_prefix(prefix: &str) -> bool {
+    prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+}
+
 /// Shared context between HTML and JS generators.
 /// HTML generation populates bindings; JS generation reads them.
 #[derive(Default)]
 pub struct CompilationContext {
+    pub options: CodegenOptions,
     pub next_id: usize,
     pub bindings: Vec<Binding>,
     pub state_fields: Vec<(String, String)>,
+    /// `const` fields as name and JS value, declared before the state and
+    /// read by name rather than through `_s`.
+    pub const_fields: Vec<(String, String)>,
     pub computed_fields: Vec<(String, String)>,
+    /// `fn` declarations as JS function declarations, in source order.
+    pub functions: Vec<String>,
+    /// Names of the top-level `fn` declarations; a bare handler name
+    /// (`@click="save"`) calls one of these with the event.
+    pub fn_names: Vec<String>,
+    /// Bodies of `effect` blocks as JS statements, in source order.
+    pub effects: Vec<String>,
+    /// Item names of the enclosing `:for` loops, innermost last.
+    pub loop_vars: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Errors skipped over by [`compile_lenient`]; `None` when compiling
+    /// strictly, where the first error ends compilation.
+    pub errors: Option<Vec<CodegenError>>,
 }
 
 impl CompilationContext {
@@ -47,7 +476,7 @@ impl CompilationContext {
     }
 
     pub fn assign_id(&mut self) -> String {
-        let id = format!("hrml-{}", self.next_id);
+        let id = format!("{}-{}", self.options.id_prefix(), self.next_id);
         self.next_id += 1;
         id
     }
@@ -55,30 +484,225 @@ impl CompilationContext {
     pub fn state_names(&self) -> Vec<String> {
         self.state_fields.iter().map(|(name, _)| name.clone()).collect()
     }
+
+    /// Pass `result` through when compiling strictly. Under
+    /// [`compile_lenient`] an error is recorded instead and `None` is
+    /// returned, so the caller can fall back and carry on.
+    pub(crate) fn recover<T>(
+        &mut self,
+        result: Result<T, CodegenError>,
+    ) -> Result<Option<T>, CodegenError> {
+        match (result, &mut self.errors) {
+            (Ok(value), _) => Ok(Some(value)),
+            (Err(e), Some(errors)) => {
+                errors.push(e);
+                Ok(None)
+            }
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    /// State and computed names — everything that lives on `_s`.
+    pub fn reactive_names(&self) -> Vec<String> {
+        self.state_fields
+            .iter()
+            .chain(&self.computed_fields)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Counters for [`CompilerOutput::stats`].
+    pub fn stats(&self) -> CompileStats {
+        fn count(bindings: &[Binding]) -> usize {
+            bindings
+                .iter()
+                .map(|binding| match binding {
+                    Binding::List { bindings, .. } => 1 + count(bindings),
+                    _ => 1,
+                })
+                .sum()
+        }
+
+        CompileStats {
+            bindings: count(&self.bindings),
+            state_fields: self.state_fields.len(),
+            computed_fields: self.computed_fields.len(),
+        }
+    }
 }
 
 /// A reactive binding collected during HTML generation.
 pub enum Binding {
-    /// `hrml.text(id, () => template)`
+    /// `hrml.text(id, () => template)` — `template` is a template-literal body,
+    /// or a concatenation expression when targeting ES5.
     Text { id: String, template: String },
-    /// `hrml.on(id, event, handler)`
+    /// `hrml.on(id, event, handler)` — `handler` is a statement run with
+    /// `$event` in scope, or, when `listener` is set, a function expression
+    /// passed to `hrml.on` as is. `is_async` marks a statement that awaits;
+    /// `emits` one that calls `emit` and so also needs the element as `$el`.
     Event {
         id: String,
         event: String,
         handler: String,
-        modifiers: Vec<String>,
+        listener: bool,
+        is_async: bool,
+        emits: bool,
+        modifiers: Vec<Modifier>,
     },
     /// `hrml.show(id, () => expr)`
     Show { id: String, expr: String },
+    /// `hrml.keep(id, () => expr)` — `:if.keepalive`: detaches the element
+    /// while `expr` is falsy and reinserts the same node, bindings intact.
+    Keep { id: String, expr: String },
+    /// `hrml.prop(id, 'property', () => expr)` — a DOM property such as
+    /// `disabled`, which form controls read instead of the attribute.
+    Prop {
+        id: String,
+        prop: String,
+        expr: String,
+    },
+    /// `hrml.toggle(id, 'class', () => expr)` — adds the class while `expr` is
+    /// truthy (`.active:isActive`).
+    Class {
+        id: String,
+        class: String,
+        expr: String,
+    },
+    /// `hrml.classes(id, () => expr)` — a `:class` value: a string, array, or
+    /// `{ name: condition }` object.
+    Classes { id: String, expr: String },
+    /// `hrml.html(id, () => expr)` — sets `innerHTML`; the value is not escaped.
+    Html { id: String, expr: String },
+    /// `hrml.spread(id, () => expr)` — each entry of the object becomes an
+    /// attribute, or a property where the element has one (`div {...attrs}`).
+    Spread { id: String, expr: String },
+    /// `hrml.attr(id, 'name', () => template)` — an interpolated plain attribute.
+    Attr {
+        id: String,
+        name: String,
+        template: String,
+    },
     /// `hrml.model(id, _s, 'field')`
     Model { id: String, field: String },
+    /// `hrml.list(id, () => items, key, (item) => { ...bindings })` — `id` is
+    /// the item `<template>`, `key` is an optional `:key` expression.
+    List {
+        id: String,
+        item: String,
+        index: Option<String>,
+        items: String,
+        key: Option<String>,
+        bindings: Vec<Binding>,
+    },
+}
+
+/// The HRML reactive runtime, for setups that load it once and compile
+/// components with [`CodegenOptions::no_runtime`].
+pub fn runtime() -> &'static str {
+    js::RUNTIME
 }
 
 /// Compile an HRML document AST into HTML + CSS + JS.
 pub fn compile(doc: &Document) -> Result<CompilerOutput, CodegenError> {
-    let mut ctx = CompilationContext::new();
+    compile_with_options(doc, &CodegenOptions::default())
+}
+
+/// Compile an HRML document AST with explicit code generation options.
+pub fn compile_with_options(
+    doc: &Document,
+    options: &CodegenOptions,
+) -> Result<CompilerOutput, CodegenError> {
+    let ctx = CompilationContext {
+        options: options.clone(),
+        ..CompilationContext::default()
+    };
+    compile_in(doc, ctx).map(|(output, _)| output)
+}
+
+/// Compile a document AST given as JSON, in the shape `serde` gives
+/// [`Document`], so tools outside Rust can generate it and skip the parser.
+#[cfg(feature = "serde")]
+pub fn compile_from_json(json: &str) -> Result<CompilerOutput, CodegenError> {
+    let doc: Document = serde_json::from_str(json).map_err(|e| CodegenError {
+        message: format!("Invalid document JSON: {e}"),
+    })?;
+    compile(&doc)
+}
 
-    // Pre-pass: collect state and computed fields
+/// Compile as much of the document as possible, e.g. for an editor preview.
+/// A node that fails to compile is replaced by an HTML comment, and the
+/// errors are returned alongside the output instead of ending compilation.
+pub fn compile_lenient(
+    doc: &Document,
+    options: &CodegenOptions,
+) -> (CompilerOutput, Vec<CodegenError>) {
+    let ctx = CompilationContext {
+        options: options.clone(),
+        errors: Some(Vec::new()),
+        ..CompilationContext::default()
+    };
+    compile_in(doc, ctx).unwrap_or_else(|e| (CompilerOutput::default(), vec![e]))
+}
+
+/// Run code generation as far as collecting the bindings, without emitting
+/// JS, e.g. to inspect what the compiler found.
+pub fn collect_bindings(
+    doc: &Document,
+    options: &CodegenOptions,
+) -> Result<Vec<Binding>, CodegenError> {
+    let mut ctx = CompilationContext {
+        options: options.clone(),
+        ..CompilationContext::default()
+    };
+    collect(doc, &mut ctx)?;
+    Ok(ctx.bindings)
+}
+
+/// Shared body of [`compile_with_options`] and [`compile_lenient`]; also
+/// returns the errors recovered from, if `ctx` allows it.
+fn compile_in(
+    doc: &Document,
+    mut ctx: CompilationContext,
+) -> Result<(CompilerOutput, Vec<CodegenError>), CodegenError> {
+    let (html_output, page) = collect(doc, &mut ctx)?;
+    let css_output = css::generate(doc)?;
+    // An invalid tag name under `compile_lenient` falls back to plain output
+    let wrap = match ctx.options.wrap_as_custom_element.clone() {
+        Some(tag) => ctx
+            .recover(validate_custom_element_name(&tag))?
+            .map(|()| tag),
+        None => None,
+    };
+    // A custom element carries its styles in its shadow root
+    let (html_output, css_output, js_output) = match wrap {
+        Some(tag) => {
+            let js_output = js::generate_custom_element(&ctx, &tag, &html_output, &css_output)?;
+            (format!("<{tag}></{tag}>\n"), String::new(), js_output)
+        }
+        None => (html_output, css_output, js::generate(&ctx)?),
+    };
+
+    let output = CompilerOutput {
+        html: html_output,
+        css: css_output,
+        js: js_output,
+        stats: ctx.stats(),
+        warnings: ctx.warnings,
+        page,
+    };
+    Ok((output, ctx.errors.unwrap_or_default()))
+}
+
+/// Collect state, computed fields, page settings, and config, then generate
+/// the HTML, which gathers the bindings into `ctx`.
+fn collect(
+    doc: &Document,
+    ctx: &mut CompilationContext,
+) -> Result<(String, PageMeta), CodegenError> {
+    // Pre-pass: collect state and computed fields, page settings, and config
+    let mut computed = Vec::new();
+    let mut effects = Vec::new();
+    let mut page = PageMeta::default();
     for node in &doc.nodes {
         match node {
             Node::StateBlock(sb) => {
@@ -87,26 +711,375 @@ pub fn compile(doc: &Document) -> Result<CompilerOutput, CodegenError> {
                     ctx.state_fields.push((field.name.clone(), value));
                 }
             }
-            Node::ComputedBlock(cb) => {
+            Node::ConstBlock(cb) => {
                 for field in &cb.fields {
-                    let state_names = ctx.state_names();
-                    let expr = expr_to_js(&field.body, &state_names);
-                    ctx.computed_fields.push((field.name.clone(), expr));
+                    let value = expr_to_js_literal(&field.value);
+                    ctx.const_fields.push((field.name.clone(), value));
                 }
             }
+            Node::ComputedBlock(cb) => computed.extend(cb.fields.iter()),
+            Node::EffectBlock(eb) => effects.push(eb),
+            Node::PageBlock(pb) => page.apply(pb),
+            Node::Element(el) if el.tag == "head" => {
+                ctx.recover(page.add_head(el))?;
+            }
+            Node::ConfigBlock(cb) => ctx.options.apply_config(cb, &mut ctx.warnings),
+            Node::FnDecl(decl) => ctx.fn_names.push(decl.name.clone()),
             _ => {}
         }
     }
 
-    let html_output = html::generate(doc, &mut ctx)?;
-    let css_output = css::generate(doc)?;
-    let js_output = js::generate(&ctx)?;
+    if ctx.options.sort_state {
+        ctx.state_fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    let reactive = ctx.state_names();
+    let shadowed = ctx
+        .const_fields
+        .iter()
+        .find(|(name, _)| reactive.contains(name) || computed.iter().any(|f| &f.name == name));
+    if let Some((name, _)) = shadowed {
+        let error = CodegenError {
+            message: format!("'{name}' is declared both as a const and in state or computed"),
+        };
+        ctx.recover::<()>(Err(error))?;
+    }
 
-    Ok(CompilerOutput {
-        html: html_output,
-        css: css_output,
-        js: js_output,
-    })
+    // Computeds may read state and other computeds (both live on `_s`)
+    let mut names = ctx.state_names();
+    names.extend(computed.iter().map(|f| f.name.clone()));
+    for field in ctx.recover(order_computed(&computed))?.unwrap_or_default() {
+        let place = format!("computed '{}'", field.name);
+        ctx.recover(reject_emit(&field.body, &names, &place))?;
+        check_chained_comparisons(&field.body, &mut ctx.warnings);
+        let expr = expr_to_js(&field.body, &names);
+        ctx.computed_fields.push((field.name.clone(), expr));
+    }
+    for block in effects {
+        for statement in &block.body {
+            ctx.recover(reject_emit(statement, &names, "an effect"))?;
+        }
+        let body: String = block
+            .body
+            .iter()
+            .map(|statement| {
+                check_chained_comparisons(statement, &mut ctx.warnings);
+                format!("{}; ", expr_to_js(statement, &names))
+            })
+            .collect();
+        ctx.effects.push(body);
+    }
+
+    let html_output = html::generate(doc, ctx)?;
+    Ok((html_output, page))
+}
+
+/// Custom element names must start with a lowercase letter and contain a hyphen.
+fn validate_custom_element_name(tag: &str) -> Result<(), CodegenError> {
+    let valid = tag.starts_with(|c: char| c.is_ascii_lowercase())
+        && tag.contains('-')
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(CodegenError {
+            message: format!(
+                "Invalid custom element name '{tag}': use lowercase letters and include a hyphen (e.g. 'my-widget')"
+            ),
+        })
+    }
+}
+
+/// Order computed fields so each one comes after the computeds it reads.
+/// Source order is kept where there is no dependency; cycles are an error.
+fn order_computed<'a>(
+    fields: &[&'a ComputedField],
+) -> Result<Vec<&'a ComputedField>, CodegenError> {
+    const UNVISITED: u8 = 0;
+    const VISITING: u8 = 1;
+    const DONE: u8 = 2;
+
+    fn visit<'a>(
+        index: usize,
+        fields: &[&'a ComputedField],
+        marks: &mut [u8],
+        path: &mut Vec<String>,
+        ordered: &mut Vec<&'a ComputedField>,
+    ) -> Result<(), CodegenError> {
+        let field = fields[index];
+        path.push(field.name.clone());
+        match marks[index] {
+            DONE => {}
+            VISITING => {
+                return Err(CodegenError {
+                    message: format!("Computed fields form a cycle: {}", path.join(" -> ")),
+                });
+            }
+            _ => {
+                marks[index] = VISITING;
+                let mut deps = Vec::new();
+                collect_identifiers(&field.body, &mut deps);
+                for dep in deps {
+                    if let Some(dep_index) = fields.iter().position(|f| f.name == dep) {
+                        visit(dep_index, fields, marks, path, ordered)?;
+                    }
+                }
+                marks[index] = DONE;
+                ordered.push(field);
+            }
+        }
+        path.pop();
+        Ok(())
+    }
+
+    let mut marks = vec![UNVISITED; fields.len()];
+    let mut ordered = Vec::with_capacity(fields.len());
+    for index in 0..fields.len() {
+        visit(index, fields, &mut marks, &mut Vec::new(), &mut ordered)?;
+    }
+    Ok(ordered)
+}
+
+/// Whether an expression awaits, so the function running it must be `async`.
+/// A nested arrow is its own function and is not looked into.
+pub(crate) fn contains_await(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExprKind::Unary {
+            op: UnaryOp::Await, ..
+        } => true,
+        ExprKind::Unary { operand, .. }
+        | ExprKind::Postfix { operand, .. }
+        | ExprKind::Interpolation(operand)
+        | ExprKind::Filter { input: operand, .. } => contains_await(operand),
+        ExprKind::Binary { left, right, .. }
+        | ExprKind::Assignment {
+            target: left,
+            value: right,
+            ..
+        } => contains_await(left) || contains_await(right),
+        ExprKind::Member {
+            object, property, ..
+        } => contains_await(object) || contains_await(property),
+        ExprKind::Call { callee, arguments } => {
+            contains_await(callee) || arguments.iter().any(contains_await)
+        }
+        ExprKind::Ternary {
+            condition,
+            consequent,
+            alternate,
+        } => contains_await(condition) || contains_await(consequent) || contains_await(alternate),
+        ExprKind::Object(props) => props.iter().any(|p| contains_await(&p.value)),
+        ExprKind::Array(items) | ExprKind::Sequence(items) => items.iter().any(contains_await),
+        ExprKind::Arrow { .. }
+        | ExprKind::Number(_)
+        | ExprKind::String(_)
+        | ExprKind::Boolean(_)
+        | ExprKind::Null
+        | ExprKind::Undefined
+        | ExprKind::Identifier(_) => false,
+    }
+}
+
+/// Whether `callee` is the built-in `emit`, which a state field can shadow.
+fn is_emit(callee: &Expression, state_names: &[String]) -> bool {
+    matches!(&callee.kind, ExprKind::Identifier(name) if name == "emit")
+        && !state_names.iter().any(|s| s == "emit")
+}
+
+/// Whether an event handler calls `emit`, and so needs `$el`.
+pub(crate) fn calls_emit(expr: &Expression, state_names: &[String]) -> bool {
+    let mut names = Vec::new();
+    collect_identifiers(expr, &mut names);
+    names.iter().any(|n| n == "emit") && !state_names.iter().any(|s| s == "emit")
+}
+
+/// An error for `emit` outside an event handler, where there is no element
+/// to dispatch from and the call would throw a ReferenceError.
+pub(crate) fn reject_emit(
+    expr: &Expression,
+    state_names: &[String],
+    place: &str,
+) -> Result<(), CodegenError> {
+    if calls_emit(expr, state_names) {
+        return Err(CodegenError {
+            message: format!("`emit` can only be called in an event handler, not in {place}"),
+        });
+    }
+    Ok(())
+}
+
+fn is_comparison(op: BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Lt
+            | BinaryOp::Gt
+            | BinaryOp::Lte
+            | BinaryOp::Gte
+            | BinaryOp::Eq
+            | BinaryOp::Neq
+            | BinaryOp::StrictEq
+            | BinaryOp::StrictNeq
+    )
+}
+
+/// Warn about `a < b < c`: JavaScript compares the boolean `a < b` with `c`,
+/// which is rarely what was meant. Parentheses are not kept in the AST, so
+/// `(a < b) < c` is reported too.
+pub(crate) fn check_chained_comparisons(expr: &Expression, warnings: &mut Vec<String>) {
+    match &expr.kind {
+        ExprKind::Binary { left, op, right } => {
+            if is_comparison(*op) {
+                if let ExprKind::Binary {
+                    left: first,
+                    op: inner,
+                    right: middle,
+                } = &left.kind
+                {
+                    if is_comparison(*inner) {
+                        let [a, b, c] = [first, middle, right].map(|e| expr_to_js(e, &[]));
+                        let (inner, op) = (binary_op_to_js(*inner), binary_op_to_js(*op));
+                        warnings.push(format!(
+                            "`{a} {inner} {b} {op} {c}` compares the result of `{a} {inner} {b}` with `{c}`; did you mean `{a} {inner} {b} && {b} {op} {c}`?"
+                        ));
+                    }
+                }
+            }
+            check_chained_comparisons(left, warnings);
+            check_chained_comparisons(right, warnings);
+        }
+        ExprKind::Unary { operand, .. }
+        | ExprKind::Postfix { operand, .. }
+        | ExprKind::Interpolation(operand)
+        | ExprKind::Filter { input: operand, .. }
+        | ExprKind::Arrow { body: operand, .. } => check_chained_comparisons(operand, warnings),
+        ExprKind::Assignment {
+            target: left,
+            value: right,
+            ..
+        }
+        | ExprKind::Member {
+            object: left,
+            property: right,
+            ..
+        } => {
+            check_chained_comparisons(left, warnings);
+            check_chained_comparisons(right, warnings);
+        }
+        ExprKind::Call { callee, arguments } => {
+            check_chained_comparisons(callee, warnings);
+            for argument in arguments {
+                check_chained_comparisons(argument, warnings);
+            }
+        }
+        ExprKind::Ternary {
+            condition,
+            consequent,
+            alternate,
+        } => {
+            for part in [condition, consequent, alternate] {
+                check_chained_comparisons(part, warnings);
+            }
+        }
+        ExprKind::Object(props) => {
+            for prop in props {
+                check_chained_comparisons(&prop.value, warnings);
+            }
+        }
+        ExprKind::Array(items) | ExprKind::Sequence(items) => {
+            for item in items {
+                check_chained_comparisons(item, warnings);
+            }
+        }
+        ExprKind::Number(_)
+        | ExprKind::String(_)
+        | ExprKind::Boolean(_)
+        | ExprKind::Null
+        | ExprKind::Undefined
+        | ExprKind::Identifier(_) => {}
+    }
+}
+
+/// Collect the free identifiers an expression reads, in source order.
+/// Member properties, arrow parameters, and filter names are not free
+/// identifiers.
+pub(crate) fn collect_identifiers(expr: &Expression, out: &mut Vec<String>) {
+    match &expr.kind {
+        ExprKind::Identifier(name) => {
+            if !out.contains(name) {
+                out.push(name.clone());
+            }
+        }
+        ExprKind::Binary { left, right, .. } => {
+            collect_identifiers(left, out);
+            collect_identifiers(right, out);
+        }
+        ExprKind::Unary { operand, .. } | ExprKind::Postfix { operand, .. } => {
+            collect_identifiers(operand, out);
+        }
+        ExprKind::Member {
+            object,
+            property,
+            computed,
+        } => {
+            collect_identifiers(object, out);
+            if *computed {
+                collect_identifiers(property, out);
+            }
+        }
+        ExprKind::Call { callee, arguments } => {
+            collect_identifiers(callee, out);
+            for arg in arguments {
+                collect_identifiers(arg, out);
+            }
+        }
+        ExprKind::Ternary {
+            condition,
+            consequent,
+            alternate,
+        } => {
+            collect_identifiers(condition, out);
+            collect_identifiers(consequent, out);
+            collect_identifiers(alternate, out);
+        }
+        ExprKind::Object(props) => {
+            for prop in props {
+                if prop.shorthand {
+                    if !out.contains(&prop.key) {
+                        out.push(prop.key.clone());
+                    }
+                } else {
+                    collect_identifiers(&prop.value, out);
+                }
+            }
+        }
+        ExprKind::Array(items) | ExprKind::Sequence(items) => {
+            for item in items {
+                collect_identifiers(item, out);
+            }
+        }
+        ExprKind::Arrow { params, body, .. } => {
+            let mut inner = Vec::new();
+            collect_identifiers(body, &mut inner);
+            for name in inner {
+                if !params.contains(&name) && !out.contains(&name) {
+                    out.push(name);
+                }
+            }
+        }
+        ExprKind::Assignment { target, value, .. } => {
+            collect_identifiers(target, out);
+            collect_identifiers(value, out);
+        }
+        ExprKind::Interpolation(inner) | ExprKind::Filter { input: inner, .. } => {
+            collect_identifiers(inner, out)
+        }
+        ExprKind::Number(_)
+        | ExprKind::String(_)
+        | ExprKind::Boolean(_)
+        | ExprKind::Null
+        | ExprKind::Undefined => {}
+    }
 }
 
 // =========================================================================
@@ -118,7 +1091,7 @@ pub fn compile(doc: &Document) -> Result<CompilerOutput, CodegenError> {
 pub fn expr_to_js_literal(expr: &Expression) -> String {
     match &expr.kind {
         ExprKind::Number(n) => format_number(*n),
-        ExprKind::String(s) => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+        ExprKind::String(s) => js_string(s),
         ExprKind::Boolean(b) => b.to_string(),
         ExprKind::Null => "null".into(),
         ExprKind::Undefined => "undefined".into(),
@@ -144,11 +1117,26 @@ pub fn expr_to_js_literal(expr: &Expression) -> String {
     }
 }
 
+/// Filters provided by the runtime as `hrml.filters.*`. Any other filter name
+/// is called as a function of that name.
+const BUILTIN_FILTERS: &[&str] = &["json", "lower", "round", "upper"];
+
 /// Convert an expression to JS code, prefixing state variable identifiers with `_s.`.
 pub fn expr_to_js(expr: &Expression, state_names: &[String]) -> String {
+    lower(expr, state_names, false)
+}
+
+/// [`expr_to_js`] for an event handler, where `emit('name', detail)`
+/// dispatches a bubbling `CustomEvent` from `$el`, the element the handler is
+/// attached to.
+pub(crate) fn handler_expr_to_js(expr: &Expression, state_names: &[String]) -> String {
+    lower(expr, state_names, true)
+}
+
+fn lower(expr: &Expression, state_names: &[String], handler: bool) -> String {
     match &expr.kind {
         ExprKind::Number(n) => format_number(*n),
-        ExprKind::String(s) => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+        ExprKind::String(s) => js_string(s),
         ExprKind::Boolean(b) => b.to_string(),
         ExprKind::Null => "null".into(),
         ExprKind::Undefined => "undefined".into(),
@@ -162,15 +1150,15 @@ pub fn expr_to_js(expr: &Expression, state_names: &[String]) -> String {
         ExprKind::Binary { left, op, right } => {
             format!(
                 "{} {} {}",
-                expr_to_js(left, state_names),
+                lower(left, state_names, handler),
                 binary_op_to_js(*op),
-                expr_to_js(right, state_names),
+                lower(right, state_names, handler),
             )
         }
         ExprKind::Unary { op, operand } => {
             let op_str = unary_op_to_js(*op);
-            let operand_str = expr_to_js(operand, state_names);
-            if matches!(op, UnaryOp::Typeof) {
+            let operand_str = lower(operand, state_names, handler);
+            if matches!(op, UnaryOp::Typeof | UnaryOp::Await) {
                 format!("{op_str} {operand_str}")
             } else {
                 format!("{op_str}{operand_str}")
@@ -179,16 +1167,16 @@ pub fn expr_to_js(expr: &Expression, state_names: &[String]) -> String {
         ExprKind::Postfix { operand, op } => {
             format!(
                 "{}{}",
-                expr_to_js(operand, state_names),
+                lower(operand, state_names, handler),
                 postfix_op_to_js(*op)
             )
         }
         ExprKind::Assignment { target, op, value } => {
             format!(
                 "{} {} {}",
-                expr_to_js(target, state_names),
+                lower(target, state_names, handler),
                 assign_op_to_js(*op),
-                expr_to_js(value, state_names),
+                lower(value, state_names, handler),
             )
         }
         ExprKind::Member {
@@ -199,25 +1187,35 @@ pub fn expr_to_js(expr: &Expression, state_names: &[String]) -> String {
             if *computed {
                 format!(
                     "{}[{}]",
-                    expr_to_js(object, state_names),
-                    expr_to_js(property, state_names),
+                    lower(object, state_names, handler),
+                    lower(property, state_names, handler),
                 )
             } else {
+                // `.name` is a property, never state, whatever it is called
                 format!(
                     "{}.{}",
-                    expr_to_js(object, state_names),
-                    expr_to_js(property, state_names),
+                    lower(object, state_names, handler),
+                    lower(property, &[], handler)
                 )
             }
         }
+        ExprKind::Call { callee, arguments } if handler && is_emit(callee, state_names) => {
+            let mut args = arguments.iter().map(|a| lower(a, state_names, handler));
+            let name = args.next().unwrap_or_else(|| "''".to_string());
+            let init = match args.next() {
+                Some(detail) => format!("{{ detail: {detail}, bubbles: true }}"),
+                None => "{ bubbles: true }".to_string(),
+            };
+            format!("$el.dispatchEvent(new CustomEvent({name}, {init}))")
+        }
         ExprKind::Call { callee, arguments } => {
             let args: Vec<String> = arguments
                 .iter()
-                .map(|a| expr_to_js(a, state_names))
+                .map(|a| lower(a, state_names, handler))
                 .collect();
             format!(
                 "{}({})",
-                expr_to_js(callee, state_names),
+                lower(callee, state_names, handler),
                 args.join(", ")
             )
         }
@@ -228,9 +1226,9 @@ pub fn expr_to_js(expr: &Expression, state_names: &[String]) -> String {
         } => {
             format!(
                 "{} ? {} : {}",
-                expr_to_js(condition, state_names),
-                expr_to_js(consequent, state_names),
-                expr_to_js(alternate, state_names),
+                lower(condition, state_names, handler),
+                lower(consequent, state_names, handler),
+                lower(alternate, state_names, handler),
             )
         }
         ExprKind::Object(props) => {
@@ -242,9 +1240,9 @@ pub fn expr_to_js(expr: &Expression, state_names: &[String]) -> String {
                             kind: ExprKind::Identifier(p.key.clone()),
                             span: expr.span,
                         };
-                        format!("{}: {}", p.key, expr_to_js(&ident_expr, state_names))
+                        format!("{}: {}", p.key, lower(&ident_expr, state_names, handler))
                     } else {
-                        format!("{}: {}", p.key, expr_to_js(&p.value, state_names))
+                        format!("{}: {}", p.key, lower(&p.value, state_names, handler))
                     }
                 })
                 .collect();
@@ -253,28 +1251,143 @@ pub fn expr_to_js(expr: &Expression, state_names: &[String]) -> String {
         ExprKind::Array(items) => {
             let parts: Vec<String> = items
                 .iter()
-                .map(|i| expr_to_js(i, state_names))
+                .map(|i| lower(i, state_names, handler))
                 .collect();
             format!("[{}]", parts.join(", "))
         }
-        ExprKind::Arrow { params, body } => {
+        ExprKind::Arrow {
+            params,
+            body,
+            is_async,
+        } => {
             let ps = if params.len() == 1 {
                 params[0].clone()
             } else {
                 format!("({})", params.join(", "))
             };
-            format!("{ps} => {}", expr_to_js(body, state_names))
+            let keyword = if *is_async { "async " } else { "" };
+            format!("{keyword}{ps} => {}", lower(body, state_names, handler))
+        }
+        ExprKind::Interpolation(inner) => lower(inner, state_names, handler),
+        ExprKind::Filter { input, name } => {
+            let input = lower(input, state_names, handler);
+            if BUILTIN_FILTERS.contains(&name.as_str()) {
+                format!("hrml.filters.{name}({input})")
+            } else {
+                format!("{name}({input})")
+            }
+        }
+        ExprKind::Sequence(stmts) => {
+            let parts: Vec<String> = stmts
+                .iter()
+                .map(|s| lower(s, state_names, handler))
+                .collect();
+            format!("({})", parts.join(", "))
+        }
+    }
+}
+
+/// Quote a string as a JS string literal.
+/// Single quotes are preferred; double quotes are used when the content has
+/// more `'` than `"`, so the literal needs fewer escapes.
+/// Control characters and the JS line terminators U+2028/U+2029 are escaped
+/// so the literal always stays on one line.
+pub(crate) fn js_string(s: &str) -> String {
+    let singles = s.matches('\'').count();
+    let doubles = s.matches('"').count();
+    let quote = if singles > doubles { '"' } else { '\'' };
+
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => push_js_char(c, &mut out),
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Escape static text for the body of a JS template literal: backslashes,
+/// backticks, and `${` as well as what [`js_string`] escapes.
+pub(crate) fn js_template_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '`' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            c => push_js_char(c, &mut out),
         }
-        ExprKind::Interpolation(inner) => expr_to_js(inner, state_names),
+    }
+    out
+}
+
+/// Push a character of a JS string or template literal, escaping control
+/// characters and the line terminators U+2028/U+2029.
+fn push_js_char(c: char, out: &mut String) {
+    match c {
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        '\u{8}' => out.push_str("\\b"),
+        '\u{b}' => out.push_str("\\v"),
+        '\u{c}' => out.push_str("\\f"),
+        '\u{2028}' => out.push_str("\\u2028"),
+        '\u{2029}' => out.push_str("\\u2029"),
+        // Including NUL: `\0` followed by a digit would be an octal escape
+        c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+        c => out.push(c),
     }
 }
 
-/// Format a number, removing `.0` for integers.
+/// Format a number the way JavaScript's `String(n)` does: shortest
+/// round-trip digits, no `.0` on integers, exponent form below `1e-6` and
+/// from `1e21`, and `-0` as `0`.
 pub fn format_number(n: f64) -> String {
-    if n.fract() == 0.0 && n.is_finite() {
-        format!("{}", n as i64)
+    if n.is_nan() {
+        return "NaN".into();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.into();
+    }
+    if n == 0.0 {
+        return "0".into();
+    }
+
+    // Shortest digits and decimal exponent: n = 0.digits × 10^point
+    let sci = format!("{:e}", n.abs());
+    let (mantissa, exponent) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits = mantissa.replace('.', "");
+    let point = exponent.parse::<i32>().unwrap_or(0) + 1;
+    let len = digits.len() as i32;
+
+    let body = if len <= point && point <= 21 {
+        format!("{digits}{}", "0".repeat((point - len) as usize))
+    } else if 0 < point && point <= 21 {
+        let (int, frac) = digits.split_at(point as usize);
+        format!("{int}.{frac}")
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{digits}", "0".repeat(-point as usize))
+    } else {
+        let sign = if point > 0 { "+" } else { "-" };
+        let (first, rest) = digits.split_at(1);
+        let dot = if rest.is_empty() { "" } else { "." };
+        format!("{first}{dot}{rest}e{sign}{}", (point - 1).abs())
+    };
+
+    if n < 0.0 {
+        format!("-{body}")
     } else {
-        format!("{n}")
+        body
     }
 }
 
@@ -304,6 +1417,7 @@ fn unary_op_to_js(op: UnaryOp) -> &'static str {
         UnaryOp::Not => "!",
         UnaryOp::Neg => "-",
         UnaryOp::Typeof => "typeof",
+        UnaryOp::Await => "await",
     }
 }
 
@@ -347,10 +1461,7 @@ mod tests {
 
     #[test]
     fn test_literal_number_float() {
-        assert_eq!(
-            expr_to_js_literal(&make_expr(ExprKind::Number(3.14))),
-            "3.14"
-        );
+        assert_eq!(expr_to_js_literal(&make_expr(ExprKind::Number(2.5))), "2.5");
     }
 
     #[test]
@@ -374,6 +1485,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_literal_string_escapes_newline_and_tab() {
+        assert_eq!(
+            expr_to_js_literal(&make_expr(ExprKind::String("a\nb\tc\rd".into()))),
+            "'a\\nb\\tc\\rd'"
+        );
+    }
+
+    #[test]
+    fn test_literal_string_escapes_line_separators() {
+        assert_eq!(
+            expr_to_js_literal(&make_expr(ExprKind::String("a\u{2028}b\u{2029}".into()))),
+            "'a\\u2028b\\u2029'"
+        );
+    }
+
+    #[test]
+    fn test_literal_string_escapes_control_chars() {
+        assert_eq!(
+            expr_to_js_literal(&make_expr(ExprKind::String("\u{0}\u{1b}\u{7f}".into()))),
+            "'\\u0000\\u001b\\u007f'"
+        );
+    }
+
+    #[test]
+    fn test_literal_string_with_apostrophe_uses_double_quotes() {
+        assert_eq!(
+            expr_to_js_literal(&make_expr(ExprKind::String("it's Bob's".into()))),
+            "\"it's Bob's\""
+        );
+    }
+
+    #[test]
+    fn test_literal_string_balanced_quotes_stays_single() {
+        assert_eq!(
+            expr_to_js_literal(&make_expr(ExprKind::String("say \"hi\" it's".into()))),
+            "'say \"hi\" it\\'s'"
+        );
+    }
+
+    #[test]
+    fn test_literal_string_double_quoted_escapes_double_quote() {
+        assert_eq!(
+            expr_to_js_literal(&make_expr(ExprKind::String("'a' \"b\" 'c'".into()))),
+            "\"'a' \\\"b\\\" 'c'\""
+        );
+    }
+
     #[test]
     fn test_literal_boolean_true() {
         assert_eq!(
@@ -476,6 +1635,33 @@ mod tests {
         assert_eq!(expr_to_js(&expr, &state), "_s.items.length");
     }
 
+    #[test]
+    fn test_js_member_property_named_like_state() {
+        use hrml_parser::expr_parser::ExprParser;
+
+        let state = vec!["items".into(), "count".into()];
+        let expr = ExprParser::parse("items.count > count").unwrap();
+        assert_eq!(expr_to_js(&expr, &state), "_s.items.count > _s.count");
+
+        let nested = ExprParser::parse("items[count].count.items").unwrap();
+        assert_eq!(
+            expr_to_js(&nested, &state),
+            "_s.items[_s.count].count.items"
+        );
+    }
+
+    #[test]
+    fn test_js_object_keys_are_not_state() {
+        use hrml_parser::expr_parser::ExprParser;
+
+        let state = vec!["count".into(), "x".into()];
+        let js = |source: &str| expr_to_js(&ExprParser::parse(source).unwrap(), &state);
+        // A shorthand key reads the state field of that name
+        assert_eq!(js("{ count }"), "{ count: _s.count }");
+        assert_eq!(js("{ count: 1 }"), "{ count: 1 }");
+        assert_eq!(js("{ count: x, x: count }"), "{ count: _s.x, x: _s.count }");
+    }
+
     #[test]
     fn test_js_call() {
         let state = vec!["count".into()];
@@ -504,6 +1690,37 @@ mod tests {
         );
     }
 
+    // =========================================================================
+    // Number formatting
+    // =========================================================================
+
+    #[test]
+    fn test_format_number_matches_js_string() {
+        let cases: &[(f64, &str)] = &[
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-42.0, "-42"),
+            (1.5, "1.5"),
+            (0.1, "0.1"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (100000000000.0, "100000000000"),
+            (123e18, "123000000000000000000"),
+            (1e21, "1e+21"),
+            (1.5e300, "1.5e+300"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.25e-7, "-1.25e-7"),
+            (9007199254740993.0, "9007199254740992"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ];
+        for (n, expected) in cases {
+            assert_eq!(format_number(*n), *expected, "formatting {n:?}");
+        }
+    }
+
     // =========================================================================
     // Integration: compile()
     // =========================================================================
@@ -521,6 +1738,113 @@ mod tests {
         assert_eq!(output.js, "");
     }
 
+    #[test]
+    fn test_config_block_sets_options() {
+        let source = "state\n  count: 0\n\ndiv\n  button @click=\"count++\" \"+\"";
+        let configured = compile(&parse(&format!("config\n  minify: true\n\n{source}"))).unwrap();
+        let minified = compile_with_options(
+            &parse(source),
+            &CodegenOptions {
+                minify: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(configured.js, minified.js);
+        assert_eq!(configured.html, minified.html);
+        assert!(configured.warnings.is_empty());
+
+        let external = compile(&parse(&format!("config\n  runtime: external\n\n{source}"))).unwrap();
+        assert!(!external.js.contains("const hrml ="));
+    }
+
+    #[test]
+    fn test_config_block_sets_whitespace() {
+        let output = compile(&parse("config\n  whitespace: collapse\n\np \"a   b\"")).unwrap();
+        assert_eq!(output.html, "<p>a b</p>\n");
+    }
+
+    #[test]
+    fn test_config_block_sets_id_prefix() {
+        let output = compile(&parse(
+            "config\n  id-prefix: app\n\nstate\n  n: 0\n\np \"{n}\"",
+        ))
+        .unwrap();
+        assert_eq!(output.html, "<p id=\"app-0\"></p>\n");
+        assert!(output.js.contains("hrml.text('app-0',"));
+
+        let output = compile(&parse("config\n  id-prefix: \"my app\"\n\ndiv")).unwrap();
+        assert_eq!(
+            output.warnings,
+            vec!["Invalid value 'my app' for config key 'id-prefix'".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_config_block_warns_when_overriding_passed_option() {
+        let options = CodegenOptions {
+            minify: true,
+            target: JsTarget::Es5,
+            ..Default::default()
+        };
+        let source = "config\n  minify: false\n  target: es5\n  debug: true\n\ndiv";
+        let output = compile_with_options(&parse(source), &options).unwrap();
+        assert_eq!(
+            output.warnings,
+            vec!["config sets 'minify: false', overriding 'minify: true' passed to the compiler"]
+        );
+        assert!(compile(&parse(source)).unwrap().warnings.is_empty());
+    }
+
+    #[test]
+    fn test_documented_config_block_compiles() {
+        let guide = include_str!("../../../docs/getting-started.md");
+        let start = guide.find("```hrml\nconfig\n").unwrap() + "```hrml\n".len();
+        let block = &guide[start..start + guide[start..].find("```").unwrap()];
+        let output = compile(&parse(&format!("{block}\np \"Hi\""))).unwrap();
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+        assert_eq!(output.html, "<p>Hi</p>");
+        assert!(!output.js.contains("const hrml ="));
+    }
+
+    #[test]
+    fn test_config_block_warns_on_unknown_key_and_value() {
+        let output = compile(&parse("config\n  minify: yes\n  ids: short\n\ndiv")).unwrap();
+        assert_eq!(
+            output.warnings,
+            vec![
+                "Invalid value 'yes' for config key 'minify'".to_string(),
+                "Unknown config key 'ids'".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compile_whitespace_and_comment_only() {
+        for source in ["   \n\t\n  \n", "// notes\n\n  // more notes\n"] {
+            let output = compile(&parse(source)).unwrap();
+            assert_eq!(output.html, "", "{source:?}");
+            assert_eq!(output.css, "", "{source:?}");
+            assert_eq!(output.js, "", "{source:?}");
+            assert!(output.warnings.is_empty(), "{source:?}");
+        }
+    }
+
+    #[test]
+    fn test_stats_counter_example() {
+        let doc = parse(include_str!("../../../examples/counter.hrml"));
+        let stats = compile(&doc).unwrap().stats;
+        // 1 text, 3 show, 3 click, 1 model
+        assert_eq!(
+            stats,
+            CompileStats {
+                bindings: 8,
+                state_fields: 2,
+                computed_fields: 0,
+            }
+        );
+    }
+
     #[test]
     fn test_compile_counter() {
         let doc = parse(
@@ -530,9 +1854,13 @@ mod tests {
 
         // HTML has structure
         assert!(output.html.contains("<div class=\"counter\">"));
-        assert!(output.html.contains("<button id=\"hrml-0\">-</button>"));
+        assert!(output
+            .html
+            .contains("<button id=\"hrml-0\" type=\"button\">-</button>"));
         assert!(output.html.contains("<span id=\"hrml-1\"></span>"));
-        assert!(output.html.contains("<button id=\"hrml-2\">+</button>"));
+        assert!(output
+            .html
+            .contains("<button id=\"hrml-2\" type=\"button\">+</button>"));
 
         // JS has state and bindings
         assert!(output.js.contains("hrml.state({ count: 0 })"));
@@ -548,13 +1876,42 @@ mod tests {
         );
         let output = compile(&doc).unwrap();
 
-        assert!(output.html.contains("<button id=\"hrml-0\">Toggle</button>"));
+        assert!(output
+            .html
+            .contains("<button id=\"hrml-0\" type=\"button\">Toggle</button>"));
         assert!(output.html.contains("<div id=\"hrml-1\">Content</div>"));
         assert!(output.js.contains("hrml.state({ visible: true })"));
         assert!(output.js.contains("_s.visible = !_s.visible"));
         assert!(output.js.contains("hrml.show("));
     }
 
+    #[test]
+    fn test_compile_ternary_interpolation() {
+        let doc = parse(
+            "state\n  count: 0\n\nspan \"{count > 0 ? 'a' : 'b'}\"\np \"{count > 1 ? count + ' items' : 'one'}\"",
+        );
+        let output = compile(&doc).unwrap();
+
+        assert!(output
+            .js
+            .contains("hrml.text('hrml-0', () => `${_s.count > 0 ? 'a' : 'b'}`);"));
+        assert!(output
+            .js
+            .contains("`${_s.count > 1 ? _s.count + ' items' : 'one'}`"));
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+    }
+
+    #[test]
+    fn test_compile_show_hidden() {
+        let doc = parse("state\n  open: false\n\nnav :show.hidden=\"open\" \"Menu\"");
+        let output = compile(&doc).unwrap();
+
+        assert!(output
+            .js
+            .contains("hrml.prop('hrml-0', 'hidden', () => !(_s.open));"));
+        assert!(!output.js.contains("hrml.show("));
+    }
+
     #[test]
     fn test_compile_input_binding() {
         let doc = parse(
@@ -569,4 +1926,387 @@ mod tests {
         assert!(output.js.contains("hrml.model("));
         assert!(output.js.contains("Hello ${_s.name}!"));
     }
+
+    // =========================================================================
+    // Standalone document
+    // =========================================================================
+
+    #[test]
+    fn test_standalone_document() {
+        let doc = parse("state\n  count: 0\n\nspan \"{count}\"");
+        let output = compile(&doc).unwrap();
+        let page = output.standalone("counter");
+
+        assert!(page.starts_with("<!DOCTYPE html>\n"));
+        assert!(page.contains("<title>counter</title>"));
+        assert!(page.contains("<body>\n<span id=\"hrml-0\"></span>\n"));
+        assert!(page.contains(&format!("<script>\n{}</script>", output.js)));
+        assert!(page.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_standalone_keeps_imported_document() {
+        let source = "state\n  n: 0\n\nraw\n  <!DOCTYPE html>\n  <html>\n  <!-- from the old site {n} -->\n  <body>\n  </body>\n  </html>\nspan \"{n}\"";
+        let output = compile(&parse(source)).unwrap();
+        let page = output.standalone("import");
+        assert_eq!(page.matches("<!DOCTYPE").count(), 1);
+        assert!(page.starts_with("<!DOCTYPE html>\n<html>\n<!-- from the old site {n} -->\n"));
+        assert!(!page.contains("<title>"));
+        assert!(page.contains(&format!("<script>\n{}</script>\n</body>", output.js)));
+        assert!(page.contains("<span id=\"hrml-0\"></span>\n<script>"));
+        assert!(page.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_standalone_adds_page_head_to_imported_document() {
+        let source = "page\n  title: \"New\"\n  description: \"Moved\"\n\nhead\n  raw \"<link rel=\\\"icon\\\" href=\\\"/i.png\\\">\"\n\nraw\n  <!DOCTYPE html>\n  <html>\n  <head><meta charset=\"utf-8\"></head>\n  <body></body>\n  </html>";
+        let page = compile(&parse(source)).unwrap().standalone("import");
+        assert_eq!(
+            page,
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\">\n  <meta name=\"description\" content=\"Moved\">\n  <title>New</title>\n  <link rel=\"icon\" href=\"/i.png\">\n</head>\n<body></body>\n</html>\n"
+        );
+
+        let source = "page\n  title: \"New\"\n\nraw\n  <!DOCTYPE html>\n  <html><head><title>Old</title></head></html>";
+        let page = compile(&parse(source)).unwrap().standalone("import");
+        assert!(!page.contains("New"));
+    }
+
+    #[test]
+    fn test_standalone_default_head() {
+        let page = compile(&parse("p \"Hi\"")).unwrap().standalone("hi");
+        assert!(page.contains("<html>\n<head>\n  <meta charset=\"UTF-8\">\n"));
+        assert!(page.contains(
+            "  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n"
+        ));
+    }
+
+    #[test]
+    fn test_standalone_page_block() {
+        let doc = parse(
+            "page\n  title: \"Q&A\"\n  lang: en\n  charset: \"ISO-8859-1\"\n  viewport: \"width=500\"\n  description: \"Questions\"\n\np \"Hi\"",
+        );
+        let page = compile(&doc).unwrap().standalone("qa");
+        assert!(page.contains("<html lang=\"en\">\n"));
+        assert!(page.contains("  <meta charset=\"ISO-8859-1\">\n"));
+        assert!(page.contains("  <meta name=\"viewport\" content=\"width=500\">\n"));
+        assert!(page.contains("  <meta name=\"description\" content=\"Questions\">\n"));
+        assert!(page.contains("<title>Q&amp;A</title>"));
+    }
+
+    #[test]
+    fn test_standalone_head_block() {
+        let doc = parse("head\n  raw \"<link rel=stylesheet href=/app.css>\"\n  raw\n    <script src=\"/a.js\"></script>\n    <meta name=\"x\" content=\"1\">\n\np \"Hi\"");
+        let output = compile(&doc).unwrap();
+        assert_eq!(output.html, "<p>Hi</p>\n");
+        assert!(output.standalone("hi").contains(
+            "  <title>hi</title>\n  <link rel=stylesheet href=/app.css>\n  <script src=\"/a.js\"></script>\n  <meta name=\"x\" content=\"1\">\n</head>\n"
+        ));
+    }
+
+    #[test]
+    fn test_head_block_rejects_closing_head_and_elements() {
+        let err = compile(&parse("head\n  raw \"</HEAD><body>\"")).unwrap_err();
+        assert!(err.message.contains("must not contain </head>"));
+        let err = compile(&parse("head\n  link rel=stylesheet")).unwrap_err();
+        assert!(err.message.contains("may only contain raw content"));
+    }
+
+    #[test]
+    fn test_standalone_without_js_has_no_script() {
+        let output = compile(&parse("p \"Static\"")).unwrap();
+        let page = output.standalone("static");
+        assert!(page.contains("<p>Static</p>"));
+        assert!(!page.contains("<script>"));
+        assert!(!page.contains("<style>"));
+    }
+
+    #[test]
+    fn test_sort_state_alphabetizes_keys() {
+        let doc = parse("state\n  zoom: 1\n  apple: 2\n  mid: 3\n\np \"{zoom} {apple}\"");
+        let options = CodegenOptions {
+            sort_state: true,
+            ..Default::default()
+        };
+        let js = compile_with_options(&doc, &options).unwrap().js;
+        assert!(js.contains("hrml.state({ apple: 2, mid: 3, zoom: 1 })"));
+        assert!(js.contains("`${_s.zoom} ${_s.apple}`"));
+
+        let js = compile(&doc).unwrap().js;
+        assert!(js.contains("hrml.state({ zoom: 1, apple: 2, mid: 3 })"));
+    }
+
+    // =========================================================================
+    // Escaped braces
+    // =========================================================================
+
+    #[test]
+    fn test_escaped_braces_render_literally() {
+        let output = compile(&parse("p \"price is \\{50\\}\"")).unwrap();
+        assert_eq!(output.html, "<p>price is {50}</p>\n");
+        assert_eq!(output.js, "");
+    }
+
+    #[test]
+    fn test_escaped_braces_beside_interpolation() {
+        let output = compile(&parse("state\n  n: 1\n\np \"\\{n\\} = {n}\"")).unwrap();
+        assert_eq!(output.html, "<p id=\"hrml-0\"></p>\n");
+        assert!(output.js.contains("hrml.text('hrml-0', () => `{n} = ${_s.n}`)"));
+    }
+
+    #[test]
+    fn test_escaped_braces_in_state_value() {
+        let output = compile(&parse("state\n  s: \"\\{x\\}\"\n\np \"{s}\"")).unwrap();
+        assert!(output.js.contains("hrml.state({ s: '{x}' })"));
+    }
+
+    #[test]
+    fn test_escaped_backslash_before_interpolation() {
+        let output = compile(&parse("state\n  dir: \"tmp\"\n\np \"C:\\\\{dir}\"")).unwrap();
+        assert_eq!(output.html, "<p id=\"hrml-0\"></p>\n");
+        assert!(output
+            .js
+            .contains("hrml.text('hrml-0', () => `C:\\\\${_s.dir}`)"));
+        assert!(output.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_control_escapes_survive_to_js() {
+        let source =
+            "state\n  s: \"a\\0b\\bc\\fd\\ve\"\n\np :show=\"s !== '\\b'\" \"\\0\\b\\f\\v{s}\"";
+        let output = compile(&parse(source)).unwrap();
+        assert!(output
+            .js
+            .contains("hrml.state({ s: 'a\\u0000b\\bc\\fd\\ve' })"));
+        assert!(output.js.contains("() => _s.s !== '\\b'"));
+        assert!(output.js.contains("() => `\\u0000\\b\\f\\v${_s.s}`"));
+    }
+
+    #[test]
+    fn test_template_text_escapes_backslash_backtick_and_dollar_brace() {
+        let source = "state\n  n: 1\n\np \"C:\\\\ `$\\{n\\}` {n}\"";
+        let output = compile(&parse(source)).unwrap();
+        assert!(output.js.contains("() => `C:\\\\ \\`\\${n}\\` ${_s.n}`"));
+    }
+
+    // =========================================================================
+    // Warnings
+    // =========================================================================
+
+    #[test]
+    fn test_unknown_interpolation_warns() {
+        let output = compile(&parse("state\n  user: 'Ada'\n\np \"Hello {usr}\"")).unwrap();
+        assert_eq!(
+            output.warnings,
+            vec!["Unknown name 'usr' in interpolation on <p>; declare it in state or computed"]
+        );
+    }
+
+    #[test]
+    fn test_known_interpolation_does_not_warn() {
+        let output = compile(&parse(
+            "state\n  user: 'Ada'\n\ncomputed\n  greeting: user\n\np \"{greeting}, {user.length} {Math.max(1, 2)}\"",
+        ))
+        .unwrap();
+        assert!(output.warnings.is_empty());
+        assert!(output.js.contains("${_s.greeting}"));
+    }
+
+    #[test]
+    fn test_nan_and_infinity_interpolation_does_not_warn() {
+        let output = compile(&parse(
+            "state\n  n: 0\n\np \"{n === Infinity || isNaN(n) ? NaN : n}\"",
+        ))
+        .unwrap();
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+    }
+
+    #[test]
+    fn test_chained_comparison_warns() {
+        let output = compile(&parse(
+            "state\n  a: 1\n  b: 2\n  c: 3\n\np :show=\"a < b < c\" \"in order\"",
+        ))
+        .unwrap();
+        assert_eq!(
+            output.warnings,
+            vec![
+                "`a < b < c` compares the result of `a < b` with `c`; did you mean `a < b && b < c`?"
+            ]
+        );
+
+        let output = compile(&parse(
+            "state\n  a: 1\n  b: 2\n  c: 3\n\np :show=\"a < b && b < c\" \"in order\"",
+        ))
+        .unwrap();
+        assert!(output.warnings.is_empty());
+    }
+
+    // =========================================================================
+    // Computed dependencies
+    // =========================================================================
+
+    #[test]
+    fn test_computed_reads_other_computed() {
+        let doc = parse("state\n  price: 10\n\ncomputed\n  a: price\n  b: a");
+        let output = compile(&doc).unwrap();
+        assert!(output.js.contains("'a', { get: hrml.computed(() => _s.price)"));
+        assert!(output.js.contains("'b', { get: hrml.computed(() => _s.a)"));
+    }
+
+    #[test]
+    fn test_quoted_computed_compiles_as_expression() {
+        let doc = parse("state\n  count: 0\n  hidden: false\n\ncomputed\n  positive: \"count > 0 && !hidden\"\n\np :show=\"positive\" \"Yes\"");
+        let output = compile(&doc).unwrap();
+        assert!(output
+            .js
+            .contains("'positive', { get: hrml.computed(() => _s.count > 0 && !_s.hidden)"));
+    }
+
+    #[test]
+    fn test_single_quoted_computed_is_a_string() {
+        let doc = parse("computed\n  sep: ' - '\n\np \"{sep}\"");
+        let output = compile(&doc).unwrap();
+        assert!(output
+            .js
+            .contains("'sep', { get: hrml.computed(() => ' - ')"));
+        assert!(hrml_parser::Parser::parse("computed\n  sep: \" - \"").is_err());
+    }
+
+    #[test]
+    fn test_computed_ordered_by_dependency() {
+        let doc = parse("state\n  price: 10\n\ncomputed\n  b: a\n  a: price");
+        let output = compile(&doc).unwrap();
+        let a = output.js.find("'a', { get: hrml.computed(() => _s.price)").unwrap();
+        let b = output.js.find("'b', { get: hrml.computed(() => _s.a)").unwrap();
+        assert!(a < b, "dependency `a` must be defined before `b`");
+    }
+
+    #[test]
+    fn test_computed_cycle_is_error() {
+        let doc = parse("computed\n  a: b\n  b: a");
+        let err = compile(&doc).unwrap_err();
+        assert!(err.message.contains("a -> b -> a"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_const_is_read_without_state_prefix() {
+        let doc = parse("const\n  MAX: 100\n\nstate\n  count: 0\n\ncomputed\n  left: MAX - count\n\np \"{count} of {MAX}\"");
+        let output = compile(&doc).unwrap();
+        let constant = output.js.find("const MAX = 100;").unwrap();
+        let state = output.js.find("hrml.state(").unwrap();
+        assert!(constant < state, "constants must be defined before state");
+        assert!(output.js.contains("hrml.computed(() => MAX - _s.count)"));
+        assert!(output.js.contains("${_s.count} of ${MAX}"));
+        assert!(!output.js.contains("_s.MAX"));
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+    }
+
+    #[test]
+    fn test_const_named_like_state_is_error() {
+        let doc = parse("const\n  count: 1\n\nstate\n  count: 0");
+        let err = compile(&doc).unwrap_err();
+        assert!(err.message.contains("both as a const"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_compile_lenient_keeps_the_rest_of_the_document() {
+        use hrml_parser::ast::Component;
+
+        let mut doc = parse(
+            "head\n  p \"not raw\"\n\nstate\n  count: 0\n\nmain\n  h1 \"Title\"\n  p \"{count}\"",
+        );
+        let Node::Element(main) = &mut doc.nodes[2] else {
+            panic!("expected <main>");
+        };
+        main.children.insert(
+            1,
+            Node::Component(Component {
+                name: "Card".into(),
+                props: Vec::new(),
+                children: Vec::new(),
+            }),
+        );
+        assert!(compile(&doc).is_err());
+
+        let (output, errors) = compile_lenient(&doc, &CodegenOptions::default());
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.contains("raw content"));
+        assert!(errors[1].message.contains("Component 'Card'"));
+        assert_eq!(
+            output.html,
+            "<main>\n  <h1>Title</h1>\n  <!-- hrml: compile error -->\n  <p id=\"hrml-0\"></p>\n</main>\n"
+        );
+        assert!(output.js.contains("hrml.text('hrml-0'"));
+    }
+
+    #[test]
+    fn test_compile_lenient_without_errors_matches_compile() {
+        let doc = parse("state\n  count: 0\n\nbutton @click=\"count++\" \"{count}\"");
+        let (output, errors) = compile_lenient(&doc, &CodegenOptions::default());
+        assert!(errors.is_empty());
+        assert_eq!(output, compile(&doc).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_compile_from_json_matches_compiling_the_source() {
+        let sources = [
+            "page\n  title: \"Todos\"\n\nconst\n  MAX: 10\n\nstate\n  items: ['a', 'b']\n  draft: \"\"\n  open: true\n  attrs: { title: \"Show\" }\n\ncomputed\n  left: MAX - items.length\n\neffect\n  document.title = left + \" left\"\n\nmain .app\n  input :model=\"draft\" @keydown.enter=\"items.push(draft)\"\n  ul :if.keepalive=\"open\"\n    li :for=\"(item, i) in items\" :key=\"i\" \"{i}: {item | upper}\"\n  button @click=\"open = !open\" {...attrs} \"Toggle\"",
+            "raw\n  <!DOCTYPE html>\n  <html><body></body></html>",
+            "// note\ndiv\n  \"Read the \"\n  a href=\"/docs\" tabindex=-1 \"docs\"",
+        ];
+        for source in sources {
+            let doc = parse(source);
+            let json = serde_json::to_string(&doc).unwrap();
+            assert_eq!(compile_from_json(&json).unwrap(), compile(&doc).unwrap());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_compile_from_json_rejects_invalid_json() {
+        let err = compile_from_json("{\"nodes\": 1}").unwrap_err();
+        assert!(err.message.starts_with("Invalid document JSON"));
+    }
+
+    #[test]
+    fn test_strip_comments_leaves_no_comments() {
+        use hrml_parser::ast::Component;
+
+        let source =
+            "state\n  body: ''\n\n// a note\nmain\n  article :html=\"body\"\n  p \"{body}\"";
+        let output = compile(&parse(source)).unwrap();
+        assert!(output.js.contains("/*") && output.js.contains("// :html"));
+
+        let mut doc = parse(source);
+        let Some(Node::Element(main)) = doc.nodes.last_mut() else {
+            panic!("expected <main>");
+        };
+        main.children.push(Node::Component(Component {
+            name: "Card".into(),
+            props: Vec::new(),
+            children: Vec::new(),
+        }));
+        for minify in [false, true] {
+            let options = CodegenOptions {
+                strip_comments: true,
+                minify,
+                ..Default::default()
+            };
+            let (output, errors) = compile_lenient(&doc, &options);
+            assert_eq!(errors.len(), 1);
+            let page = output.standalone("page");
+            for comment in ["/*", "<!--", "//"] {
+                assert!(!page.contains(comment), "{comment} in {page}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_compile_state_string_with_newline() {
+        let doc = parse("state\n  msg: \"line one\\nline\u{2028}two\"\n\nspan \"{msg}\"");
+        let output = compile(&doc).unwrap();
+        assert!(output
+            .js
+            .contains("hrml.state({ msg: 'line one\\nline\\u2028two' })"));
+    }
 }
@@ -4,6 +4,12 @@
 //! Handles indentation-based structure, element names, CSS class prefixes,
 //! the three HRML prefixes (`:` `@` `$`), string literals, and interpolation.
 //!
+//! Identifiers are Unicode: a letter or `_`, then letters, digits, or `_`
+//! (`größe`, `名前`). Element and class names may also contain inner hyphens.
+//!
+//! Columns are 1-based; a tab mid-line advances to the next tab stop
+//! ([`Scanner::DEFAULT_TAB_WIDTH`] unless set with [`Scanner::with_tab_width`]).
+//!
 //! # Example
 //!
 //! ```
@@ -12,18 +18,67 @@
 //! let tokens = Scanner::tokenize("").unwrap();
 //! assert_eq!(tokens.len(), 1); // Just EOF
 //! ```
+//!
+//! To read the tokens with lookahead, as the parser does, wrap them in a
+//! [`TokenStream`].
+//!
+//! # `no_std`
+//!
+//! With default features off the crate is `no_std` and needs only `alloc`;
+//! [`LexerError`] then implements `Display` by hand instead of via thiserror.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 pub mod scanner;
+pub mod stream;
 pub mod token;
 
-pub use scanner::Scanner;
-pub use token::{Span, Token, TokenKind};
+use alloc::string::String;
+
+pub use scanner::{next_tab_stop, Scanner};
+pub use stream::TokenStream;
+pub use token::{unescape_braces, Span, Token, TokenKind, ESCAPED_CLOSE_BRACE, ESCAPED_OPEN_BRACE};
 
 /// Lexer error with position information.
-#[derive(Debug, Clone, PartialEq, thiserror::Error)]
-#[error("Lexer error at line {line}, column {column}: {message}")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[cfg_attr(
+    feature = "std",
+    error("Lexer error at line {line}, column {column}: {message}")
+)]
 pub struct LexerError {
     pub message: String,
     pub line: usize,
     pub column: usize,
 }
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for LexerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Lexer error at line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::error::Error for LexerError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Also run with `--no-default-features`, where `Display` is hand-written.
+    #[test]
+    fn test_error_display() {
+        let err = Scanner::tokenize("p \"open").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Lexer error at line 1, column 3: {}", err.message)
+        );
+    }
+}
@@ -1,5 +1,8 @@
-use crate::token::{Span, Token, TokenKind};
+use crate::token::{Span, Token, TokenKind, ESCAPED_CLOSE_BRACE, ESCAPED_OPEN_BRACE};
 use crate::LexerError;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
 
 /// Scanner mode determines how braces are interpreted.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -10,6 +13,14 @@ pub enum ScannerMode {
     Expression,
 }
 
+/// The column reached by a tab at `column`, with stops every `tab_width`.
+/// Columns are 1-based: a tab at column 1 with a width of 8 reaches column 9.
+/// A `column` of 0 is read as 1 and a `tab_width` of 0 as 1.
+pub fn next_tab_stop(column: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    (column.max(1) - 1) / tab_width * tab_width + tab_width + 1
+}
+
 /// HRML source scanner.
 ///
 /// Tokenizes `.hrml` source files into a stream of tokens.
@@ -21,8 +32,7 @@ pub enum ScannerMode {
 /// - Stack-based indentation tracking
 /// - Mode-aware brace handling
 /// - Position tracking on every token
-pub struct Scanner<'a> {
-    source: &'a str,
+pub struct Scanner {
     chars: Vec<char>,
     pos: usize,
     line: usize,
@@ -31,13 +41,21 @@ pub struct Scanner<'a> {
     indent_stack: Vec<usize>,
     at_line_start: bool,
     mode: ScannerMode,
+    tab_width: usize,
+    /// The keyword (`state`, `computed`, `page`, ...) of the top-level block
+    /// being scanned. In `computed` and `effect` blocks, and for array and
+    /// object values in `state` and `const`, expressions are kept as written
+    /// for the expression parser.
+    block: Option<TokenKind>,
 }
 
-impl<'a> Scanner<'a> {
+impl Scanner {
+    /// Default spacing of tab stops when reporting columns after a mid-line tab.
+    pub const DEFAULT_TAB_WIDTH: usize = 8;
+
     /// Create a new scanner for the given source.
-    pub fn new(source: &'a str) -> Self {
+    pub fn new(source: &str) -> Self {
         Self {
-            source,
             chars: source.chars().collect(),
             pos: 0,
             line: 1,
@@ -46,11 +64,19 @@ impl<'a> Scanner<'a> {
             indent_stack: vec![0],
             at_line_start: true,
             mode: ScannerMode::Html,
+            tab_width: Self::DEFAULT_TAB_WIDTH,
+            block: None,
         }
     }
 
+    /// Set the tab stop spacing used for column numbers after a mid-line tab.
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width.max(1);
+        self
+    }
+
     /// Create a scanner with a specific mode.
-    pub fn with_mode(source: &'a str, mode: ScannerMode) -> Self {
+    pub fn with_mode(source: &str, mode: ScannerMode) -> Self {
         let mut scanner = Self::new(source);
         scanner.mode = mode;
         scanner
@@ -58,9 +84,13 @@ impl<'a> Scanner<'a> {
 
     /// Tokenize the entire source into a vector of tokens.
     pub fn tokenize(source: &str) -> Result<Vec<Token>, LexerError> {
-        let mut scanner = Scanner::new(source);
-        scanner.scan_tokens()?;
-        Ok(scanner.tokens)
+        Scanner::new(source).scan()
+    }
+
+    /// Tokenize the source given to [`Scanner::new`].
+    pub fn scan(mut self) -> Result<Vec<Token>, LexerError> {
+        self.scan_tokens()?;
+        Ok(self.tokens)
     }
 
     /// Scan all tokens from the source.
@@ -87,6 +117,18 @@ impl<'a> Scanner<'a> {
             if self.is_at_end() {
                 return Ok(());
             }
+            if self.peek() == '\\' && matches!(self.peek_next(), '@' | '$' | ':' | '.') {
+                return self.scan_escaped_line();
+            }
+            // Each line of an `effect` block is a statement
+            if self.block == Some(TokenKind::Effect)
+                && self.indent_stack.len() == 2
+                && !matches!(self.peek(), '\n' | '\r')
+                && !(self.peek() == '/' && self.peek_next() == '/')
+            {
+                self.scan_expr_text();
+                return Ok(());
+            }
         }
 
         let ch = self.peek();
@@ -129,8 +171,9 @@ impl<'a> Scanner<'a> {
             // Interpolation in HTML mode
             '{' if self.mode == ScannerMode::Html => self.scan_interpolation(),
 
-            // Numbers
+            // Numbers, and a negative one right after `=` (`tabindex=-1`)
             '0'..='9' => self.scan_number(),
+            '-' if self.peek_next().is_ascii_digit() && self.after_equals() => self.scan_number(),
 
             // Prefixes
             '.' => {
@@ -139,8 +182,12 @@ impl<'a> Scanner<'a> {
                 Ok(())
             }
             ':' => {
+                let field_value = self.at_expr_field();
                 self.emit(TokenKind::Colon);
                 self.advance();
+                if field_value {
+                    self.scan_expr_text();
+                }
                 Ok(())
             }
             '@' => {
@@ -189,7 +236,80 @@ impl<'a> Scanner<'a> {
             // Identifiers and keywords
             c if c.is_alphabetic() || c == '_' => self.scan_identifier(),
 
-            _ => Err(self.error(format!("Unexpected character: '{ch}'"))),
+            _ => {
+                if let Some(name) = self.unquoted_directive() {
+                    return Err(self.error(format!(
+                        "Unexpected character '{ch}' after the unquoted value of {name}; \
+                         quote the whole expression (e.g. {name}=\"count > 0\")"
+                    )));
+                }
+                if self.in_unquoted_value() {
+                    return Err(self.error(format!(
+                        "Unexpected character '{ch}' in unquoted attribute value; \
+                         quote paths and URLs (e.g. href=\"/about\")"
+                    )));
+                }
+                Err(self.error(format!("Unexpected character: '{ch}'")))
+            }
+        }
+    }
+
+    /// The `:name`, `@name`, or `$name` (with any modifiers) whose unquoted
+    /// value was the last token scanned, as in `:show=count`.
+    fn unquoted_directive(&self) -> Option<String> {
+        let n = self.tokens.len();
+        let value = self.tokens.last()?;
+        if n < 4
+            || !matches!(
+                value.kind,
+                TokenKind::Identifier(_) | TokenKind::Number(_) | TokenKind::Boolean(_)
+            )
+            || self.tokens[n - 2].kind != TokenKind::Equals
+        {
+            return None;
+        }
+        // Walk back over the name and modifiers, written without spaces.
+        // Single-character tokens are emitted with an empty span.
+        let adjacent = |i: usize| {
+            let prev = self.tokens[i - 1].span;
+            prev.end.max(prev.start + 1) == self.tokens[i].span.start
+        };
+        let mut i = n - 2;
+        while i > 0 && adjacent(i) {
+            i -= 1;
+            match self.tokens[i].kind {
+                TokenKind::Colon | TokenKind::At | TokenKind::Dollar => {
+                    // `xlink:href=` is a namespaced attribute, not a directive
+                    if i > 0 && adjacent(i) {
+                        return None;
+                    }
+                    let end = self.tokens[n - 2].span.start;
+                    return Some(self.chars[self.tokens[i].span.start..end].iter().collect());
+                }
+                TokenKind::Identifier(_) | TokenKind::Dot | TokenKind::Number(_) => {}
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Whether the current position directly follows `=`.
+    fn after_equals(&self) -> bool {
+        matches!(self.tokens.last(), Some(t) if t.kind == TokenKind::Equals && t.span.start + 1 == self.pos)
+    }
+
+    /// Whether the current position continues an unquoted attribute value
+    /// (`href=/about`, `src=logo/x`), i.e. directly follows `=` or a value after `=`.
+    fn in_unquoted_value(&self) -> bool {
+        let n = self.tokens.len();
+        match self.tokens.last() {
+            Some(t) if t.kind == TokenKind::Equals => t.span.start + 1 == self.pos,
+            Some(t) if matches!(t.kind, TokenKind::Identifier(_) | TokenKind::Number(_)) => {
+                t.span.end == self.pos
+                    && n >= 2
+                    && self.tokens[n - 2].kind == TokenKind::Equals
+            }
+            _ => false,
         }
     }
 
@@ -205,37 +325,43 @@ impl<'a> Scanner<'a> {
             spaces += 1;
         }
 
+        // Trailing whitespace on a blank line (including a final line without
+        // a newline) never changes indentation, tabs included
+        if self.rest_of_line_is_blank() {
+            while !self.is_at_end() && matches!(self.peek(), ' ' | '\t') {
+                self.advance();
+            }
+            return Ok(());
+        }
+
         // Skip tabs (count as error for now, HRML uses spaces)
         if !self.is_at_end() && self.peek() == '\t' {
             return Err(self.error("Tabs are not allowed for indentation, use spaces".into()));
         }
 
-        // Skip blank lines (just whitespace then newline or EOF)
-        if self.is_at_end() || self.peek() == '\n' || self.peek() == '\r' {
-            return Ok(());
-        }
-
         // Skip comment-only lines (don't affect indentation)
         if self.peek() == '/' && self.peek_next() == '/' {
             return Ok(());
         }
 
-        let current_indent = *self.indent_stack.last().expect("indent stack never empty");
+        if spaces == 0 {
+            self.block = None;
+        }
+
+        let current_indent = self.current_indent();
 
         if spaces > current_indent {
             self.indent_stack.push(spaces);
             self.emit(TokenKind::Indent);
         } else if spaces < current_indent {
             // Pop multiple levels if needed
-            while self.indent_stack.len() > 1
-                && *self.indent_stack.last().expect("indent stack never empty") > spaces
-            {
+            while self.indent_stack.len() > 1 && self.current_indent() > spaces {
                 self.indent_stack.pop();
                 self.emit(TokenKind::Dedent);
             }
 
             // Validate alignment
-            if *self.indent_stack.last().expect("indent stack never empty") != spaces {
+            if self.current_indent() != spaces {
                 return Err(self.error(format!(
                     "Indentation does not match any outer level (got {spaces} spaces)"
                 )));
@@ -245,10 +371,22 @@ impl<'a> Scanner<'a> {
         Ok(())
     }
 
+    /// Whether only spaces/tabs remain before the next newline or EOF.
+    fn rest_of_line_is_blank(&self) -> bool {
+        self.chars[self.pos..]
+            .iter()
+            .take_while(|c| !matches!(c, '\n' | '\r'))
+            .all(|c| matches!(c, ' ' | '\t'))
+    }
+
     // --- Scanners ---
 
     /// Scan a string literal. Strings carry raw content including `{expr}` markers.
     /// The parser is responsible for splitting interpolation segments.
+    ///
+    /// Escaped braces become [`ESCAPED_OPEN_BRACE`] and [`ESCAPED_CLOSE_BRACE`] so
+    /// interpolation-aware consumers can tell them from `{expr}` markers; others
+    /// use [`crate::unescape_braces`].
     fn scan_string(&mut self) -> Result<(), LexerError> {
         let quote = self.peek();
         let start_line = self.line;
@@ -256,7 +394,7 @@ impl<'a> Scanner<'a> {
         let start_pos = self.pos;
         self.advance(); // consume opening quote
 
-        let mut value = std::string::String::new();
+        let mut value = String::new();
 
         while !self.is_at_end() && self.peek() != quote {
             if self.peek() == '\\' {
@@ -272,9 +410,14 @@ impl<'a> Scanner<'a> {
                     'n' => value.push('\n'),
                     't' => value.push('\t'),
                     'r' => value.push('\r'),
+                    '0' => value.push('\0'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'v' => value.push('\u{b}'),
                     '\\' => value.push('\\'),
-                    '{' => value.push('{'),
-                    '}' => value.push('}'),
+                    // Kept apart: `{` would otherwise read as interpolation
+                    '{' => value.push(ESCAPED_OPEN_BRACE),
+                    '}' => value.push(ESCAPED_CLOSE_BRACE),
                     c if c == quote => value.push(c),
                     c => {
                         value.push('\\');
@@ -303,19 +446,59 @@ impl<'a> Scanner<'a> {
         Ok(())
     }
 
-    /// Scan interpolation `{expr}` in HTML mode. Tracks brace depth for nesting.
+    /// Scan a content line written with a leading backslash (`\@mention`), so
+    /// that it can start with a prefix character. The rest of the line, minus
+    /// the backslash, becomes a `String` token: text with `{expr}` markers.
+    fn scan_escaped_line(&mut self) -> Result<(), LexerError> {
+        let start_line = self.line;
+        let start_col = self.column;
+        let start_pos = self.pos;
+        self.advance(); // consume backslash
+
+        let mut value = String::new();
+        while !self.is_at_end() && !matches!(self.peek(), '\n' | '\r') {
+            value.push(self.peek());
+            self.advance();
+        }
+        value.truncate(value.trim_end().len());
+
+        let span = Span::new(start_pos, self.pos, start_line, start_col);
+        self.tokens.push(Token::new(TokenKind::String(value), span));
+        Ok(())
+    }
+
+    /// Scan interpolation `{expr}` in HTML mode. Tracks brace depth for nesting;
+    /// braces inside string literals (`{label('}')}`) don't count.
     fn scan_interpolation(&mut self) -> Result<(), LexerError> {
         let start_line = self.line;
         let start_col = self.column;
         let start_pos = self.pos;
         self.advance(); // consume opening `{`
 
-        let mut content = std::string::String::new();
+        let mut content = String::new();
         let mut depth = 1;
+        let mut quote = None;
 
         while !self.is_at_end() && depth > 0 {
             let c = self.peek();
             match c {
+                // Inside a string only its closing quote matters; an escaped
+                // character is kept with its backslash
+                _ if quote.is_some() && c != '\n' => {
+                    content.push(c);
+                    self.advance();
+                    if c == '\\' && !self.is_at_end() && self.peek() != '\n' {
+                        content.push(self.peek());
+                        self.advance();
+                    } else if quote == Some(c) {
+                        quote = None;
+                    }
+                }
+                '\'' | '"' | '`' => {
+                    quote = Some(c);
+                    content.push(c);
+                    self.advance();
+                }
                 '{' => {
                     depth += 1;
                     content.push(c);
@@ -357,12 +540,15 @@ impl<'a> Scanner<'a> {
 
     /// Scan an identifier or keyword. Supports hyphens when followed by alphanumeric
     /// (for CSS class names like `text-2xl`, `bg-blue-500`).
+    ///
+    /// Identifiers start with a Unicode letter or `_` and continue with Unicode
+    /// alphanumerics or `_`; the expression lexer uses the same rule.
     fn scan_identifier(&mut self) -> Result<(), LexerError> {
         let start_line = self.line;
         let start_col = self.column;
         let start_pos = self.pos;
 
-        let mut ident = std::string::String::new();
+        let mut ident = String::new();
         ident.push(self.peek());
         self.advance();
 
@@ -389,6 +575,10 @@ impl<'a> Scanner<'a> {
                     | TokenKind::Hash
             )
         });
+        let line_start = matches!(
+            self.tokens.last().map(|t| &t.kind),
+            None | Some(TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent)
+        );
         let kind = if after_prefix {
             match ident.as_str() {
                 "true" => TokenKind::Boolean(true),
@@ -396,24 +586,204 @@ impl<'a> Scanner<'a> {
                 "null" => TokenKind::Null,
                 _ => TokenKind::Identifier(ident),
             }
+        } else if line_start {
+            self.line_start_keyword(ident)
         } else {
             Self::keyword_or_ident(ident)
         };
+        let is_raw = kind == TokenKind::Raw;
+        if matches!(
+            kind,
+            TokenKind::State
+                | TokenKind::Const
+                | TokenKind::Computed
+                | TokenKind::Effect
+                | TokenKind::Page
+                | TokenKind::Config
+        ) && self.indent_stack.len() == 1
+            && line_start
+        {
+            self.block = Some(kind.clone());
+        }
         self.tokens.push(Token::new(kind, span));
+
+        if is_raw {
+            self.scan_raw_block();
+        }
         Ok(())
     }
 
+    /// Whether the `:` at the current position follows the name of a field in
+    /// a `computed` block (`  total: price * qty`), or of a `state` or `const`
+    /// field whose value is an array or object literal (`  tags: ['a', 'b']`).
+    fn at_expr_field(&self) -> bool {
+        let n = self.tokens.len();
+        let literal = || {
+            let mut rest = self.chars[self.pos + 1..].iter();
+            matches!(rest.find(|c| !matches!(c, ' ' | '\t')), Some('[' | '{'))
+        };
+        let value_block = match self.block {
+            Some(TokenKind::Computed) => true,
+            Some(TokenKind::State | TokenKind::Const) => literal(),
+            _ => false,
+        };
+        value_block
+            && self.indent_stack.len() == 2
+            && n >= 2
+            && matches!(self.tokens[n - 1].kind, TokenKind::Identifier(_))
+            && matches!(
+                self.tokens[n - 2].kind,
+                TokenKind::Indent | TokenKind::Newline
+            )
+    }
+
+    /// Capture the rest of the line as written into an `ExprText` token, up
+    /// to a `//` comment outside quotes. The expression parser reads it, so
+    /// operators the source lexer doesn't know (`*`, `<`, `!`, ...) survive.
+    fn scan_expr_text(&mut self) {
+        while !self.is_at_end() && matches!(self.peek(), ' ' | '\t') {
+            self.advance();
+        }
+        let start_line = self.line;
+        let start_col = self.column;
+        let start_pos = self.pos;
+
+        let mut text = String::new();
+        let mut quote = None;
+        while !self.is_at_end() && !matches!(self.peek(), '\n' | '\r') {
+            let c = self.peek();
+            match quote {
+                None if c == '/' && self.peek_next() == '/' => break,
+                None if matches!(c, '"' | '\'' | '`') => quote = Some(c),
+                Some(q) if c == q => quote = None,
+                Some(_) if c == '\\' => {
+                    text.push(c);
+                    self.advance();
+                    if self.is_at_end() || matches!(self.peek(), '\n' | '\r') {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            text.push(self.peek());
+            self.advance();
+        }
+        text.truncate(text.trim_end().len());
+
+        let end = start_pos + text.chars().count();
+        let span = Span::new(start_pos, end, start_line, start_col);
+        self.tokens
+            .push(Token::new(TokenKind::ExprText(text), span));
+    }
+
+    /// Capture the indented block after a `raw` keyword verbatim.
+    ///
+    /// Lines indented deeper than the `raw` line are collected as-is (minus
+    /// their common indentation) into a single `RawText` token, so markup
+    /// inside is never tokenized. If `raw` is followed by anything other than
+    /// a line break (e.g. `raw "<hr>"`), scanning continues normally.
+    fn scan_raw_block(&mut self) {
+        let mut lookahead = self.pos;
+        while lookahead < self.chars.len() && matches!(self.chars[lookahead], ' ' | '\t') {
+            lookahead += 1;
+        }
+        if lookahead < self.chars.len() && !matches!(self.chars[lookahead], '\n' | '\r') {
+            return;
+        }
+
+        let base_indent = self.current_indent();
+        let mut lines: Vec<String> = Vec::new();
+        let mut content_lines = 0;
+        let mut block_end = self.pos;
+        let mut cursor = lookahead;
+
+        // Walk line by line until a non-blank line at or above the `raw` indent
+        while cursor < self.chars.len() {
+            // Step past the line break
+            if self.chars[cursor] == '\r' {
+                cursor += 1;
+            }
+            if cursor < self.chars.len() && self.chars[cursor] == '\n' {
+                cursor += 1;
+            }
+
+            let line_start = cursor;
+            while cursor < self.chars.len() && !matches!(self.chars[cursor], '\n' | '\r') {
+                cursor += 1;
+            }
+            let line: String = self.chars[line_start..cursor].iter().collect();
+
+            if line.trim().is_empty() {
+                lines.push(String::new());
+                continue;
+            }
+            let indent = line.chars().take_while(|c| *c == ' ').count();
+            if indent <= base_indent {
+                break;
+            }
+            lines.push(line);
+            content_lines = lines.len();
+            block_end = cursor;
+        }
+
+        if content_lines == 0 {
+            return;
+        }
+        lines.truncate(content_lines);
+
+        let strip = lines
+            .iter()
+            .filter(|l| !l.is_empty())
+            .map(|l| l.chars().take_while(|c| *c == ' ').count())
+            .min()
+            .unwrap_or(0);
+        let text = lines
+            .iter()
+            .map(|l| l.chars().skip(strip).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let start_pos = self.pos;
+        let start_line = self.line;
+        let start_col = self.column;
+        while self.pos < block_end {
+            if self.peek() == '\n' {
+                self.advance();
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.advance();
+            }
+        }
+
+        let span = Span::new(start_pos, self.pos, start_line, start_col);
+        self.tokens.push(Token::new(TokenKind::RawText(text), span));
+    }
+
     /// Scan a number literal (integer or float).
     fn scan_number(&mut self) -> Result<(), LexerError> {
         let start_line = self.line;
         let start_col = self.column;
         let start_pos = self.pos;
 
-        while !self.is_at_end() && (self.peek().is_ascii_digit() || self.peek() == '.') {
+        if self.peek() == '-' {
+            self.advance();
+        }
+
+        // A dot only continues the number before a digit, so the `.` in
+        // `.debounce.300.prevent` still starts the next modifier
+        let mut seen_dot = false;
+        while !self.is_at_end() {
+            let c = self.peek();
+            let fraction = c == '.' && !seen_dot && self.peek_next().is_ascii_digit();
+            if !c.is_ascii_digit() && !fraction {
+                break;
+            }
+            seen_dot |= c == '.';
             self.advance();
         }
 
-        let text = &self.source[start_pos..self.pos];
+        let text: String = self.chars[start_pos..self.pos].iter().collect();
         let value: f64 = text.parse().map_err(|_| LexerError {
             message: format!("Invalid number: '{text}'"),
             line: start_line,
@@ -440,7 +810,7 @@ impl<'a> Scanner<'a> {
             self.advance();
         }
 
-        let mut content = std::string::String::new();
+        let mut content = String::new();
         while !self.is_at_end() && self.peek() != '\n' && self.peek() != '\r' {
             content.push(self.peek());
             self.advance();
@@ -455,7 +825,10 @@ impl<'a> Scanner<'a> {
     // --- Keyword detection ---
 
     /// Determine if an identifier is a keyword or remains an identifier.
-    fn keyword_or_ident(ident: std::string::String) -> TokenKind {
+    /// `const`, `effect`, `raw`, and `fragment` are only keywords at the start
+    /// of a line (see [`Scanner::line_start_keyword`]), so elsewhere they stay
+    /// names (`div raw`).
+    fn keyword_or_ident(ident: String) -> TokenKind {
         match ident.as_str() {
             "state" => TokenKind::State,
             "computed" => TokenKind::Computed,
@@ -474,6 +847,22 @@ impl<'a> Scanner<'a> {
         }
     }
 
+    /// Keyword detection for the first word on a line. `const` and `effect`
+    /// open a block only at the top level, and `raw` and `fragment` start a
+    /// node anywhere but a block's fields, so `effect: "fade"` in a `state`
+    /// block is a field like any other.
+    fn line_start_keyword(&self, ident: String) -> TokenKind {
+        let top_level = self.indent_stack.len() == 1;
+        match ident.as_str() {
+            "const" if top_level => TokenKind::Const,
+            "effect" if top_level => TokenKind::Effect,
+            "raw" if top_level || self.block.is_none() => TokenKind::Raw,
+            "fragment" if top_level || self.block.is_none() => TokenKind::Fragment,
+            "const" | "effect" | "raw" | "fragment" => TokenKind::Identifier(ident),
+            _ => Self::keyword_or_ident(ident),
+        }
+    }
+
     // --- Helpers ---
 
     fn emit(&mut self, kind: TokenKind) {
@@ -499,8 +888,11 @@ impl<'a> Scanner<'a> {
 
     fn advance(&mut self) {
         if !self.is_at_end() {
+            self.column = match self.chars[self.pos] {
+                '\t' => next_tab_stop(self.column, self.tab_width),
+                _ => self.column + 1,
+            };
             self.pos += 1;
-            self.column += 1;
         }
     }
 
@@ -508,7 +900,13 @@ impl<'a> Scanner<'a> {
         self.pos >= self.chars.len()
     }
 
-    fn error(&self, message: std::string::String) -> LexerError {
+    /// Width of the innermost open indentation level. The stack starts at
+    /// `[0]` and the bottom entry is never popped.
+    fn current_indent(&self) -> usize {
+        self.indent_stack.last().copied().unwrap_or(0)
+    }
+
+    fn error(&self, message: String) -> LexerError {
         LexerError {
             message,
             line: self.line,
@@ -546,6 +944,14 @@ mod tests {
         assert_eq!(toks[0].kind, TokenKind::Eof);
     }
 
+    #[test]
+    fn test_whitespace_only_source() {
+        assert_eq!(
+            kinds("   \n\t\n  "),
+            vec![TokenKind::Newline, TokenKind::Newline, TokenKind::Eof]
+        );
+    }
+
     #[test]
     fn test_single_newline() {
         assert_eq!(kinds("\n"), vec![TokenKind::Newline, TokenKind::Eof]);
@@ -636,6 +1042,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dedent_two_then_indent_one() {
+        let k = kinds("a\n  b\n    c\nd\n  e");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Identifier("a".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Identifier("b".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Identifier("c".into()),
+                TokenKind::Newline,
+                TokenKind::Dedent,
+                TokenKind::Dedent,
+                TokenKind::Identifier("d".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Identifier("e".into()),
+                TokenKind::Dedent,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn test_eof_auto_closes_indents() {
         let k = kinds("a\n  b\n    c");
@@ -644,6 +1076,68 @@ mod tests {
         assert_eq!(*k.last().unwrap(), TokenKind::Eof);
     }
 
+    #[test]
+    fn test_trailing_spaces_at_eof() {
+        let k = kinds("a\n  b\n  ");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Identifier("a".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Identifier("b".into()),
+                TokenKind::Newline,
+                TokenKind::Dedent,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trailing_spaces_at_eof_top_level() {
+        let k = kinds("a\n  ");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Identifier("a".into()),
+                TokenKind::Newline,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trailing_tab_on_blank_line() {
+        let k = kinds("a\n  b\n\t\n  c\n\t");
+        assert_eq!(k.iter().filter(|t| **t == TokenKind::Indent).count(), 1);
+        assert_eq!(k.iter().filter(|t| **t == TokenKind::Dedent).count(), 1);
+    }
+
+    #[test]
+    fn test_comment_line_at_eof() {
+        let k = kinds("a\n  b\n// end");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Identifier("a".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Identifier("b".into()),
+                TokenKind::Newline,
+                TokenKind::Comment("end".into()),
+                TokenKind::Dedent,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blank_last_line_after_nesting() {
+        let k = kinds("a\n  b\n    c\n\n");
+        assert_eq!(k.iter().filter(|t| **t == TokenKind::Dedent).count(), 2);
+        assert_eq!(k[k.len() - 3..], [TokenKind::Dedent, TokenKind::Dedent, TokenKind::Eof]);
+    }
+
     #[test]
     fn test_blank_lines_ignored_for_indent() {
         let k = kinds("a\n\n  b");
@@ -745,6 +1239,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unicode_identifier() {
+        assert_eq!(
+            kinds("größe .café"),
+            vec![
+                TokenKind::Identifier("größe".into()),
+                TokenKind::Dot,
+                TokenKind::Identifier("café".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn test_identifier_with_underscore() {
         assert_eq!(
@@ -944,12 +1451,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_escape_control_chars() {
+        assert_eq!(
+            kinds("\"a\\0b\\bc\\fd\\ve\""),
+            vec![
+                TokenKind::String("a\0b\u{8}c\u{c}d\u{b}e".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn test_string_escape_braces() {
         assert_eq!(
             kinds("\"\\{not interpolation\\}\""),
             vec![
-                TokenKind::String("{not interpolation}".into()),
+                TokenKind::String("\u{E000}not interpolation\u{E001}".into()),
                 TokenKind::Eof,
             ]
         );
@@ -985,6 +1503,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_escaped_prefix_line_is_text() {
+        assert_eq!(
+            kinds("p\n  \\@team: ping {name} \n  \\$5 \"each\""),
+            vec![
+                TokenKind::Identifier("p".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::String("@team: ping {name}".into()),
+                TokenKind::Newline,
+                TokenKind::String("$5 \"each\"".into()),
+                TokenKind::Dedent,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backslash_mid_line_is_not_an_escape() {
+        assert!(Scanner::tokenize("p \\@x").is_err());
+    }
+
     // =========================================================================
     // Interpolation (HTML mode)
     // =========================================================================
@@ -1030,6 +1570,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_interpolation_braces_in_strings() {
+        assert_eq!(
+            kinds(r#"{label('}')} {fmt("{", `}`, '\'}')}"#),
+            vec![
+                TokenKind::Interpolation("label('}')".into()),
+                TokenKind::Interpolation(r#"fmt("{", `}`, '\'}')"#.into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn test_interpolation_unterminated() {
         let result = Scanner::tokenize("{count");
@@ -1049,11 +1601,81 @@ mod tests {
         assert_eq!(kinds("state"), vec![TokenKind::State, TokenKind::Eof]);
     }
 
+    #[test]
+    fn test_const_keyword() {
+        assert_eq!(kinds("const"), vec![TokenKind::Const, TokenKind::Eof]);
+    }
+
     #[test]
     fn test_computed_keyword() {
         assert_eq!(kinds("computed"), vec![TokenKind::Computed, TokenKind::Eof]);
     }
 
+    #[test]
+    fn test_computed_value_kept_as_written() {
+        let k = kinds("computed\n  a: x * 2 // twice\n  b: s == \"//\" && !done\n\np :x=\"1\"");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Computed,
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Identifier("a".into()),
+                TokenKind::Colon,
+                TokenKind::ExprText("x * 2".into()),
+                TokenKind::Comment("twice".into()),
+                TokenKind::Newline,
+                TokenKind::Identifier("b".into()),
+                TokenKind::Colon,
+                TokenKind::ExprText("s == \"//\" && !done".into()),
+                TokenKind::Newline,
+                TokenKind::Newline,
+                TokenKind::Dedent,
+                TokenKind::Identifier("p".into()),
+                TokenKind::Colon,
+                TokenKind::Identifier("x".into()),
+                TokenKind::Equals,
+                TokenKind::String("1".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_computed_value_span_covers_source() {
+        let source = "computed\n  ok: «a» > 0 && b   // note";
+        let tokens = Scanner::tokenize(source).unwrap();
+        let token = tokens
+            .iter()
+            .find(|t| matches!(t.kind, TokenKind::ExprText(_)))
+            .unwrap();
+        assert_eq!(token.kind, TokenKind::ExprText("«a» > 0 && b".into()));
+        assert_eq!((token.span.line, token.span.column), (2, 7));
+    }
+
+    #[test]
+    fn test_effect_lines_kept_as_written() {
+        let k = kinds("effect\n  // sync title\n  document.title = count * 2\n\n  log(!done)\np");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Effect,
+                TokenKind::Newline,
+                TokenKind::Comment("sync title".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::ExprText("document.title = count * 2".into()),
+                TokenKind::Newline,
+                TokenKind::Newline,
+                TokenKind::ExprText("log(!done)".into()),
+                TokenKind::Newline,
+                TokenKind::Dedent,
+                TokenKind::Identifier("p".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn test_fn_keyword() {
         assert_eq!(kinds("fn"), vec![TokenKind::Fn, TokenKind::Eof]);
@@ -1090,6 +1712,78 @@ mod tests {
         assert_eq!(kinds("null"), vec![TokenKind::Null, TokenKind::Eof]);
     }
 
+    // =========================================================================
+    // Raw blocks
+    // =========================================================================
+
+    #[test]
+    fn test_raw_keyword() {
+        assert_eq!(kinds("raw"), vec![TokenKind::Raw, TokenKind::Eof]);
+    }
+
+    #[test]
+    fn test_raw_inline_string() {
+        assert_eq!(
+            kinds("raw \"<hr>\""),
+            vec![TokenKind::Raw, TokenKind::String("<hr>".into()), TokenKind::Eof]
+        );
+    }
+
+    #[test]
+    fn test_raw_block_captured_verbatim() {
+        let k = kinds("raw\n  <svg viewBox=\"0 0 10 10\">\n    <path d=\"M0 0L10 10\"/>\n  </svg>\ndiv");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Raw,
+                TokenKind::RawText(
+                    "<svg viewBox=\"0 0 10 10\">\n  <path d=\"M0 0L10 10\"/>\n</svg>".into()
+                ),
+                TokenKind::Newline,
+                TokenKind::Identifier("div".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_raw_block_nested() {
+        let k = kinds("div\n  raw\n    <b>{x}</b>\n\n    <i>y</i>\n  span");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Identifier("div".into()),
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Raw,
+                TokenKind::RawText("<b>{x}</b>\n\n<i>y</i>".into()),
+                TokenKind::Newline,
+                TokenKind::Identifier("span".into()),
+                TokenKind::Dedent,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_raw_block_tracks_lines() {
+        let toks = tokens("raw\n  <p>\n  </p>\nspan");
+        let span = toks
+            .iter()
+            .find(|t| t.kind == TokenKind::Identifier("span".into()))
+            .unwrap();
+        assert_eq!(span.span.line, 4);
+        assert_eq!(span.span.column, 1);
+    }
+
+    #[test]
+    fn test_raw_after_prefix_is_identifier() {
+        assert_eq!(
+            kinds(".raw"),
+            vec![TokenKind::Dot, TokenKind::Identifier("raw".into()), TokenKind::Eof]
+        );
+    }
+
     // =========================================================================
     // Numbers
     // =========================================================================
@@ -1110,6 +1804,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_leading_dot_stays_a_class_prefix() {
+        assert_eq!(
+            kinds(".5"),
+            vec![TokenKind::Dot, TokenKind::Number(5.0), TokenKind::Eof]
+        );
+    }
+
+    #[test]
+    fn test_number_after_multibyte_text() {
+        assert_eq!(
+            kinds("é 1.5"),
+            vec![
+                TokenKind::Identifier("é".into()),
+                TokenKind::Number(1.5),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn test_zero() {
         assert_eq!(
@@ -1154,6 +1868,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_indented_comment_only_source() {
+        assert_eq!(
+            kinds("  // one\n\n    // two\n"),
+            vec![
+                TokenKind::Comment("one".into()),
+                TokenKind::Newline,
+                TokenKind::Newline,
+                TokenKind::Comment("two".into()),
+                TokenKind::Newline,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn test_comment_lines_dont_affect_indent() {
         let k = kinds("a\n  // comment\n  b");
@@ -1172,6 +1901,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trivia_and_structural_tokens() {
+        let k = kinds("a // note\n  b");
+        let trivia: Vec<_> = k.iter().filter(|t| t.is_trivia()).collect();
+        let structural: Vec<_> = k.iter().filter(|t| t.is_structural()).collect();
+        assert_eq!(
+            trivia,
+            vec![&TokenKind::Comment("note".into()), &TokenKind::Newline]
+        );
+        assert_eq!(
+            structural,
+            vec![&TokenKind::Indent, &TokenKind::Dedent, &TokenKind::Eof]
+        );
+        assert!(!TokenKind::Identifier("a".into()).is_trivia());
+        assert!(!TokenKind::String("// not a comment".into()).is_trivia());
+        assert!(!TokenKind::Newline.is_structural());
+    }
+
     // =========================================================================
     // Punctuation
     // =========================================================================
@@ -1198,6 +1945,40 @@ mod tests {
     // Error handling
     // =========================================================================
 
+    #[test]
+    fn test_unquoted_path_value_error() {
+        let err = Scanner::tokenize("a href=/about").unwrap_err();
+        assert!(err.message.contains("unquoted attribute value"), "{}", err.message);
+        assert_eq!(err.column, 8);
+
+        let err = Scanner::tokenize("a href=docs/intro").unwrap_err();
+        assert!(err.message.contains("unquoted attribute value"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_unquoted_directive_value_error() {
+        let err = Scanner::tokenize("p :show=count > 0").unwrap_err();
+        assert!(
+            err.message.contains("unquoted value of :show"),
+            "{}",
+            err.message
+        );
+
+        let err = Scanner::tokenize("p @click.prevent=open>0").unwrap_err();
+        assert!(
+            err.message.contains("unquoted value of @click.prevent"),
+            "{}",
+            err.message
+        );
+
+        let err = Scanner::tokenize("use xlink:href=icon>").unwrap_err();
+        assert!(
+            err.message.contains("unquoted attribute value"),
+            "{}",
+            err.message
+        );
+    }
+
     #[test]
     fn test_unexpected_character() {
         let result = Scanner::tokenize("~");
@@ -1205,6 +1986,25 @@ mod tests {
         assert!(result.unwrap_err().message.contains("Unexpected character"));
     }
 
+    #[test]
+    fn test_error_column_after_mid_line_tab() {
+        let err = Scanner::tokenize("div\t~").unwrap_err();
+        assert!(err.message.contains("Unexpected character"));
+        assert_eq!(err.column, 9);
+
+        let err = Scanner::new("div\t~").with_tab_width(4).scan().unwrap_err();
+        assert_eq!(err.column, 5);
+    }
+
+    #[test]
+    fn test_next_tab_stop() {
+        assert_eq!(next_tab_stop(1, 8), 9);
+        assert_eq!(next_tab_stop(4, 4), 5);
+        assert_eq!(next_tab_stop(5, 4), 9);
+        assert_eq!(next_tab_stop(0, 4), 5);
+        assert_eq!(next_tab_stop(3, 0), 4);
+    }
+
     // =========================================================================
     // Span tracking
     // =========================================================================
@@ -1457,6 +2257,21 @@ div .p-4
         );
     }
 
+    #[test]
+    fn test_negative_number_after_equals() {
+        assert_eq!(
+            kinds("div tabindex=-1"),
+            vec![
+                TokenKind::Identifier("div".into()),
+                TokenKind::Identifier("tabindex".into()),
+                TokenKind::Equals,
+                TokenKind::Number(-1.0),
+                TokenKind::Eof,
+            ]
+        );
+        assert!(Scanner::tokenize("div tabindex= -1").is_err());
+    }
+
     #[test]
     fn test_identifier_starting_with_keyword() {
         // "stateful" starts with "state" but is an identifier
@@ -1500,6 +2315,42 @@ div .p-4
         );
     }
 
+    #[test]
+    fn test_line_start_keywords() {
+        let k = kinds("const\neffect\nfragment\n  raw\ndiv raw fragment const effect");
+        assert_eq!(
+            k,
+            vec![
+                TokenKind::Const, TokenKind::Newline,
+                TokenKind::Effect, TokenKind::Newline,
+                TokenKind::Fragment, TokenKind::Newline,
+                TokenKind::Indent, TokenKind::Raw, TokenKind::Newline,
+                TokenKind::Dedent, TokenKind::Identifier("div".into()),
+                TokenKind::Identifier("raw".into()), TokenKind::Identifier("fragment".into()),
+                TokenKind::Identifier("const".into()), TokenKind::Identifier("effect".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_start_keywords_are_names_in_block_fields() {
+        let k = kinds("state\n  effect: 1\n  raw: 2\n  const: 3");
+        for name in ["effect", "raw", "const"] {
+            assert!(k.contains(&TokenKind::Identifier(name.into())), "{name}");
+        }
+        assert!(!k.contains(&TokenKind::Effect));
+        assert!(!k.contains(&TokenKind::Raw));
+    }
+
+    #[test]
+    fn test_block_keyword_mid_line_does_not_open_block() {
+        let k = kinds("div computed\n  span :show=\"x\" \"hi\"");
+        assert!(k.contains(&TokenKind::Identifier("span".into())));
+        assert!(k.contains(&TokenKind::String("hi".into())));
+        assert!(!k.iter().any(|t| matches!(t, TokenKind::ExprText(_))));
+    }
+
     #[test]
     fn test_all_prefixes_in_sequence() {
         assert_eq!(
@@ -0,0 +1,132 @@
+use crate::token::{Span, Token, TokenKind};
+use alloc::vec::Vec;
+
+/// A cursor over scanned tokens, with lookahead that doesn't consume.
+///
+/// Past the end of the tokens, [`TokenStream::peek`] and
+/// [`TokenStream::next`] yield an `Eof` token, so a reader never runs off
+/// the end.
+///
+/// ```
+/// use hrml_lexer::{Scanner, TokenKind, TokenStream};
+///
+/// let mut tokens = TokenStream::new(Scanner::tokenize("div .card").unwrap());
+/// assert_eq!(tokens.peek_kind(1), &TokenKind::Dot);
+/// assert_eq!(tokens.next().kind, TokenKind::Identifier("div".into()));
+/// assert_eq!(tokens.peek_kind(0), &TokenKind::Dot);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenStream {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+static EOF: Token = Token {
+    kind: TokenKind::Eof,
+    span: Span {
+        start: 0,
+        end: 0,
+        line: 0,
+        column: 0,
+    },
+};
+
+impl TokenStream {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    /// The token `n` places ahead without consuming anything; `peek(0)` is
+    /// the one [`TokenStream::next`] returns next.
+    pub fn peek(&self, n: usize) -> &Token {
+        self.pos
+            .checked_add(n)
+            .and_then(|i| self.tokens.get(i))
+            .unwrap_or(&EOF)
+    }
+
+    /// The kind of the token `n` places ahead.
+    pub fn peek_kind(&self, n: usize) -> &TokenKind {
+        &self.peek(n).kind
+    }
+
+    /// Consume and return the current token.
+    // Not `Iterator::next`: it lends the token rather than cloning it, and
+    // keeps returning `Eof` instead of ending
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> &Token {
+        let i = self.pos;
+        if self.pos < self.tokens.len() {
+            self.pos += 1;
+        }
+        self.tokens.get(i).unwrap_or(&EOF)
+    }
+
+    /// The token most recently consumed, if any.
+    pub fn previous(&self) -> Option<&Token> {
+        self.pos.checked_sub(1).and_then(|i| self.tokens.get(i))
+    }
+
+    /// How many tokens have been consumed.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Whether every token up to `Eof` has been consumed.
+    pub fn is_at_end(&self) -> bool {
+        matches!(self.peek_kind(0), TokenKind::Eof)
+    }
+}
+
+impl From<Vec<Token>> for TokenStream {
+    fn from(tokens: Vec<Token>) -> Self {
+        Self::new(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scanner;
+
+    fn stream(source: &str) -> TokenStream {
+        TokenStream::new(Scanner::tokenize(source).unwrap())
+    }
+
+    #[test]
+    fn test_peek_ahead_does_not_consume() {
+        let tokens = stream("a href=x");
+        assert_eq!(tokens.peek_kind(0), &TokenKind::Identifier("a".into()));
+        assert_eq!(tokens.peek_kind(1), &TokenKind::Identifier("href".into()));
+        assert_eq!(tokens.peek_kind(2), &TokenKind::Equals);
+        assert_eq!(tokens.peek_kind(3), &TokenKind::Identifier("x".into()));
+        assert_eq!(tokens.peek_kind(0), &TokenKind::Identifier("a".into()));
+        assert_eq!(tokens.position(), 0);
+    }
+
+    #[test]
+    fn test_next_moves_the_cursor() {
+        let mut tokens = stream("a href=x");
+        assert!(tokens.previous().is_none());
+        tokens.next();
+        tokens.next();
+        assert_eq!(tokens.position(), 2);
+        assert_eq!(tokens.peek_kind(0), &TokenKind::Equals);
+        assert_eq!(
+            tokens.previous().map(|t| &t.kind),
+            Some(&TokenKind::Identifier("href".into()))
+        );
+    }
+
+    #[test]
+    fn test_eof_past_the_end() {
+        let mut tokens = stream("p");
+        assert_eq!(tokens.peek_kind(usize::MAX), &TokenKind::Eof);
+        tokens.next();
+        assert!(tokens.is_at_end());
+        for _ in 0..3 {
+            assert_eq!(tokens.next().kind, TokenKind::Eof);
+        }
+        assert_eq!(tokens.position(), 2);
+    }
+}
@@ -1,5 +1,8 @@
+use alloc::string::String;
+
 /// A position in source text, tracking line and column for error reporting.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -37,6 +40,8 @@ pub enum TokenKind {
     Null,
     Comment(String),
     Interpolation(String),
+    RawText(String),  // verbatim body of a `raw` block
+    ExprText(String), // a `computed` value, `effect` statement, or `state` array/object, as written
 
     // Prefixes
     Dot,    // .class
@@ -44,7 +49,7 @@ pub enum TokenKind {
     At,     // @event
     Dollar, // $server
     Hash,   // #id (future)
-    Plus,   // +element stacking (future)
+    Plus,   // +.class continuation line
 
     // Punctuation
     Equals,
@@ -54,7 +59,9 @@ pub enum TokenKind {
 
     // Keywords
     State,
+    Const,
     Computed,
+    Effect,
     Fn,
     Async,
     Watch,
@@ -63,11 +70,27 @@ pub enum TokenKind {
     Import,
     Page,
     Config,
+    Raw,
+    Fragment,
 
     // End of input
     Eof,
 }
 
+impl TokenKind {
+    /// Whether the token carries nothing for the tree: line breaks and
+    /// comments. Blocks that keep no comments skip these freely.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenKind::Newline | TokenKind::Comment(_))
+    }
+
+    /// Whether the token shapes the tree through indentation rather than
+    /// content: `Indent`, `Dedent`, and `Eof`, which closes every open block.
+    pub fn is_structural(&self) -> bool {
+        matches!(self, TokenKind::Indent | TokenKind::Dedent | TokenKind::Eof)
+    }
+}
+
 /// A token produced by the HRML lexer.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
@@ -91,3 +114,16 @@ pub const VOID_ELEMENTS: &[&str] = &[
 pub fn is_void_element(tag: &str) -> bool {
     VOID_ELEMENTS.contains(&tag)
 }
+
+/// Stand-ins for `\{` and `\}` in string tokens. Private-use characters, so an
+/// escaped brace can't be mistaken for an `{expr}` marker, or for an escaped
+/// backslash followed by one (`"C:\\{dir}"`).
+pub const ESCAPED_OPEN_BRACE: char = '\u{E000}';
+pub const ESCAPED_CLOSE_BRACE: char = '\u{E001}';
+
+/// Resolve the escaped braces that string tokens keep, for values where
+/// braces have no interpolation meaning.
+pub fn unescape_braces(s: &str) -> String {
+    s.replace(ESCAPED_OPEN_BRACE, "{")
+        .replace(ESCAPED_CLOSE_BRACE, "}")
+}
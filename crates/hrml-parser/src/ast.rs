@@ -6,18 +6,26 @@
 //! Document types are new for v2.0. Expression types are reused from the
 //! v1.0 prototype's expression parser.
 
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub use hrml_lexer::Span;
+
 // ---------------------------------------------------------------------------
 // Document-level AST (new for v2.0)
 // ---------------------------------------------------------------------------
 
 /// A complete HRML document.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Document {
     pub nodes: Vec<Node>,
 }
 
 /// A top-level node in the document.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Node {
     /// An HTML element with optional classes, attributes, and children.
     Element(Element),
@@ -25,43 +33,123 @@ pub enum Node {
     /// A `state` block declaring reactive state.
     StateBlock(StateBlock),
 
+    /// A `const` block declaring plain, non-reactive values.
+    ConstBlock(ConstBlock),
+
     /// A `computed` block declaring derived values.
     ComputedBlock(ComputedBlock),
 
+    /// An `effect` block of statements re-run when the state they read changes.
+    EffectBlock(EffectBlock),
+
+    /// A `page` block with document-level settings (title, meta tags).
+    PageBlock(PageBlock),
+
+    /// A `config` block with compiler options for this file.
+    ConfigBlock(ConfigBlock),
+
     /// A `fn` or `async fn` declaration.
     FnDecl(FnDecl),
 
     /// A component definition.
     Component(Component),
 
+    /// Siblings grouped by a `fragment` without a wrapper element. An `:if` or
+    /// `:show` on the fragment is copied onto each child element.
+    Fragment(Vec<Node>),
+
     /// Raw text content (may contain `{expr}` interpolation markers).
     Text(String),
 
+    /// Verbatim markup from a `raw` block, emitted unchanged.
+    Raw(String),
+
     /// A `// comment` line.
     Comment(String),
 }
 
 /// An HTML element.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Element {
     pub tag: String,
     pub classes: Vec<String>,
     pub attributes: Vec<Attribute>,
     pub children: Vec<Node>,
+    /// Set when the element repeats per item (`li :for="todo in todos"`).
+    pub for_loop: Option<ForLoop>,
+    /// Objects whose entries become attributes at runtime (`div {...attrs}`).
+    pub spreads: Vec<Expression>,
+    /// Classes toggled by a condition (`.active:isActive`), as class name
+    /// and condition.
+    pub class_toggles: Vec<(String, Expression)>,
+    /// The element's own line, from the tag (or first class) through its
+    /// last attribute or inline text.
+    pub span: Span,
 }
 
-/// An attribute on an element.
-/// For event handlers (`@click.prevent`), modifiers stores `["prevent"]`.
+/// An empty `div`, the usual generic container. Set `tag` for anything else:
+/// `Element { tag: "ul".into(), ..Default::default() }`.
+impl Default for Element {
+    fn default() -> Self {
+        Self {
+            tag: "div".to_string(),
+            classes: Vec::new(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            for_loop: None,
+            spreads: Vec::new(),
+            class_toggles: Vec::new(),
+            span: Span::default(),
+        }
+    }
+}
+
+/// A `:for="item in items"` loop: the element is rendered once per item.
+/// `:for="(item, i) in items"` also names the item's position.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForLoop {
+    pub item: String,
+    pub index: Option<String>,
+    pub iterable: Expression,
+}
+
+/// An attribute on an element.
+/// For event handlers (`@click.prevent`), modifiers stores `[prevent]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attribute {
     pub name: String,
     pub value: Option<Expression>,
     pub prefix: Option<AttributePrefix>,
-    pub modifiers: Vec<String>,
+    pub modifiers: Vec<Modifier>,
+    /// From the prefix or name through the end of the value.
+    pub span: Span,
+}
+
+/// A `.name` modifier on an attribute. A number after it is its argument:
+/// `@input.debounce.300` has the modifier `debounce` with argument `300`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Modifier {
+    pub name: String,
+    pub arg: Option<String>,
+}
+
+impl Modifier {
+    /// A modifier without an argument.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            arg: None,
+        }
+    }
 }
 
 /// The three HRML prefixes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AttributePrefix {
     /// `:` — state and reactivity
     State,
@@ -72,33 +160,86 @@ pub enum AttributePrefix {
 }
 
 /// A `:state` block.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StateBlock {
     pub fields: Vec<StateField>,
 }
 
 /// A field inside a `:state` block.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StateField {
     pub name: String,
     pub value: Expression,
+    /// From the name through the end of the value.
+    pub span: Span,
+}
+
+/// A `const` block. Its fields are fixed values read by name, not through
+/// the state.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstBlock {
+    pub fields: Vec<StateField>,
 }
 
 /// A `:computed` block.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComputedBlock {
     pub fields: Vec<ComputedField>,
 }
 
 /// A field inside a `:computed` block.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComputedField {
     pub name: String,
     pub body: Expression,
 }
 
+/// An `effect` block: one statement per line, run once and again after
+/// every change to state it reads.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectBlock {
+    pub body: Vec<Expression>,
+}
+
+/// A `page` block. Values are static, so they are stored as text.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PageBlock {
+    pub fields: Vec<PageField>,
+}
+
+/// A field inside a `page` block: `viewport: "width=device-width"`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PageField {
+    pub name: String,
+    pub value: String,
+}
+
+/// A `config` block. Like `page`, values are static text.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigBlock {
+    pub fields: Vec<ConfigField>,
+}
+
+/// A field inside a `config` block: `minify: true`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigField {
+    pub name: String,
+    pub value: String,
+}
+
 /// A function declaration (`fn` or `async fn`).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FnDecl {
     pub name: String,
     pub params: Vec<String>,
@@ -108,6 +249,7 @@ pub struct FnDecl {
 
 /// A component definition.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Component {
     pub name: String,
     pub props: Vec<PropDef>,
@@ -116,6 +258,7 @@ pub struct Component {
 
 /// A prop definition in a component.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PropDef {
     pub name: String,
     pub default: Option<Expression>,
@@ -128,6 +271,7 @@ pub struct PropDef {
 /// A position in expression text (relative to the expression string, not the source file).
 /// Named `ExprSpan` to distinguish from `hrml_lexer::Span` which tracks source file positions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExprSpan {
     pub start: usize,
     pub end: usize,
@@ -141,6 +285,7 @@ impl ExprSpan {
 
 /// A complete expression node.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Expression {
     pub kind: ExprKind,
     pub span: ExprSpan,
@@ -148,6 +293,7 @@ pub struct Expression {
 
 /// Expression variants.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExprKind {
     /// Numeric literal: `42`, `3.14`
     Number(f64),
@@ -212,10 +358,11 @@ pub enum ExprKind {
     /// Array literal: `[1, 2, 3]`
     Array(Vec<Expression>),
 
-    /// Arrow function: `(x) => x + 1`
+    /// Arrow function: `(x) => x + 1`, or `async (x) => await f(x)`
     Arrow {
         params: Vec<String>,
         body: Box<Expression>,
+        is_async: bool,
     },
 
     /// Assignment: `count = 5`, `count += 1`
@@ -227,9 +374,22 @@ pub enum ExprKind {
 
     /// Template literal segment (from `{expr}` interpolation)
     Interpolation(Box<Expression>),
+
+    /// Interpolation filter: `price | currency` calls `currency(price)`.
+    /// Only parsed by [`ExprParser::parse_interpolation`].
+    ///
+    /// [`ExprParser::parse_interpolation`]: crate::expr_parser::ExprParser::parse_interpolation
+    Filter {
+        input: Box<Expression>,
+        name: String,
+    },
+
+    /// Statements separated by `;` (event handlers: `a = 1; b = 2`)
+    Sequence(Vec<Expression>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectProperty {
     pub key: String,
     pub value: Expression,
@@ -237,6 +397,7 @@ pub struct ObjectProperty {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOp {
     Add,
     Sub,
@@ -257,19 +418,23 @@ pub enum BinaryOp {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnaryOp {
     Not,
     Neg,
     Typeof,
+    Await,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PostfixOp {
     Increment,
     Decrement,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AssignOp {
     Assign,
     AddAssign,
@@ -277,3 +442,28 @@ pub enum AssignOp {
     MulAssign,
     DivAssign,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_default_is_empty_div() {
+        let el = Element::default();
+        assert_eq!(el.tag, "div");
+        assert!(el.classes.is_empty() && el.attributes.is_empty() && el.children.is_empty());
+        assert!(el.for_loop.is_none());
+    }
+
+    #[test]
+    fn test_build_element_with_default() {
+        let doc = Document {
+            nodes: vec![Node::Element(Element {
+                tag: "section".to_string(),
+                ..Default::default()
+            })],
+        };
+        assert_eq!(doc.to_sexpr(), "(document\n  (element section))");
+        assert_eq!(Document::default().nodes.len(), 0);
+    }
+}
@@ -19,6 +19,9 @@
 //! ```
 
 use crate::ast::ExprSpan;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// A token produced by the expression lexer.
 #[derive(Debug, Clone, PartialEq)]
@@ -41,6 +44,8 @@ pub enum TokenKind {
     // Identifiers & keywords
     Identifier,
     Typeof,
+    Await,
+    Async,
 
     // Arithmetic
     Plus,
@@ -92,6 +97,8 @@ pub enum TokenKind {
     Question,
     Arrow,
     OptionalChain,
+    /// `|` between an interpolated value and a filter: `{price | currency}`
+    Pipe,
 
     // End of input
     Eof,
@@ -114,8 +121,8 @@ pub struct ExprLexerError {
     pub span: ExprSpan,
 }
 
-impl std::fmt::Display for ExprLexerError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ExprLexerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "Expression lexer error at position {}: {}",
@@ -124,23 +131,21 @@ impl std::fmt::Display for ExprLexerError {
     }
 }
 
-impl std::error::Error for ExprLexerError {}
+impl core::error::Error for ExprLexerError {}
 
 /// HRML expression lexer.
 ///
 /// Tokenizes expressions found in HRML attributes and interpolation blocks.
 /// Operates on a single expression string (e.g. the content of `{count + 1}`).
-pub struct ExprLexer<'a> {
-    source: &'a str,
+pub struct ExprLexer {
     chars: Vec<char>,
     pos: usize,
 }
 
-impl<'a> ExprLexer<'a> {
+impl ExprLexer {
     /// Create a new expression lexer for the given source.
-    pub fn new(source: &'a str) -> Self {
+    pub fn new(source: &str) -> Self {
         Self {
-            source,
             chars: source.chars().collect(),
             pos: 0,
         }
@@ -181,12 +186,13 @@ impl<'a> ExprLexer<'a> {
         match ch {
             // Numbers
             '0'..='9' => self.read_number(start),
+            '.' if self.peek().is_some_and(|c| c.is_ascii_digit()) => self.read_number(start),
 
             // Strings
             '\'' | '"' | '`' => self.read_string(start),
 
-            // Identifiers and keywords
-            'a'..='z' | 'A'..='Z' | '_' | '$' => self.read_identifier(start),
+            // Identifiers and keywords (Unicode letters, as in the source scanner)
+            c if c.is_alphabetic() || c == '_' || c == '$' => self.read_identifier(start),
 
             // Two-character operators (check first)
             '=' if self.peek() == Some('=') => {
@@ -215,6 +221,10 @@ impl<'a> ExprLexer<'a> {
                 self.advance_n(2);
                 Ok(self.token(TokenKind::Or, start, TokenValue::None))
             }
+            '|' => {
+                self.advance();
+                Ok(self.token(TokenKind::Pipe, start, TokenValue::None))
+            }
             '+' if self.peek() == Some('+') => {
                 self.advance_n(2);
                 Ok(self.token(TokenKind::PlusPlus, start, TokenValue::None))
@@ -341,6 +351,15 @@ impl<'a> ExprLexer<'a> {
                 Ok(self.token(TokenKind::Question, start, TokenValue::None))
             }
 
+            // HRML expressions never reach class internals, so private fields
+            // (`this.#x`) are not supported — point users at the source-level prefix.
+            '#' => Err(ExprLexerError {
+                message: "Unexpected character: '#' (`#` is only valid as a source-level \
+                          id prefix like `div #main`, not inside expressions)"
+                    .into(),
+                span: ExprSpan::new(start, start + 1),
+            }),
+
             _ => Err(ExprLexerError {
                 message: format!("Unexpected character: '{ch}'"),
                 span: ExprSpan::new(start, start + 1),
@@ -350,12 +369,20 @@ impl<'a> ExprLexer<'a> {
 
     // --- Private helpers ---
 
+    /// Read `5`, `0.5`, `5.` or `.5`. A second `.` ends the number, so
+    /// `5..toString()` calls a method on `5.` as in JavaScript.
     fn read_number(&mut self, start: usize) -> Result<Token, ExprLexerError> {
-        while !self.is_at_end() && (self.current().is_ascii_digit() || self.current() == '.') {
+        let mut seen_dot = false;
+        while !self.is_at_end() {
+            match self.current() {
+                '0'..='9' => {}
+                '.' if !seen_dot => seen_dot = true,
+                _ => break,
+            }
             self.advance();
         }
 
-        let text = &self.source[start..self.pos];
+        let text: String = self.chars[start..self.pos].iter().collect();
         let value: f64 = text.parse().map_err(|_| ExprLexerError {
             message: format!("Invalid number: '{text}'"),
             span: ExprSpan::new(start, self.pos),
@@ -383,6 +410,10 @@ impl<'a> ExprLexer<'a> {
                     'n' => value.push('\n'),
                     't' => value.push('\t'),
                     'r' => value.push('\r'),
+                    '0' => value.push('\0'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'v' => value.push('\u{b}'),
                     '\\' => value.push('\\'),
                     c if c == quote => value.push(c),
                     c => {
@@ -412,6 +443,9 @@ impl<'a> ExprLexer<'a> {
         ))
     }
 
+    /// Read an identifier: a Unicode letter, `_`, or `$`, then any Unicode
+    /// alphanumerics, `_`, or `$` — the same rule as `hrml_lexer`'s scanner,
+    /// plus `$` (a prefix in source, a plain identifier character in JS).
     fn read_identifier(&mut self, start: usize) -> Result<Token, ExprLexerError> {
         while !self.is_at_end()
             && (self.current().is_alphanumeric() || self.current() == '_' || self.current() == '$')
@@ -419,9 +453,10 @@ impl<'a> ExprLexer<'a> {
             self.advance();
         }
 
-        let text = &self.source[start..self.pos];
+        // Positions count chars, not bytes
+        let text: String = self.chars[start..self.pos].iter().collect();
 
-        match text {
+        match text.as_str() {
             "true" => Ok(self.token(
                 TokenKind::Boolean,
                 start,
@@ -435,10 +470,12 @@ impl<'a> ExprLexer<'a> {
             "null" => Ok(self.token(TokenKind::Null, start, TokenValue::None)),
             "undefined" => Ok(self.token(TokenKind::Undefined, start, TokenValue::None)),
             "typeof" => Ok(self.token(TokenKind::Typeof, start, TokenValue::None)),
+            "await" => Ok(self.token(TokenKind::Await, start, TokenValue::None)),
+            "async" => Ok(self.token(TokenKind::Async, start, TokenValue::None)),
             _ => Ok(self.token(
                 TokenKind::Identifier,
                 start,
-                TokenValue::Identifier(text.to_string()),
+                TokenValue::Identifier(text),
             )),
         }
     }
@@ -509,6 +546,60 @@ mod tests {
         assert_eq!(tokens[0].value, TokenValue::Number(2.75));
     }
 
+    #[test]
+    fn test_leading_dot_decimal() {
+        let tokens = tokenize(".5");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].value, TokenValue::Number(0.5));
+        assert_eq!(tokens[0].span, ExprSpan::new(0, 2));
+    }
+
+    #[test]
+    fn test_trailing_dot_decimal() {
+        let tokens = tokenize("5.");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].value, TokenValue::Number(5.0));
+    }
+
+    #[test]
+    fn test_zero_point_five() {
+        let tokens = tokenize("0.5");
+        assert_eq!(tokens[0].value, TokenValue::Number(0.5));
+        assert_eq!(tokens[0].span, ExprSpan::new(0, 3));
+    }
+
+    #[test]
+    fn test_second_dot_ends_number() {
+        assert_eq!(
+            kinds("5..toString"),
+            vec![
+                TokenKind::Number,
+                TokenKind::Dot,
+                TokenKind::Identifier,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dot_before_identifier_is_member_access() {
+        assert_eq!(
+            kinds("a.b"),
+            vec![
+                TokenKind::Identifier,
+                TokenKind::Dot,
+                TokenKind::Identifier,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_number_after_multibyte_string() {
+        let tokens = tokenize("'é' + 12");
+        assert_eq!(tokens[2].value, TokenValue::Number(12.0));
+    }
+
     #[test]
     fn test_string_single_quotes() {
         let tokens = tokenize("'hello'");
@@ -528,6 +619,15 @@ mod tests {
         assert_eq!(tokens[0].value, TokenValue::String("hello\nworld".into()));
     }
 
+    #[test]
+    fn test_string_control_escapes() {
+        let tokens = tokenize("'\\0\\b\\f\\v'");
+        assert_eq!(
+            tokens[0].value,
+            TokenValue::String("\0\u{8}\u{c}\u{b}".into())
+        );
+    }
+
     #[test]
     fn test_boolean_true() {
         let tokens = tokenize("true");
@@ -558,6 +658,46 @@ mod tests {
         assert_eq!(tokens[0].value, TokenValue::Identifier("count".into()));
     }
 
+    #[test]
+    fn test_await_and_async_keywords() {
+        let kinds: Vec<TokenKind> = tokenize("await fetch(url) async awaiting asyncTask")
+            .iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Await,
+                TokenKind::Identifier,
+                TokenKind::LParen,
+                TokenKind::Identifier,
+                TokenKind::RParen,
+                TokenKind::Async,
+                TokenKind::Identifier,
+                TokenKind::Identifier,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unicode_identifier() {
+        let tokens = tokenize("größe + 名前");
+        assert_eq!(tokens[0].value, TokenValue::Identifier("größe".into()));
+        assert_eq!(tokens[0].span, ExprSpan::new(0, 5));
+        assert_eq!(tokens[2].value, TokenValue::Identifier("名前".into()));
+    }
+
+    #[test]
+    fn test_unicode_identifier_matches_source_scanner() {
+        for name in ["café", "größe", "名前", "_été2"] {
+            let source = hrml_lexer::Scanner::tokenize(name).unwrap();
+            assert_eq!(source[0].kind, hrml_lexer::TokenKind::Identifier(name.into()));
+            let expr = tokenize(name);
+            assert_eq!(expr[0].value, TokenValue::Identifier(name.into()));
+        }
+    }
+
     // --- Operators ---
 
     #[test]
@@ -809,6 +949,13 @@ mod tests {
         assert!(result.unwrap_err().message.contains("Unexpected character"));
     }
 
+    #[test]
+    fn test_hash_error_suggests_id_prefix() {
+        let err = ExprLexer::tokenize("this.#secret").unwrap_err();
+        assert!(err.message.contains("source-level id prefix"));
+        assert_eq!(err.span, ExprSpan::new(5, 6));
+    }
+
     #[test]
     fn test_empty_input() {
         let tokens = tokenize("");
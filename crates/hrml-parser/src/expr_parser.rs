@@ -23,6 +23,11 @@ use crate::ast::{
 };
 use crate::expr_lexer::{ExprLexer, Token, TokenKind, TokenValue};
 use crate::ParseError;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use hrml_lexer::{next_tab_stop, Scanner};
 
 /// HRML expression parser.
 ///
@@ -31,16 +36,43 @@ use crate::ParseError;
 pub struct ExprParser {
     tokens: Vec<Token>,
     pos: usize,
+    /// Depth of the expression tree being built.
+    depth: usize,
 }
 
 impl ExprParser {
+    /// Deepest expression tree accepted. Adversarial input such as `((((...`
+    /// or `a+a+a+...` is an error instead of a stack overflow here or in
+    /// code that walks the tree.
+    pub const MAX_DEPTH: usize = 64;
+
     /// Create a new expression parser for the given tokens.
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, pos: 0 }
+        Self {
+            tokens,
+            pos: 0,
+            depth: 0,
+        }
     }
 
     /// Parse a complete expression from a source string.
     pub fn parse(source: &str) -> Result<Expression, ParseError> {
+        Self::parse_single(source).map_err(|e| with_source_column(e, source))
+    }
+
+    /// Parse one or more `;`-separated statements (used for event handlers).
+    /// A single statement is returned as-is; several become a `Sequence`.
+    pub fn parse_statements(source: &str) -> Result<Expression, ParseError> {
+        Self::parse_sequence(source).map_err(|e| with_source_column(e, source))
+    }
+
+    /// Parse the inside of a `{...}` interpolation: an expression followed by
+    /// any number of `| filter`s, applied left to right.
+    pub fn parse_interpolation(source: &str) -> Result<Expression, ParseError> {
+        Self::parse_filtered(source).map_err(|e| with_source_column(e, source))
+    }
+
+    fn parse_single(source: &str) -> Result<Expression, ParseError> {
         let tokens = ExprLexer::tokenize(source).map_err(|e| ParseError {
             message: e.message,
             line: 1,
@@ -50,6 +82,12 @@ impl ExprParser {
         let mut parser = ExprParser::new(tokens);
         let expr = parser.parse_expression()?;
 
+        if parser.peek().kind == TokenKind::Pipe {
+            return Err(parser.error(
+                "Filters (`value | filter`) are only allowed in `{...}` interpolation".into(),
+            ));
+        }
+
         // Ensure we consumed everything (except Eof)
         if parser.peek().kind != TokenKind::Eof {
             return Err(parser.error(format!(
@@ -61,6 +99,78 @@ impl ExprParser {
         Ok(expr)
     }
 
+    fn parse_filtered(source: &str) -> Result<Expression, ParseError> {
+        let tokens = ExprLexer::tokenize(source).map_err(|e| ParseError {
+            message: e.message,
+            line: 1,
+            column: e.span.start + 1,
+        })?;
+
+        let mut parser = ExprParser::new(tokens);
+        let mut expr = parser.parse_expression()?;
+
+        while parser.peek().kind == TokenKind::Pipe {
+            parser.advance();
+            let end = parser.peek().span.end;
+            let name = parser.expect_identifier()?;
+            let span = ExprSpan::new(expr.span.start, end);
+            expr = Expression {
+                kind: ExprKind::Filter {
+                    input: Box::new(expr),
+                    name,
+                },
+                span,
+            };
+        }
+
+        if parser.peek().kind != TokenKind::Eof {
+            return Err(parser.error(format!(
+                "Unexpected token: {:?}",
+                parser.peek().kind
+            )));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_sequence(source: &str) -> Result<Expression, ParseError> {
+        let tokens = ExprLexer::tokenize(source).map_err(|e| ParseError {
+            message: e.message,
+            line: 1,
+            column: e.span.start + 1,
+        })?;
+
+        let mut parser = ExprParser::new(tokens);
+        let mut statements = vec![parser.parse_expression()?];
+
+        while parser.peek().kind == TokenKind::Semicolon {
+            parser.advance();
+            if parser.peek().kind == TokenKind::Eof {
+                break; // trailing `;`
+            }
+            statements.push(parser.parse_expression()?);
+        }
+
+        if parser.peek().kind != TokenKind::Eof {
+            return Err(parser.error(format!(
+                "Unexpected token: {:?}",
+                parser.peek().kind
+            )));
+        }
+
+        if statements.len() == 1 {
+            return Ok(statements.remove(0));
+        }
+        let span = ExprSpan::new(
+            statements[0].span.start,
+            statements[statements.len() - 1].span.end,
+        );
+        Ok(Expression {
+            kind: ExprKind::Sequence(statements),
+            span,
+        })
+    }
+
     // =========================================================================
     // Precedence levels (lowest to highest)
     // =========================================================================
@@ -72,6 +182,13 @@ impl ExprParser {
 
     /// Level 1: Assignment (`=`, `+=`, `-=`, `*=`, `/=`) — right-associative.
     fn parse_assignment(&mut self) -> Result<Expression, ParseError> {
+        self.descend()?;
+        let expr = self.parse_assignment_inner();
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_assignment_inner(&mut self) -> Result<Expression, ParseError> {
         let expr = self.parse_ternary()?;
 
         let op = match self.peek().kind {
@@ -128,12 +245,14 @@ impl ExprParser {
 
     /// Level 3: Nullish coalescing (`??`)
     fn parse_nullish(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut left = self.parse_or()?;
 
         while self.peek().kind == TokenKind::QuestionQuestion {
             self.advance();
             let right = self.parse_or()?;
             let span = ExprSpan::new(left.span.start, right.span.end);
+            self.descend()?;
             left = Expression {
                 kind: ExprKind::Binary {
                     left: Box::new(left),
@@ -144,17 +263,20 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(left)
     }
 
     /// Level 4: Logical OR (`||`)
     fn parse_or(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut left = self.parse_and()?;
 
         while self.peek().kind == TokenKind::Or {
             self.advance();
             let right = self.parse_and()?;
             let span = ExprSpan::new(left.span.start, right.span.end);
+            self.descend()?;
             left = Expression {
                 kind: ExprKind::Binary {
                     left: Box::new(left),
@@ -165,17 +287,20 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(left)
     }
 
     /// Level 5: Logical AND (`&&`)
     fn parse_and(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut left = self.parse_equality()?;
 
         while self.peek().kind == TokenKind::And {
             self.advance();
             let right = self.parse_equality()?;
             let span = ExprSpan::new(left.span.start, right.span.end);
+            self.descend()?;
             left = Expression {
                 kind: ExprKind::Binary {
                     left: Box::new(left),
@@ -186,11 +311,13 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(left)
     }
 
     /// Level 6: Equality (`==`, `!=`, `===`, `!==`)
     fn parse_equality(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut left = self.parse_comparison()?;
 
         loop {
@@ -204,6 +331,7 @@ impl ExprParser {
             self.advance();
             let right = self.parse_comparison()?;
             let span = ExprSpan::new(left.span.start, right.span.end);
+            self.descend()?;
             left = Expression {
                 kind: ExprKind::Binary {
                     left: Box::new(left),
@@ -214,11 +342,13 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(left)
     }
 
     /// Level 7: Comparison (`<`, `>`, `<=`, `>=`)
     fn parse_comparison(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut left = self.parse_additive()?;
 
         loop {
@@ -232,6 +362,7 @@ impl ExprParser {
             self.advance();
             let right = self.parse_additive()?;
             let span = ExprSpan::new(left.span.start, right.span.end);
+            self.descend()?;
             left = Expression {
                 kind: ExprKind::Binary {
                     left: Box::new(left),
@@ -242,11 +373,13 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(left)
     }
 
     /// Level 8: Additive (`+`, `-`)
     fn parse_additive(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut left = self.parse_multiplicative()?;
 
         loop {
@@ -258,6 +391,7 @@ impl ExprParser {
             self.advance();
             let right = self.parse_multiplicative()?;
             let span = ExprSpan::new(left.span.start, right.span.end);
+            self.descend()?;
             left = Expression {
                 kind: ExprKind::Binary {
                     left: Box::new(left),
@@ -268,11 +402,13 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(left)
     }
 
     /// Level 9: Multiplicative (`*`, `/`, `%`)
     fn parse_multiplicative(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut left = self.parse_unary()?;
 
         loop {
@@ -285,6 +421,7 @@ impl ExprParser {
             self.advance();
             let right = self.parse_unary()?;
             let span = ExprSpan::new(left.span.start, right.span.end);
+            self.descend()?;
             left = Expression {
                 kind: ExprKind::Binary {
                     left: Box::new(left),
@@ -295,6 +432,7 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(left)
     }
 
@@ -305,12 +443,16 @@ impl ExprParser {
             TokenKind::Not => Some(UnaryOp::Not),
             TokenKind::Minus => Some(UnaryOp::Neg),
             TokenKind::Typeof => Some(UnaryOp::Typeof),
+            TokenKind::Await => Some(UnaryOp::Await),
             _ => None,
         };
 
         if let Some(op) = op {
             self.advance();
-            let operand = self.parse_unary()?; // Right-recursive for chaining: !!x
+            self.descend()?;
+            let operand = self.parse_unary(); // Right-recursive for chaining: !!x
+            self.depth -= 1;
+            let operand = operand?;
             let span = ExprSpan::new(start, operand.span.end);
             Ok(Expression {
                 kind: ExprKind::Unary {
@@ -326,6 +468,7 @@ impl ExprParser {
 
     /// Level 11: Postfix (`++`, `--`)
     fn parse_postfix(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut expr = self.parse_call_member()?;
 
         loop {
@@ -337,6 +480,7 @@ impl ExprParser {
             let end = self.peek().span.end;
             self.advance();
             let span = ExprSpan::new(expr.span.start, end);
+            self.descend()?;
             expr = Expression {
                 kind: ExprKind::Postfix {
                     operand: Box::new(expr),
@@ -346,11 +490,13 @@ impl ExprParser {
             };
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
     /// Level 12: Call and member access (`.`, `[]`, `()`, `?.`)
     fn parse_call_member(&mut self) -> Result<Expression, ParseError> {
+        let depth = self.depth;
         let mut expr = self.parse_primary()?;
 
         loop {
@@ -362,6 +508,7 @@ impl ExprParser {
                     let prop_end = self.peek().span.end;
                     let name = self.expect_identifier()?;
                     let span = ExprSpan::new(expr.span.start, prop_end);
+                    self.descend()?;
                     expr = Expression {
                         kind: ExprKind::Member {
                             object: Box::new(expr),
@@ -381,6 +528,7 @@ impl ExprParser {
                     let prop_end = self.peek().span.end;
                     let name = self.expect_identifier()?;
                     let span = ExprSpan::new(expr.span.start, prop_end);
+                    self.descend()?;
                     expr = Expression {
                         kind: ExprKind::Member {
                             object: Box::new(expr),
@@ -403,6 +551,7 @@ impl ExprParser {
                     let end = self.peek().span.end;
                     self.advance();
                     let span = ExprSpan::new(expr.span.start, end);
+                    self.descend()?;
                     expr = Expression {
                         kind: ExprKind::Member {
                             object: Box::new(expr),
@@ -422,6 +571,7 @@ impl ExprParser {
                     let end = self.peek().span.end;
                     self.advance();
                     let span = ExprSpan::new(expr.span.start, end);
+                    self.descend()?;
                     expr = Expression {
                         kind: ExprKind::Call {
                             callee: Box::new(expr),
@@ -434,6 +584,7 @@ impl ExprParser {
             }
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
@@ -516,6 +667,7 @@ impl ExprParser {
                         kind: ExprKind::Arrow {
                             params: vec![name],
                             body: Box::new(body),
+                            is_async: false,
                         },
                         span,
                     });
@@ -528,6 +680,31 @@ impl ExprParser {
                 })
             }
 
+            // `async` arrow function; otherwise `async` is a plain name
+            TokenKind::Async => {
+                self.advance(); // consume async
+                let arrow_follows = match self.peek().kind {
+                    TokenKind::Identifier => {
+                        self.peek_at(1).map(|t| &t.kind) == Some(&TokenKind::Arrow)
+                    }
+                    TokenKind::LParen => self.is_arrow_params(),
+                    _ => false,
+                };
+                if !arrow_follows {
+                    return Ok(Expression {
+                        kind: ExprKind::Identifier("async".into()),
+                        span: token.span,
+                    });
+                }
+
+                let mut arrow = self.parse_primary()?;
+                if let ExprKind::Arrow { is_async, .. } = &mut arrow.kind {
+                    *is_async = true;
+                }
+                arrow.span.start = token.span.start;
+                Ok(arrow)
+            }
+
             // Parenthesized expression or arrow function params
             TokenKind::LParen => {
                 // Try arrow function: (params) => body
@@ -553,6 +730,7 @@ impl ExprParser {
                         kind: ExprKind::Arrow {
                             params,
                             body: Box::new(body),
+                            is_async: false,
                         },
                         span,
                     });
@@ -645,11 +823,11 @@ impl ExprParser {
     // =========================================================================
 
     fn peek(&self) -> &Token {
-        static EOF: std::sync::LazyLock<Token> = std::sync::LazyLock::new(|| Token {
+        static EOF: Token = Token {
             kind: TokenKind::Eof,
-            span: ExprSpan::new(0, 0),
+            span: ExprSpan { start: 0, end: 0 },
             value: TokenValue::None,
-        });
+        };
         self.tokens.get(self.pos).unwrap_or(&EOF)
     }
 
@@ -746,6 +924,7 @@ impl ExprParser {
             kind: ExprKind::Arrow {
                 params,
                 body: Box::new(body),
+                is_async: false,
             },
             span,
         })
@@ -764,6 +943,21 @@ impl ExprParser {
         }
     }
 
+    /// Enter one level of nesting, failing past [`Self::MAX_DEPTH`]. Besides
+    /// recursive calls, the left-associative loops descend once per operator,
+    /// as each one wraps the tree built so far; callers restore `depth` when
+    /// they return.
+    fn descend(&mut self) -> Result<(), ParseError> {
+        if self.depth >= Self::MAX_DEPTH {
+            return Err(self.error(format!(
+                "Expression is nested more than {} levels deep",
+                Self::MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
     fn error(&self, message: String) -> ParseError {
         let span = self.peek().span;
         ParseError {
@@ -774,6 +968,19 @@ impl ExprParser {
     }
 }
 
+/// Turn an error's character offset (stored as `column - 1`) into a column,
+/// expanding tabs the same way the source scanner does.
+fn with_source_column(mut err: ParseError, source: &str) -> ParseError {
+    err.column = source
+        .chars()
+        .take(err.column - 1)
+        .fold(1, |column, c| match c {
+            '\t' => next_tab_stop(column, Scanner::DEFAULT_TAB_WIDTH),
+            _ => column + 1,
+        });
+    err
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -797,7 +1004,14 @@ mod tests {
 
     #[test]
     fn test_number_float() {
-        assert_eq!(parse_kind("3.14"), ExprKind::Number(3.14));
+        assert_eq!(parse_kind("2.5"), ExprKind::Number(2.5));
+    }
+
+    #[test]
+    fn test_number_leading_and_trailing_dot() {
+        assert_eq!(parse("price * .5").to_sexpr(), "(* price 0.5)");
+        assert_eq!(parse("5. + 0.5").to_sexpr(), "(+ 5 0.5)");
+        assert_eq!(parse("5..toFixed(1)").to_sexpr(), "(call (. 5 toFixed) 1)");
     }
 
     #[test]
@@ -1085,6 +1299,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_async_arrow() {
+        let expr = parse("async (id) => await load(id)");
+        assert_eq!(expr.span, ExprSpan::new(0, 28));
+        match &expr.kind {
+            ExprKind::Arrow {
+                params,
+                body,
+                is_async: true,
+            } => {
+                assert_eq!(params, &["id"]);
+                assert!(matches!(body.kind, ExprKind::Unary { op: UnaryOp::Await, .. }));
+            }
+            _ => panic!("Expected async arrow"),
+        }
+        assert!(matches!(parse("async x => x").kind, ExprKind::Arrow { is_async: true, .. }));
+    }
+
+    #[test]
+    fn test_async_without_arrow_is_identifier() {
+        match &parse("async(1)").kind {
+            ExprKind::Call { callee, .. } => {
+                assert!(matches!(&callee.kind, ExprKind::Identifier(name) if name == "async"))
+            }
+            _ => panic!("Expected call"),
+        }
+    }
+
     // =========================================================================
     // Object and array literals
     // =========================================================================
@@ -1143,6 +1385,23 @@ mod tests {
         assert!(matches!(expr.kind, ExprKind::Binary { op: BinaryOp::Mul, .. }));
     }
 
+    #[test]
+    fn test_nesting_beyond_max_depth_is_error() {
+        let depth = ExprParser::MAX_DEPTH;
+        let nested = |n: usize| format!("{}a{}", "(".repeat(n), ")".repeat(n));
+        assert!(ExprParser::parse(&nested(depth - 1)).is_ok());
+
+        for source in [
+            nested(depth),
+            format!("{}a", "!".repeat(depth)),
+            vec!["a"; depth + 1].join(" + "),
+            format!("a{}", ".b".repeat(depth)),
+        ] {
+            let err = ExprParser::parse(&source).unwrap_err();
+            assert!(err.message.contains("nested more than 64 levels"), "{source}");
+        }
+    }
+
     // =========================================================================
     // Complex expressions (real HRML patterns)
     // =========================================================================
@@ -1166,6 +1425,73 @@ mod tests {
         assert!(matches!(expr.kind, ExprKind::Call { .. }));
     }
 
+    // =========================================================================
+    // Statement sequences
+    // =========================================================================
+
+    #[test]
+    fn test_statements_single() {
+        let expr = ExprParser::parse_statements("count++").unwrap();
+        assert!(matches!(expr.kind, ExprKind::Postfix { .. }));
+    }
+
+    #[test]
+    fn test_statements_sequence() {
+        let expr = ExprParser::parse_statements("a = 1; b = 2; c++").unwrap();
+        match &expr.kind {
+            ExprKind::Sequence(stmts) => {
+                assert_eq!(stmts.len(), 3);
+                assert!(matches!(stmts[0].kind, ExprKind::Assignment { .. }));
+                assert!(matches!(stmts[2].kind, ExprKind::Postfix { .. }));
+            }
+            _ => panic!("Expected sequence"),
+        }
+    }
+
+    #[test]
+    fn test_statements_trailing_semicolon() {
+        let expr = ExprParser::parse_statements("a = 1;").unwrap();
+        assert!(matches!(expr.kind, ExprKind::Assignment { .. }));
+    }
+
+    #[test]
+    fn test_semicolon_rejected_in_expression() {
+        assert!(ExprParser::parse("a = 1; b = 2").is_err());
+    }
+
+    // =========================================================================
+    // Interpolation filters
+    // =========================================================================
+
+    #[test]
+    fn test_interpolation_filter() {
+        let expr = ExprParser::parse_interpolation("price | currency").unwrap();
+        assert_eq!(expr.to_sexpr(), "(| price currency)");
+    }
+
+    #[test]
+    fn test_interpolation_filters_chain_left_to_right() {
+        let expr = ExprParser::parse_interpolation("user.name || 'anon' | lower | upper").unwrap();
+        assert_eq!(
+            expr.to_sexpr(),
+            "(| (| (|| (. user name) \"anon\") lower) upper)"
+        );
+    }
+
+    #[test]
+    fn test_filter_needs_a_name() {
+        assert!(ExprParser::parse_interpolation("price |").is_err());
+        assert!(ExprParser::parse_interpolation("price | 2").is_err());
+    }
+
+    #[test]
+    fn test_filter_outside_interpolation_is_error() {
+        let err = ExprParser::parse("price | currency").unwrap_err();
+        assert!(err
+            .message
+            .contains("only allowed in `{...}` interpolation"));
+    }
+
     // =========================================================================
     // Errors
     // =========================================================================
@@ -1184,4 +1510,10 @@ mod tests {
     fn test_error_unexpected_token() {
         assert!(ExprParser::parse("+ +").is_err());
     }
+
+    #[test]
+    fn test_error_column_after_tab() {
+        let err = ExprParser::parse("a\t)").unwrap_err();
+        assert_eq!(err.column, 9);
+    }
 }
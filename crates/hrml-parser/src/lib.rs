@@ -6,20 +6,79 @@
 //!
 //! The expression lexer and AST types are reused from the v1.0 prototype
 //! and handle all JavaScript expression syntax that HRML supports.
+//!
+//! Parsing never panics: any input, however malformed, yields a `Document`
+//! or a [`ParseError`]. Nesting is bounded by [`Parser::DEFAULT_MAX_DEPTH`]
+//! for elements and [`expr_parser::ExprParser::MAX_DEPTH`] for expressions,
+//! so deeply nested input is an error rather than a stack overflow.
+//!
+//! # `no_std`
+//!
+//! With default features off the crate is `no_std` and needs only `alloc`;
+//! [`ParseError`] then implements `Display` by hand instead of via thiserror.
+//!
+//! # `serde`
+//!
+//! The `serde` feature derives `Serialize` and `Deserialize` for the AST,
+//! which `hrml_codegen::compile_from_json` reads back.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 pub mod ast;
 pub mod expr_lexer;
 pub mod expr_parser;
 pub mod parser;
+mod sexpr;
+
+use alloc::string::String;
 
 pub use ast::{Document, Expression, Node};
 pub use parser::Parser;
 
 /// Parser error with position information.
-#[derive(Debug, Clone, PartialEq, thiserror::Error)]
-#[error("Parse error at line {line}, column {column}: {message}")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[cfg_attr(
+    feature = "std",
+    error("Parse error at line {line}, column {column}: {message}")
+)]
 pub struct ParseError {
     pub message: String,
     pub line: usize,
     pub column: usize,
 }
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Parse error at line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Also run with `--no-default-features`, where `Display` is hand-written.
+    #[test]
+    fn test_error_display() {
+        let err = ParseError {
+            message: "Expected an element".into(),
+            line: 2,
+            column: 5,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 2, column 5: Expected an element"
+        );
+    }
+}
@@ -7,29 +7,53 @@
 //! Uses recursive descent parsing adapted for HRML syntax.
 
 use crate::ast::{
-    Attribute, AttributePrefix, ComputedBlock, ComputedField, Document, Element, ExprKind,
-    ExprSpan, Expression, Node, StateBlock, StateField,
+    Attribute, AttributePrefix, ComputedBlock, ComputedField, ConfigBlock, ConfigField, ConstBlock,
+    Document, EffectBlock, Element, ExprKind, ExprSpan, Expression, ForLoop, Modifier, Node,
+    PageBlock, PageField, StateBlock, StateField,
 };
 use crate::expr_parser::ExprParser;
 use crate::ParseError;
-use hrml_lexer::{Token, TokenKind};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use hrml_lexer::{unescape_braces, Span, Token, TokenKind, TokenStream};
 
 /// HRML document parser.
 ///
 /// Converts a flat token stream from the source lexer into a hierarchical
 /// `Document` AST using recursive descent.
 pub struct Parser {
-    tokens: Vec<Token>,
-    pos: usize,
+    tokens: TokenStream,
+    depth: usize,
+    max_depth: usize,
 }
 
 impl Parser {
+    /// Default limit on element nesting, keeping recursion well within the stack.
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
     /// Create a new parser for the given tokens.
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, pos: 0 }
+        Self {
+            tokens: TokenStream::new(tokens),
+            depth: 0,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Set the maximum element nesting depth; deeper input is a `ParseError`.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
     }
 
-    /// Parse source code into a document AST.
+    /// Parse the tokens given to [`Parser::new`] into a document AST.
+    pub fn parse_tokens(mut self) -> Result<Document, ParseError> {
+        self.parse_document()
+    }
+
+    /// Parse source code into a document AST. Never panics; malformed
+    /// input of any kind is reported as a `ParseError`.
     pub fn parse(source: &str) -> Result<Document, ParseError> {
         let tokens = hrml_lexer::Scanner::tokenize(source).map_err(|e| ParseError {
             message: e.message,
@@ -41,8 +65,28 @@ impl Parser {
         parser.parse_document()
     }
 
+    /// Parse a snippet of markup, such as a template partial, into its
+    /// top-level nodes. Document-level blocks (`state`, `const`, `computed`,
+    /// `effect`, `page`, `config`) are a `ParseError` here.
+    pub fn parse_fragment(source: &str) -> Result<Vec<Node>, ParseError> {
+        let tokens = hrml_lexer::Scanner::tokenize(source).map_err(|e| ParseError {
+            message: e.message,
+            line: e.line,
+            column: e.column,
+        })?;
+
+        let mut parser = Parser::new(tokens);
+        parser.parse_nodes(false)
+    }
+
     /// Parse a full document.
     fn parse_document(&mut self) -> Result<Document, ParseError> {
+        let nodes = self.parse_nodes(true)?;
+        Ok(Document { nodes })
+    }
+
+    /// Parse top-level nodes; `blocks` allows the document-level blocks.
+    fn parse_nodes(&mut self, blocks: bool) -> Result<Vec<Node>, ParseError> {
         let mut nodes = Vec::new();
 
         while !self.is_at_end() {
@@ -51,13 +95,42 @@ impl Parser {
                 break;
             }
 
+            if !blocks {
+                let keyword = match self.peek().kind {
+                    TokenKind::State => Some("state"),
+                    TokenKind::Const => Some("const"),
+                    TokenKind::Computed => Some("computed"),
+                    TokenKind::Effect => Some("effect"),
+                    TokenKind::Page => Some("page"),
+                    TokenKind::Config => Some("config"),
+                    _ => None,
+                };
+                if let Some(keyword) = keyword {
+                    return Err(self.error(format!(
+                        "A `{keyword}` block belongs to a full document, not a fragment"
+                    )));
+                }
+            }
+
             match &self.peek().kind {
                 TokenKind::State => {
                     nodes.push(self.parse_state_block()?);
                 }
+                TokenKind::Const => {
+                    nodes.push(self.parse_const_block()?);
+                }
                 TokenKind::Computed => {
                     nodes.push(self.parse_computed_block()?);
                 }
+                TokenKind::Effect => {
+                    nodes.push(self.parse_effect_block()?);
+                }
+                TokenKind::Page => {
+                    nodes.push(self.parse_page_block()?);
+                }
+                TokenKind::Config => {
+                    nodes.push(self.parse_config_block()?);
+                }
                 TokenKind::Comment(_) => {
                     if let TokenKind::Comment(text) = &self.peek().kind {
                         let text = text.clone();
@@ -68,14 +141,22 @@ impl Parser {
                 TokenKind::Identifier(_) | TokenKind::Dot => {
                     nodes.push(self.parse_element()?);
                 }
-                _ => {
-                    // Skip unexpected tokens at top level
+                TokenKind::Raw => {
+                    nodes.push(self.parse_raw()?);
+                }
+                TokenKind::Fragment => {
+                    nodes.push(self.parse_fragment_node()?);
+                }
+                TokenKind::Dedent => {
                     self.advance();
                 }
+                other => {
+                    return Err(self.error(format!("Unexpected {other:?} at top level")));
+                }
             }
         }
 
-        Ok(Document { nodes })
+        Ok(nodes)
     }
 
     // =========================================================================
@@ -89,8 +170,24 @@ impl Parser {
     ///   loading: false
     /// ```
     fn parse_state_block(&mut self) -> Result<Node, ParseError> {
-        self.advance(); // consume `state`
-        self.skip_newlines();
+        let fields = self.parse_value_fields("state")?;
+        Ok(Node::StateBlock(StateBlock { fields }))
+    }
+
+    /// Parse `const` block, written like a `state` block:
+    /// ```text
+    /// const
+    ///   MAX: 100
+    /// ```
+    fn parse_const_block(&mut self) -> Result<Node, ParseError> {
+        let fields = self.parse_value_fields("const")?;
+        Ok(Node::ConstBlock(ConstBlock { fields }))
+    }
+
+    /// The `name: value` fields of a `state` or `const` block.
+    fn parse_value_fields(&mut self, block: &str) -> Result<Vec<StateField>, ParseError> {
+        self.advance(); // consume `state` or `const`
+        self.skip_trivia();
 
         let mut fields = Vec::new();
 
@@ -98,23 +195,28 @@ impl Parser {
             self.advance(); // consume indent
 
             while self.peek().kind != TokenKind::Dedent && !self.is_at_end() {
-                self.skip_newlines();
+                self.skip_trivia();
                 if self.peek().kind == TokenKind::Dedent {
                     break;
                 }
 
                 // Parse field: name: value
+                let start = self.peek().span;
                 let name = self.expect_identifier()?;
 
                 if self.peek().kind != TokenKind::Colon {
-                    return Err(self.error(format!("Expected ':' after state field '{name}'")));
+                    return Err(self.error(format!("Expected ':' after {block} field '{name}'")));
                 }
                 self.advance(); // consume :
 
                 let value = self.parse_inline_value()?;
-                fields.push(StateField { name, value });
+                fields.push(StateField {
+                    name,
+                    value,
+                    span: self.span_from(start),
+                });
 
-                self.skip_newlines();
+                self.skip_trivia();
             }
 
             if self.peek().kind == TokenKind::Dedent {
@@ -122,7 +224,7 @@ impl Parser {
             }
         }
 
-        Ok(Node::StateBlock(StateBlock { fields }))
+        Ok(fields)
     }
 
     /// Parse `computed` block:
@@ -132,7 +234,7 @@ impl Parser {
     /// ```
     fn parse_computed_block(&mut self) -> Result<Node, ParseError> {
         self.advance(); // consume `computed`
-        self.skip_newlines();
+        self.skip_trivia();
 
         let mut fields = Vec::new();
 
@@ -140,7 +242,7 @@ impl Parser {
             self.advance(); // consume indent
 
             while self.peek().kind != TokenKind::Dedent && !self.is_at_end() {
-                self.skip_newlines();
+                self.skip_trivia();
                 if self.peek().kind == TokenKind::Dedent {
                     break;
                 }
@@ -152,12 +254,26 @@ impl Parser {
                 }
                 self.advance(); // consume :
 
-                // Computed values are expressions — collect remaining tokens on the line
-                let expr_source = self.collect_to_newline();
-                let body = ExprParser::parse(&expr_source)?;
+                // The scanner keeps the value as written for the expression parser
+                let TokenKind::ExprText(source) = &self.peek().kind else {
+                    return Err(self.error(format!("Expected a value for computed field '{name}'")));
+                };
+                let span = self.peek().span;
+                let at = |e: ParseError, offset: usize| ParseError {
+                    message: e.message,
+                    line: span.line,
+                    column: span.column + offset + e.column - 1,
+                };
+                let mut body = ExprParser::parse(source).map_err(|e| at(e, 0))?;
+                // A double-quoted value holds the expression, as in
+                // `:show="count > 0"`; a single-quoted one is a string
+                if let (ExprKind::String(quoted), true) = (&body.kind, source.starts_with('"')) {
+                    body = ExprParser::parse(&unescape_braces(quoted)).map_err(|e| at(e, 1))?;
+                }
+                self.advance();
                 fields.push(ComputedField { name, body });
 
-                self.skip_newlines();
+                self.skip_trivia();
             }
 
             if self.peek().kind == TokenKind::Dedent {
@@ -168,6 +284,153 @@ impl Parser {
         Ok(Node::ComputedBlock(ComputedBlock { fields }))
     }
 
+    /// Parse `effect` block:
+    /// ```text
+    /// effect
+    ///   document.title = count + " items"
+    /// ```
+    fn parse_effect_block(&mut self) -> Result<Node, ParseError> {
+        self.advance(); // consume `effect`
+        self.skip_trivia();
+
+        let mut body = Vec::new();
+
+        if self.peek().kind == TokenKind::Indent {
+            self.advance(); // consume indent
+
+            while self.peek().kind != TokenKind::Dedent && !self.is_at_end() {
+                self.skip_trivia();
+                match &self.peek().kind {
+                    TokenKind::Dedent => break,
+                    // The scanner keeps each line as written
+                    TokenKind::ExprText(source) => {
+                        let span = self.peek().span;
+                        let statement =
+                            ExprParser::parse_statements(source).map_err(|e| ParseError {
+                                message: e.message,
+                                line: span.line,
+                                column: span.column + e.column - 1,
+                            })?;
+                        match statement.kind {
+                            ExprKind::Sequence(statements) => body.extend(statements),
+                            _ => body.push(statement),
+                        }
+                        self.advance();
+                    }
+                    other => {
+                        return Err(self.error(format!("Unexpected {other:?} in effect block")));
+                    }
+                }
+            }
+
+            if self.peek().kind == TokenKind::Dedent {
+                self.advance();
+            }
+        }
+
+        if body.is_empty() {
+            return Err(self.error("An effect block needs at least one statement".into()));
+        }
+        Ok(Node::EffectBlock(EffectBlock { body }))
+    }
+
+    /// Parse `page` block:
+    /// ```text
+    /// page
+    ///   title: "Dashboard"
+    ///   viewport: "width=device-width, initial-scale=1"
+    /// ```
+    fn parse_page_block(&mut self) -> Result<Node, ParseError> {
+        let fields = self
+            .parse_static_fields("Page")?
+            .into_iter()
+            .map(|(name, value)| PageField { name, value })
+            .collect();
+        Ok(Node::PageBlock(PageBlock { fields }))
+    }
+
+    /// Parse `config` block:
+    /// ```text
+    /// config
+    ///   minify: true
+    ///   runtime: external
+    /// ```
+    fn parse_config_block(&mut self) -> Result<Node, ParseError> {
+        let fields = self
+            .parse_static_fields("Config")?
+            .into_iter()
+            .map(|(name, value)| ConfigField { name, value })
+            .collect();
+        Ok(Node::ConfigBlock(ConfigBlock { fields }))
+    }
+
+    /// Parse the keyword and indented `name: value` lines of a `page` or
+    /// `config` block. Values must be literals or bare words; a name may be
+    /// quoted.
+    fn parse_static_fields(&mut self, block: &str) -> Result<Vec<(String, String)>, ParseError> {
+        self.advance(); // consume `page` / `config`
+        self.skip_trivia();
+
+        let mut fields = Vec::new();
+
+        if self.peek().kind == TokenKind::Indent {
+            self.advance(); // consume indent
+
+            while self.peek().kind != TokenKind::Dedent && !self.is_at_end() {
+                self.skip_trivia();
+                if self.peek().kind == TokenKind::Dedent {
+                    break;
+                }
+
+                let name = match &self.peek().kind {
+                    // Quoted, for names containing `:` such as `"og:title"`
+                    TokenKind::String(s) => {
+                        let name = s.clone();
+                        self.advance();
+                        name
+                    }
+                    _ => self.expect_identifier()?,
+                };
+
+                if self.peek().kind != TokenKind::Colon {
+                    return Err(self.error(format!(
+                        "Expected ':' after {} field '{name}'",
+                        block.to_lowercase()
+                    )));
+                }
+                self.advance(); // consume :
+
+                let value = match &self.peek().kind {
+                    TokenKind::String(s) => unescape_braces(s),
+                    TokenKind::Identifier(s) => s.clone(),
+                    TokenKind::Number(n) => n.to_string(),
+                    TokenKind::Boolean(b) => b.to_string(),
+                    other => {
+                        return Err(self.error(format!(
+                            "{block} field '{name}' must be a static value, got {other:?}"
+                        )))
+                    }
+                };
+                self.advance();
+                if self.peek().kind == TokenKind::Colon && self.next_is_adjacent() {
+                    return Err(self.error(format!(
+                        "Quote a {} field name containing ':', as in \"{name}:{value}\"",
+                        block.to_lowercase()
+                    )));
+                }
+                fields.push((name, value));
+
+                self.skip_trivia();
+            }
+
+            if self.peek().kind == TokenKind::Dedent {
+                self.advance();
+            }
+        }
+
+        Ok(fields)
+    }
+
     // =========================================================================
     // Element parsing
     // =========================================================================
@@ -178,7 +441,28 @@ impl Parser {
     ///   span "Hello"
     /// ```
     fn parse_element(&mut self) -> Result<Node, ParseError> {
+        if self.depth >= self.max_depth {
+            return Err(self.error(format!(
+                "Elements are nested more than {} levels deep",
+                self.max_depth
+            )));
+        }
+
+        let mut element = self.parse_element_line()?;
+
+        self.depth += 1;
+        let children = self.parse_element_body(&mut element);
+        self.depth -= 1;
+        children?;
+
+        Ok(Node::Element(element))
+    }
+
+    /// Stages 1 and 2 of [`Parser::parse_element`]: the tag and everything on
+    /// its line. Kept out of the recursive path so deep nesting uses less stack.
+    fn parse_element_line(&mut self) -> Result<Element, ParseError> {
         // Stage 1: Tag name (optional if starts with dot)
+        let start = self.peek().span;
         let tag = if let TokenKind::Identifier(name) = &self.peek().kind {
             let name = name.clone();
             self.advance();
@@ -188,20 +472,38 @@ impl Parser {
             "div".to_string()
         };
 
+        let mut element = self.parse_element_rest(tag)?;
+        element.span = self.span_from(start);
+        Ok(element)
+    }
+
+    /// Stage 2 of [`Parser::parse_element_line`]: classes, attributes, and
+    /// inline text following the tag.
+    fn parse_element_rest(&mut self, tag: String) -> Result<Element, ParseError> {
         let mut classes = Vec::new();
         let mut attributes = Vec::new();
         let mut children = Vec::new();
+        let mut for_loop = None;
+        let mut spreads = Vec::new();
+        let mut class_toggles = Vec::new();
 
         // Stage 2: Inline modifiers
         let mut has_seen_class = false;
 
         loop {
+            let start = self.peek().span;
             match &self.peek().kind {
-                // .class
+                // .class, or .class:cond — toggled by a condition written
+                // without spaces, unlike a `:directive`
                 TokenKind::Dot => {
                     self.advance();
                     let class = self.expect_identifier()?;
-                    classes.push(class);
+                    if self.peek().kind == TokenKind::Colon && self.next_is_adjacent() {
+                        self.advance(); // consume :
+                        class_toggles.push((class, self.parse_expression_value()?));
+                    } else {
+                        classes.push(class);
+                    }
                     has_seen_class = true;
                 }
 
@@ -209,7 +511,21 @@ impl Parser {
                 TokenKind::Identifier(name) => {
                     let name = name.clone();
 
-                    if self.peek_next_is_equals() {
+                    if let Some(name) = self.namespaced_attr_name() {
+                        // xlink:href="#icon" — written without spaces, unlike a `:directive`
+                        self.advance(); // consume prefix
+                        self.advance(); // consume :
+                        self.advance(); // consume local name
+                        self.advance(); // consume =
+                        let value = self.parse_plain_value()?;
+                        attributes.push(Attribute {
+                            name,
+                            value: Some(value),
+                            prefix: None,
+                            modifiers: Vec::new(),
+                            span: self.span_from(start),
+                        });
+                    } else if self.peek_next_is_equals() {
                         // Plain attribute: name="value" — store as string literal
                         self.advance(); // consume name
                         self.advance(); // consume =
@@ -219,13 +535,22 @@ impl Parser {
                             value: Some(value),
                             prefix: None,
                             modifiers: Vec::new(),
+                            span: self.span_from(start),
                         });
                     } else if has_seen_class {
                         // Additional class (bare identifier after first .class)
                         self.advance();
                         classes.push(name);
                     } else {
-                        break;
+                        // Boolean attribute (bare identifier before any class)
+                        self.advance();
+                        attributes.push(Attribute {
+                            name,
+                            value: None,
+                            prefix: None,
+                            modifiers: Vec::new(),
+                            span: self.span_from(start),
+                        });
                     }
                 }
 
@@ -234,6 +559,20 @@ impl Parser {
                     self.advance();
                     let name = self.expect_identifier()?;
 
+                    if name == "for" {
+                        for_loop = Some(self.parse_for_value()?);
+                        continue;
+                    }
+
+                    // `:show.hidden`; a spaced `.name` is a class
+                    let modifiers = self.parse_modifiers(true)?;
+                    if name == "class" && !modifiers.is_empty() {
+                        return Err(self.error(format!(
+                            "`:class` takes no modifiers; toggle a single class with `.{}:condition`",
+                            modifiers[0].name
+                        )));
+                    }
+
                     let value = if self.peek().kind == TokenKind::Equals {
                         self.advance();
                         Some(self.parse_expression_value()?)
@@ -245,7 +584,8 @@ impl Parser {
                         name,
                         value,
                         prefix: Some(AttributePrefix::State),
-                        modifiers: Vec::new(),
+                        modifiers,
+                        span: self.span_from(start),
                     });
                 }
 
@@ -255,15 +595,11 @@ impl Parser {
                     let name = self.expect_identifier()?;
 
                     // Parse .modifier chains
-                    let mut modifiers = Vec::new();
-                    while self.peek().kind == TokenKind::Dot {
-                        self.advance();
-                        modifiers.push(self.expect_identifier()?);
-                    }
+                    let modifiers = self.parse_modifiers(false)?;
 
                     let value = if self.peek().kind == TokenKind::Equals {
                         self.advance();
-                        Some(self.parse_expression_value()?)
+                        Some(self.parse_handler_value()?)
                     } else {
                         None
                     };
@@ -273,6 +609,7 @@ impl Parser {
                         value,
                         prefix: Some(AttributePrefix::Event),
                         modifiers,
+                        span: self.span_from(start),
                     });
                 }
 
@@ -293,6 +630,7 @@ impl Parser {
                         value,
                         prefix: Some(AttributePrefix::Server),
                         modifiers: Vec::new(),
+                        span: self.span_from(start),
                     });
                 }
 
@@ -302,52 +640,277 @@ impl Parser {
                     self.advance();
                 }
 
-                _ => break,
+                // Attribute spread: div {...attrs}
+                TokenKind::Interpolation(expr) if expr.trim_start().starts_with("...") => {
+                    let source = expr.trim_start()[3..].to_string();
+                    self.advance();
+                    spreads.push(ExprParser::parse(&source)?);
+                }
+
+                // Bare interpolation: span {count}
+                TokenKind::Interpolation(expr) => {
+                    children.push(Node::Text(format!("{{{expr}}}")));
+                    self.advance();
+                }
+
+                _ => {
+                    if let Some(name) = self.unquoted_directive_before(&attributes) {
+                        return Err(self.error(format!(
+                            "Unexpected {:?} after the unquoted value of {name}; quote the whole expression, as in {name}=\"count > 0\"",
+                            self.peek().kind
+                        )));
+                    }
+                    break;
+                }
+            }
+        }
+
+        let has_key = attributes
+            .iter()
+            .any(|a| a.prefix == Some(AttributePrefix::State) && a.name == "key");
+        if has_key && for_loop.is_none() {
+            return Err(self.error(format!(
+                "':key' is only valid on an element with ':for' (on <{tag}>)"
+            )));
+        }
+
+        Ok(Element {
+            tag,
+            classes,
+            attributes,
+            children,
+            for_loop,
+            spreads,
+            class_toggles,
+            ..Default::default()
+        })
+    }
+
+    /// Parse the `.name` chain after an attribute name; a number after a
+    /// name is its argument (`.debounce.300`). With `adjacent`, a `.name`
+    /// after whitespace ends the chain, since it is a class.
+    fn parse_modifiers(&mut self, adjacent: bool) -> Result<Vec<Modifier>, ParseError> {
+        let mut modifiers: Vec<Modifier> = Vec::new();
+        while self.peek().kind == TokenKind::Dot && (!adjacent || self.next_is_adjacent()) {
+            self.advance();
+            if let TokenKind::Number(n) = self.peek().kind {
+                let Some(last) = modifiers.last().filter(|m| m.arg.is_none()) else {
+                    return Err(self.error(format!(
+                        "Unexpected number {n}; a modifier's argument follows its name, as in `.debounce.300`"
+                    )));
+                };
+                // Only the timing modifiers take an argument: whole milliseconds
+                if !matches!(last.name.as_str(), "debounce" | "throttle") {
+                    return Err(self.error(format!(
+                        "`.{}` takes no argument; only .debounce and .throttle take milliseconds",
+                        last.name
+                    )));
+                }
+                if n < 0.0 || n != (n as u64) as f64 {
+                    return Err(self.error(format!(
+                        "`.{}` takes a whole number of milliseconds, not {n}",
+                        last.name
+                    )));
+                }
+                if let Some(last) = modifiers.last_mut() {
+                    last.arg = Some(n.to_string());
+                }
+                self.advance();
+                continue;
+            }
+            modifiers.push(Modifier::new(self.expect_identifier()?));
+        }
+        Ok(modifiers)
+    }
+
+    /// Stage 3 of [`Parser::parse_element`]: the indented block. Lines at
+    /// its start marked with `+` carry the class list on:
+    /// ```text
+    /// div .flex items-center
+    ///   +.gap-2 px-4
+    ///   span "Hello"
+    /// ```
+    /// Without the `+`, a class-only line is a child `div` as usual.
+    fn parse_element_body(&mut self, element: &mut Element) -> Result<(), ParseError> {
+        self.skip_newlines();
+        let continues = self.tokens.peek_kind(1) == &TokenKind::Plus;
+        if !(self.peek().kind == TokenKind::Indent && continues) {
+            return self.parse_children(&mut element.children);
+        }
+
+        self.advance(); // consume indent
+        while self.peek().kind == TokenKind::Plus {
+            self.advance();
+            if self.peek().kind != TokenKind::Dot {
+                return Err(self.error("Expected a class after '+', as in `+.gap-2`".into()));
+            }
+            while !matches!(
+                self.peek().kind,
+                TokenKind::Newline | TokenKind::Dedent | TokenKind::Eof
+            ) {
+                if self.peek().kind == TokenKind::Dot {
+                    self.advance();
+                }
+                element.classes.push(self.expect_identifier()?);
+            }
+            self.skip_newlines();
+            if self.peek().kind == TokenKind::Indent {
+                return Err(self.error("A '+' class line can't have children".into()));
             }
         }
+        self.parse_child_lines(&mut element.children)
+    }
 
-        // Stage 3: Children (indented block)
+    /// The indented block of children of an element, fragment, or component.
+    fn parse_children(&mut self, children: &mut Vec<Node>) -> Result<(), ParseError> {
         self.skip_newlines();
 
         if self.peek().kind == TokenKind::Indent {
             self.advance(); // consume indent
+            self.parse_child_lines(children)?;
+        }
 
-            while self.peek().kind != TokenKind::Dedent && !self.is_at_end() {
-                self.skip_newlines();
-                if self.peek().kind == TokenKind::Dedent {
-                    break;
+        Ok(())
+    }
+
+    /// The lines of an indented block after its `Indent`, through the
+    /// closing `Dedent`.
+    fn parse_child_lines(&mut self, children: &mut Vec<Node>) -> Result<(), ParseError> {
+        while self.peek().kind != TokenKind::Dedent && !self.is_at_end() {
+            self.skip_newlines();
+            if self.peek().kind == TokenKind::Dedent {
+                break;
+            }
+
+            match &self.peek().kind {
+                TokenKind::Comment(text) => {
+                    let text = text.clone();
+                    self.advance();
+                    children.push(Node::Comment(text));
+                }
+                TokenKind::Identifier(_) | TokenKind::Dot => {
+                    children.push(self.parse_element()?);
+                }
+                TokenKind::String(text) => {
+                    children.push(Node::Text(text.clone()));
+                    self.advance();
                 }
+                TokenKind::Interpolation(expr) => {
+                    children.push(Node::Text(format!("{{{expr}}}")));
+                    self.advance();
+                }
+                TokenKind::Raw => {
+                    children.push(self.parse_raw()?);
+                }
+                TokenKind::Fragment => {
+                    children.push(self.parse_fragment_node()?);
+                }
+                TokenKind::Plus => {
+                    return Err(self.error(
+                        "A '+' class line must come right after its element's line".into(),
+                    ));
+                }
+                other => {
+                    return Err(self.error(format!("Unexpected {other:?} in element body")));
+                }
+            }
+        }
 
-                match &self.peek().kind {
-                    TokenKind::Comment(text) => {
-                        let text = text.clone();
-                        self.advance();
-                        children.push(Node::Comment(text));
-                    }
-                    TokenKind::Identifier(_) | TokenKind::Dot => {
-                        children.push(self.parse_element()?);
-                    }
-                    TokenKind::String(text) => {
-                        children.push(Node::Text(text.clone()));
-                        self.advance();
-                    }
-                    _ => {
-                        self.advance(); // skip unexpected
-                    }
+        if self.peek().kind == TokenKind::Dedent {
+            self.advance();
+        }
+
+        Ok(())
+    }
+
+    /// Parse a `fragment` grouping its children without a wrapper element:
+    /// ```text
+    /// fragment :if="show"
+    ///   h1 "A"
+    ///   p "B"
+    /// ```
+    fn parse_fragment_node(&mut self) -> Result<Node, ParseError> {
+        if self.depth >= self.max_depth {
+            return Err(self.error(format!(
+                "Elements are nested more than {} levels deep",
+                self.max_depth
+            )));
+        }
+
+        self.advance(); // consume `fragment`
+        let mut line = self.parse_element_rest("fragment".to_string())?;
+
+        let conditional = |a: &Attribute| {
+            a.prefix == Some(AttributePrefix::State) && matches!(a.name.as_str(), "if" | "show")
+        };
+        if !line.classes.is_empty()
+            || line.for_loop.is_some()
+            || !line.spreads.is_empty()
+            || !line.class_toggles.is_empty()
+            || !line.attributes.iter().all(conditional)
+        {
+            return Err(self.error(
+                "A fragment renders no element; it only accepts ':if' and ':show'".into(),
+            ));
+        }
+
+        self.depth += 1;
+        let children = self.parse_children(&mut line.children);
+        self.depth -= 1;
+        children?;
+
+        let mut nodes = line.children;
+        if !line.attributes.is_empty() {
+            self.apply_conditions(&mut nodes, &line.attributes)?;
+        }
+        Ok(Node::Fragment(nodes))
+    }
+
+    /// Copy a fragment's `:if`/`:show` onto every element it groups.
+    fn apply_conditions(
+        &self,
+        nodes: &mut [Node],
+        conditions: &[Attribute],
+    ) -> Result<(), ParseError> {
+        for node in nodes {
+            match node {
+                Node::Element(el) => el.attributes.extend(conditions.iter().cloned()),
+                Node::Fragment(children) => self.apply_conditions(children, conditions)?,
+                Node::Comment(_) => {}
+                _ => {
+                    return Err(self.error(
+                        "Text in a conditional fragment must be wrapped in an element".into(),
+                    ))
                 }
             }
+        }
+        Ok(())
+    }
 
-            if self.peek().kind == TokenKind::Dedent {
+    /// Parse a `raw` block, either indented or inline:
+    /// ```text
+    /// raw
+    ///   <svg viewBox="0 0 24 24"><path d="M0 0h24v24H0z"/></svg>
+    /// raw "<hr>"
+    /// ```
+    fn parse_raw(&mut self) -> Result<Node, ParseError> {
+        self.advance(); // consume `raw`
+
+        match &self.peek().kind {
+            TokenKind::RawText(text) => {
+                let text = text.clone();
+                self.advance();
+                Ok(Node::Raw(text))
+            }
+            TokenKind::String(text) => {
+                let text = unescape_braces(text);
                 self.advance();
+                Ok(Node::Raw(text))
             }
+            _ => Err(self.error(
+                "Expected an indented block or string after 'raw'".into(),
+            )),
         }
-
-        Ok(Node::Element(Element {
-            tag,
-            classes,
-            attributes,
-            children,
-        }))
     }
 
     // =========================================================================
@@ -356,47 +919,121 @@ impl Parser {
 
     /// Parse a prefixed attribute value (`:show="expr"`, `@click="expr"`) — string content
     /// is passed to ExprParser since it contains reactive expressions.
+    ///
+    /// Only a single name or literal may go unquoted (`:show=visible`); anything
+    /// more (`:show=a.b`, `:show=count-1`) is an error asking for quotes.
     fn parse_expression_value(&mut self) -> Result<Expression, ParseError> {
-        match &self.peek().kind {
+        let kind = match &self.peek().kind {
             TokenKind::String(s) => {
-                let s = s.clone();
+                let s = unescape_braces(s);
                 self.advance();
-                ExprParser::parse(&s)
+                return ExprParser::parse(&s);
             }
-            TokenKind::Identifier(s) => {
-                let s = s.clone();
-                self.advance();
-                Ok(Expression {
-                    kind: ExprKind::Identifier(s),
-                    span: ExprSpan::new(0, 0),
-                })
-            }
-            TokenKind::Number(n) => {
-                let n = *n;
-                self.advance();
-                Ok(Expression {
-                    kind: ExprKind::Number(n),
-                    span: ExprSpan::new(0, 0),
-                })
+            TokenKind::Identifier(s) if s.contains('-') => {
+                return Err(self.error(format!(
+                    "Unquoted value '{s}' is not a name; quote the whole expression, as in \"{s}\""
+                )))
             }
-            TokenKind::Boolean(b) => {
-                let b = *b;
-                self.advance();
-                Ok(Expression {
-                    kind: ExprKind::Boolean(b),
-                    span: ExprSpan::new(0, 0),
-                })
+            TokenKind::Identifier(s) => ExprKind::Identifier(s.clone()),
+            TokenKind::Number(n) => ExprKind::Number(*n),
+            TokenKind::Boolean(b) => ExprKind::Boolean(*b),
+            _ => {
+                return Err(self.error(format!(
+                    "Expected attribute value, got {:?}",
+                    self.peek().kind
+                )))
             }
-            _ => Err(self.error(format!(
-                "Expected attribute value, got {:?}",
+        };
+        self.advance();
+        if self.next_is_adjacent() {
+            return Err(self.error(format!(
+                "Unquoted expression cannot continue with {:?}; quote the whole expression, as in :show=\"count > 0\"",
                 self.peek().kind
-            ))),
+            )));
+        }
+        Ok(Expression {
+            kind,
+            span: ExprSpan::new(0, 0),
+        })
+    }
+
+    /// Parse a `:for` value: `="item in items"`, where `items` is any expression.
+    fn parse_for_value(&mut self) -> Result<ForLoop, ParseError> {
+        if self.peek().kind != TokenKind::Equals {
+            return Err(self.error("Expected '=\"item in items\"' after ':for'".into()));
+        }
+        self.advance();
+
+        let TokenKind::String(source) = &self.peek().kind else {
+            return Err(self.error(format!(
+                "Expected a quoted ':for' value, got {:?}",
+                self.peek().kind
+            )));
+        };
+        let source = unescape_braces(source);
+
+        let Some((item, iterable)) = source.split_once(" in ") else {
+            return Err(self.error(format!(
+                "Invalid ':for' value \"{source}\": expected \"item in items\""
+            )));
+        };
+        // `(item, i)` also names the index
+        let item = item.trim();
+        let (item, index) = match item.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+            Some(names) => match names.split_once(',') {
+                Some((item, index)) => (item.trim(), Some(index.trim())),
+                None => (names.trim(), None),
+            },
+            None => (item, None),
+        };
+        for name in core::iter::once(item).chain(index) {
+            let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if !valid {
+                return Err(self.error(format!(
+                    "Invalid ':for' item name '{name}': expected an identifier"
+                )));
+            }
+        }
+        let iterable = ExprParser::parse(iterable.trim())?;
+        self.advance();
+
+        Ok(ForLoop {
+            item: item.to_string(),
+            index: index.map(str::to_string),
+            iterable,
+        })
+    }
+
+    /// Parse an event handler value (`@click="a = 1; b = 2"`) — like an expression
+    /// value, but quoted handlers may hold several `;`-separated statements.
+    fn parse_handler_value(&mut self) -> Result<Expression, ParseError> {
+        if let TokenKind::String(s) = &self.peek().kind {
+            let s = unescape_braces(s);
+            self.advance();
+            return ExprParser::parse_statements(&s);
         }
+        self.parse_expression_value()
     }
 
     /// Parse a plain attribute value (`href="/about"`, `type="text"`) — string content
     /// is stored as a string literal, NOT parsed as an expression.
+    ///
+    /// Unquoted values may only be a single identifier, number, or boolean
+    /// (`type=text`, `maxlength=20`); anything glued on (`logo.png`, `42px`) is an error.
     fn parse_plain_value(&mut self) -> Result<Expression, ParseError> {
+        let value = self.parse_plain_value_token()?;
+        let unquoted = !matches!(value.kind, ExprKind::String(_));
+        if unquoted && self.next_is_adjacent() {
+            return Err(self.error(format!(
+                "Unquoted attribute value cannot continue with {:?}; quote the whole value",
+                self.peek().kind
+            )));
+        }
+        Ok(value)
+    }
+
+    fn parse_plain_value_token(&mut self) -> Result<Expression, ParseError> {
         match &self.peek().kind {
             TokenKind::String(s) => {
                 let s = s.clone();
@@ -450,7 +1087,7 @@ impl Parser {
                 })
             }
             TokenKind::String(s) => {
-                let s = s.clone();
+                let s = unescape_braces(s);
                 self.advance();
                 Ok(Expression {
                     kind: ExprKind::String(s),
@@ -480,6 +1117,17 @@ impl Parser {
                     span: ExprSpan::new(0, 0),
                 })
             }
+            // An array or object literal, kept as written by the scanner
+            TokenKind::ExprText(source) => {
+                let span = self.peek().span;
+                let value = ExprParser::parse(source).map_err(|e| ParseError {
+                    message: e.message,
+                    line: span.line,
+                    column: span.column + e.column - 1,
+                })?;
+                self.advance();
+                Ok(value)
+            }
             _ => Err(self.error(format!(
                 "Expected value, got {:?}",
                 self.peek().kind
@@ -487,92 +1135,20 @@ impl Parser {
         }
     }
 
-    /// Collect remaining token text until newline/dedent/eof for expression parsing.
-    fn collect_to_newline(&mut self) -> String {
-        let mut parts = Vec::new();
-
-        while !self.is_at_end() {
-            match &self.peek().kind {
-                TokenKind::Newline | TokenKind::Dedent | TokenKind::Eof => break,
-                TokenKind::Identifier(s) => {
-                    parts.push(s.clone());
-                    self.advance();
-                }
-                TokenKind::Number(n) => {
-                    parts.push(n.to_string());
-                    self.advance();
-                }
-                TokenKind::String(s) => {
-                    parts.push(format!("\"{}\"", s));
-                    self.advance();
-                }
-                TokenKind::Boolean(b) => {
-                    parts.push(b.to_string());
-                    self.advance();
-                }
-                TokenKind::Null => {
-                    parts.push("null".into());
-                    self.advance();
-                }
-                TokenKind::Dot => {
-                    parts.push(".".into());
-                    self.advance();
-                }
-                TokenKind::Colon => {
-                    parts.push(":".into());
-                    self.advance();
-                }
-                TokenKind::Equals => {
-                    parts.push("=".into());
-                    self.advance();
-                }
-                TokenKind::Comma => {
-                    parts.push(",".into());
-                    self.advance();
-                }
-                TokenKind::LParen => {
-                    parts.push("(".into());
-                    self.advance();
-                }
-                TokenKind::RParen => {
-                    parts.push(")".into());
-                    self.advance();
-                }
-                TokenKind::Plus => {
-                    parts.push("+".into());
-                    self.advance();
-                }
-                _ => {
-                    self.advance();
-                }
-            }
-        }
-
-        parts.join(" ")
-    }
-
     // =========================================================================
     // Token navigation helpers
     // =========================================================================
 
     fn peek(&self) -> &Token {
-        static EOF: std::sync::LazyLock<Token> = std::sync::LazyLock::new(|| {
-            Token::new(
-                TokenKind::Eof,
-                hrml_lexer::Span::new(0, 0, 0, 0),
-            )
-        });
-        self.tokens.get(self.pos).unwrap_or(&EOF)
+        self.tokens.peek(0)
     }
 
     fn advance(&mut self) {
-        if self.pos < self.tokens.len() {
-            self.pos += 1;
-        }
+        self.tokens.next();
     }
 
     fn is_at_end(&self) -> bool {
-        self.pos >= self.tokens.len() || matches!(self.peek().kind, TokenKind::Eof)
+        self.tokens.is_at_end()
     }
 
     fn skip_newlines(&mut self) {
@@ -581,6 +1157,13 @@ impl Parser {
         }
     }
 
+    /// Skip line breaks and comments, for blocks that keep no comments.
+    fn skip_trivia(&mut self) {
+        while self.peek().kind.is_trivia() {
+            self.advance();
+        }
+    }
+
     fn expect_identifier(&mut self) -> Result<String, ParseError> {
         if let TokenKind::Identifier(name) = &self.peek().kind {
             let name = name.clone();
@@ -594,11 +1177,73 @@ impl Parser {
         }
     }
 
+    /// Check if the current token starts exactly where the previous one ended
+    /// (no whitespace between them) and is part of the same line.
+    fn next_is_adjacent(&self) -> bool {
+        let Some(prev) = self.tokens.previous() else {
+            return false;
+        };
+        let next = self.peek();
+        !matches!(
+            next.kind,
+            TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent | TokenKind::Eof
+        ) && next.span.start == prev.span.end
+    }
+
+    /// The directive (`:show`, `@click`, `$get`) whose unquoted value was the
+    /// last token consumed, when the line carries on past it (`:show=count > 0`).
+    fn unquoted_directive_before(&self, attributes: &[Attribute]) -> Option<String> {
+        let attr = attributes.last()?;
+        let prev = self.tokens.previous()?;
+        let prefix = match attr.prefix? {
+            AttributePrefix::State => ":",
+            AttributePrefix::Event => "@",
+            AttributePrefix::Server => "$",
+        };
+        let unquoted = matches!(
+            prev.kind,
+            TokenKind::Identifier(_) | TokenKind::Number(_) | TokenKind::Boolean(_)
+        );
+        let ends_line = matches!(
+            self.peek().kind,
+            TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent | TokenKind::Eof
+        );
+        (attr.value.is_some() && unquoted && !ends_line && attr.span.end == prev.span.end)
+            .then(|| format!("{prefix}{}", attr.name))
+    }
+
+    /// The name of a namespaced attribute starting at the current token:
+    /// `prefix:name=` with no whitespace around the `:`.
+    fn namespaced_attr_name(&self) -> Option<String> {
+        let [prefix, colon, local, equals] = [0, 1, 2, 3].map(|n| self.tokens.peek(n));
+        // Single-character tokens have empty spans, so `:` ends one past its start
+        match (&prefix.kind, &colon.kind, &local.kind, &equals.kind) {
+            (
+                TokenKind::Identifier(prefix_name),
+                TokenKind::Colon,
+                TokenKind::Identifier(local_name),
+                TokenKind::Equals,
+            ) if colon.span.start == prefix.span.end
+                && local.span.start == colon.span.start + 1 =>
+            {
+                Some(format!("{prefix_name}:{local_name}"))
+            }
+            _ => None,
+        }
+    }
+
+    /// A span from `start` through the end of the last consumed token.
+    fn span_from(&self, start: Span) -> Span {
+        let end = self
+            .tokens
+            .previous()
+            .map_or(start.end, |token| token.span.end);
+        Span { end, ..start }
+    }
+
     /// Check if the next token after current is Equals.
     fn peek_next_is_equals(&self) -> bool {
-        self.tokens
-            .get(self.pos + 1)
-            .is_some_and(|t| matches!(t.kind, TokenKind::Equals))
+        matches!(self.tokens.peek_kind(1), TokenKind::Equals)
     }
 
     fn error(&self, message: String) -> ParseError {
@@ -645,116 +1290,482 @@ mod tests {
     }
 
     #[test]
-    fn test_single_element() {
-        let doc = parse("div");
-        let el = first_element(&doc);
-        assert_eq!(el.tag, "div");
-        assert!(el.classes.is_empty());
-        assert!(el.attributes.is_empty());
-        assert!(el.children.is_empty());
+    fn test_single_element() {
+        let doc = parse("div");
+        let el = first_element(&doc);
+        assert_eq!(el.tag, "div");
+        assert!(el.classes.is_empty());
+        assert!(el.attributes.is_empty());
+        assert!(el.children.is_empty());
+    }
+
+    #[test]
+    fn test_multiple_elements() {
+        let doc = parse("div\nspan\np");
+        assert_eq!(doc.nodes.len(), 3);
+    }
+
+    // =========================================================================
+    // Classes
+    // =========================================================================
+
+    #[test]
+    fn test_single_class() {
+        let doc = parse("div .container");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["container"]);
+    }
+
+    #[test]
+    fn test_multiple_classes() {
+        let doc = parse("div .flex items-center gap-4");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["flex", "items-center", "gap-4"]);
+    }
+
+    #[test]
+    fn test_implicit_div() {
+        let doc = parse(".container");
+        let el = first_element(&doc);
+        assert_eq!(el.tag, "div");
+        assert_eq!(el.classes, vec!["container"]);
+    }
+
+    #[test]
+    fn test_classes_with_hyphen() {
+        let doc = parse("div .text-2xl bg-blue-500");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["text-2xl", "bg-blue-500"]);
+    }
+
+    #[test]
+    fn test_class_list_continues_on_plus_line() {
+        let doc = parse("div .flex items-center\n  +.gap-2 px-4\n  +.rounded\n  span \"Hi\"");
+        let el = first_element(&doc);
+        assert_eq!(
+            el.classes,
+            vec!["flex", "items-center", "gap-2", "px-4", "rounded"]
+        );
+        assert_eq!(el.children.len(), 1);
+        assert!(matches!(&el.children[0], Node::Element(span) if span.tag == "span"));
+
+        let doc = parse("div .flex\n  +.gap-2\np");
+        assert_eq!(first_element(&doc).classes, vec!["flex", "gap-2"]);
+        assert!(first_element(&doc).children.is_empty());
+        assert!(matches!(&doc.nodes[1], Node::Element(p) if p.tag == "p"));
+    }
+
+    #[test]
+    fn test_misplaced_plus_class_line_is_error() {
+        let err = Parser::parse("div .card\n  p \"Hi\"\n  +.footer").unwrap_err();
+        assert!(err.message.contains("right after its element's line"));
+        let err = Parser::parse("div .card\n  +.wide\n    p \"Hi\"").unwrap_err();
+        assert!(err.message.contains("can't have children"));
+        let err = Parser::parse("div .card\n  + wide").unwrap_err();
+        assert!(err.message.contains("Expected a class after '+'"));
+    }
+
+    #[test]
+    fn test_class_only_lines_are_children() {
+        let doc = parse("div .row\n  .cell\n  .cell");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["row"]);
+        assert_eq!(el.children.len(), 2);
+        for child in &el.children {
+            assert!(
+                matches!(child, Node::Element(div) if div.tag == "div" && div.classes == ["cell"])
+            );
+        }
+
+        let doc = parse("div \"Title\"\n  .divider");
+        let el = first_element(&doc);
+        assert!(matches!(&el.children[1], Node::Element(div) if div.classes == ["divider"]));
+        let doc = parse("div .card\n  .body\n    p \"Hi\"");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["card"]);
+        assert!(matches!(&el.children[0], Node::Element(div) if div.classes == ["body"]));
+    }
+
+    // =========================================================================
+    // Attributes
+    // =========================================================================
+
+    #[test]
+    fn test_attribute_with_string() {
+        let doc = parse("a href=\"/about\"");
+        let el = first_element(&doc);
+        assert_eq!(el.attributes.len(), 1);
+        assert_eq!(el.attributes[0].name, "href");
+        assert!(el.attributes[0].prefix.is_none());
+    }
+
+    #[test]
+    fn test_multiple_attributes() {
+        let doc = parse("img src=\"logo.png\" alt=\"Logo\"");
+        let el = first_element(&doc);
+        assert_eq!(el.attributes.len(), 2);
+        assert_eq!(el.attributes[0].name, "src");
+        assert_eq!(el.attributes[1].name, "alt");
+    }
+
+    #[test]
+    fn test_class_then_attribute() {
+        let doc = parse("input .form-input type=\"text\" name=\"email\"");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["form-input"]);
+        assert_eq!(el.attributes.len(), 2);
+    }
+
+    #[test]
+    fn test_unquoted_attribute_values() {
+        let doc = parse("input type=text maxlength=20 spellcheck=false");
+        let el = first_element(&doc);
+        let values: Vec<&ExprKind> = el
+            .attributes
+            .iter()
+            .map(|a| &a.value.as_ref().unwrap().kind)
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                &ExprKind::Identifier("text".into()),
+                &ExprKind::Number(20.0),
+                &ExprKind::Boolean(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_global_attributes_are_plain() {
+        let doc = parse(
+            "div role=button tabindex=-1 title=\"Save (Ctrl+S)\" lang=en dir=rtl style=\"color:red\"",
+        );
+        let el = first_element(&doc);
+        assert!(el.attributes.iter().all(|a| a.prefix.is_none()));
+        let attrs: Vec<(&str, &ExprKind)> = el
+            .attributes
+            .iter()
+            .map(|a| (a.name.as_str(), &a.value.as_ref().unwrap().kind))
+            .collect();
+        assert_eq!(
+            attrs,
+            vec![
+                ("role", &ExprKind::Identifier("button".into())),
+                ("tabindex", &ExprKind::Number(-1.0)),
+                ("title", &ExprKind::String("Save (Ctrl+S)".into())),
+                ("lang", &ExprKind::Identifier("en".into())),
+                ("dir", &ExprKind::Identifier("rtl".into())),
+                ("style", &ExprKind::String("color:red".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unquoted_attribute_value_with_dot_is_error() {
+        let err = Parser::parse("img src=logo.png").unwrap_err();
+        assert!(err.message.contains("quote the whole value"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_unquoted_attribute_value_with_unit_is_error() {
+        assert!(Parser::parse("div width=42px").is_err());
+    }
+
+    #[test]
+    fn test_unquoted_directive_expression_is_error() {
+        let doc = parse("p :show=visible .note \"Hi\"");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["note"]);
+
+        for source in [
+            "p :show=user.name",
+            "p :show=count-1",
+            "p .on:a.b",
+            "p :show=count>0",
+            "p :show=count > 0",
+            "p @click.prevent=n ++",
+        ] {
+            let err = Parser::parse(source).unwrap_err();
+            assert!(
+                err.message.contains("quote the whole expression"),
+                "{source}: {}",
+                err.message
+            );
+        }
+    }
+
+    #[test]
+    fn test_unquoted_path_is_error() {
+        let err = Parser::parse("a href=/about").unwrap_err();
+        assert!(err.message.contains("quote paths and URLs"), "{}", err.message);
+    }
+
+    // =========================================================================
+    // Spans
+    // =========================================================================
+
+    #[test]
+    fn test_element_span_covers_tag_through_last_attribute() {
+        let source = "state\n  count: 0\n\nul\n  li .item title=\"x\" @click=\"go()\"\n";
+        let doc = parse(source);
+        let Node::Element(ul) = &doc.nodes[1] else {
+            panic!("expected an element");
+        };
+        let Node::Element(li) = &ul.children[0] else {
+            panic!("expected an element");
+        };
+        let (start, end) = (source.find("li").unwrap(), source.find(")\"").unwrap() + 2);
+        assert_eq!((li.span.start, li.span.end), (start, end));
+        assert_eq!((li.span.line, li.span.column), (5, 3));
+        assert_eq!(
+            &source[li.span.start..li.span.end],
+            "li .item title=\"x\" @click=\"go()\""
+        );
+
+        let click = &li.attributes[1];
+        assert_eq!(&source[click.span.start..click.span.end], "@click=\"go()\"");
+
+        let Node::StateBlock(state) = &doc.nodes[0] else {
+            panic!("expected a state block");
+        };
+        let field = &state.fields[0];
+        assert_eq!(&source[field.span.start..field.span.end], "count: 0");
+        assert_eq!((field.span.line, field.span.column), (2, 3));
+    }
+
+    // =========================================================================
+    // State directives
+    // =========================================================================
+
+    #[test]
+    fn test_show_directive() {
+        let doc = parse("div :show=\"visible\"");
+        let el = first_element(&doc);
+        assert_eq!(el.attributes.len(), 1);
+        assert_eq!(el.attributes[0].name, "show");
+        assert_eq!(el.attributes[0].prefix, Some(AttributePrefix::State));
+    }
+
+    #[test]
+    fn test_model_directive() {
+        let doc = parse("input :model=\"name\"");
+        let el = first_element(&doc);
+        assert_eq!(el.attributes[0].name, "model");
+        assert_eq!(el.attributes[0].prefix, Some(AttributePrefix::State));
+    }
+
+    #[test]
+    fn test_if_directive() {
+        let doc = parse("div :if=\"count > 0\"");
+        let el = first_element(&doc);
+        assert_eq!(el.attributes[0].name, "if");
+    }
+
+    // =========================================================================
+    // Event handlers
+    // =========================================================================
+
+    #[test]
+    fn test_for_directive() {
+        let doc = parse("li :for=\"todo in todos\" \"{todo}\"");
+        let el = first_element(&doc);
+        let for_loop = el.for_loop.as_ref().unwrap();
+        assert_eq!(for_loop.item, "todo");
+        assert_eq!(for_loop.index, None);
+        assert_eq!(
+            for_loop.iterable.kind,
+            ExprKind::Identifier("todos".into())
+        );
+        assert!(el.attributes.is_empty());
+    }
+
+    #[test]
+    fn test_for_with_index() {
+        let doc = parse("li :for=\"(item, i) in items\" \"{i}: {item}\"");
+        let for_loop = first_element(&doc).for_loop.as_ref().unwrap();
+        assert_eq!(for_loop.item, "item");
+        assert_eq!(for_loop.index.as_deref(), Some("i"));
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (element li (for (item i) items)\n    (text \"{i}: {item}\")))"
+        );
+
+        let err = Parser::parse("li :for=\"(item, 0) in items\"").unwrap_err();
+        assert!(err.message.contains("'0'"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_for_with_key() {
+        let doc = parse("li :for=\"t in todos\" :key=\"t.id\" \"{t.title}\"");
+        let el = first_element(&doc);
+        assert_eq!(el.for_loop.as_ref().unwrap().item, "t");
+        assert_eq!(el.attributes.len(), 1);
+        assert_eq!(el.attributes[0].name, "key");
+        assert_eq!(el.attributes[0].prefix, Some(AttributePrefix::State));
+        assert!(matches!(
+            el.attributes[0].value.as_ref().unwrap().kind,
+            ExprKind::Member { .. }
+        ));
+    }
+
+    #[test]
+    fn test_for_without_in_is_error() {
+        let err = Parser::parse("li :for=\"todos\"").unwrap_err();
+        assert!(err.message.contains("expected \"item in items\""));
+    }
+
+    #[test]
+    fn test_key_without_for_is_error() {
+        let err = Parser::parse("li :key=\"t.id\"").unwrap_err();
+        assert!(err.message.contains("':key' is only valid"));
     }
 
     #[test]
-    fn test_multiple_elements() {
-        let doc = parse("div\nspan\np");
-        assert_eq!(doc.nodes.len(), 3);
+    fn test_stray_token_at_top_level_is_error() {
+        let err = Parser::parse("div\n)\n").unwrap_err();
+        assert!(err.message.contains("Unexpected RParen at top level"));
+        assert_eq!((err.line, err.column), (2, 1));
     }
 
-    // =========================================================================
-    // Classes
-    // =========================================================================
+    #[test]
+    fn test_stray_token_in_element_body_is_error() {
+        let err = Parser::parse("div\n  = \"x\"\n").unwrap_err();
+        assert!(err.message.contains("in element body"));
+    }
 
     #[test]
-    fn test_single_class() {
-        let doc = parse("div .container");
+    fn test_namespaced_attribute() {
+        let doc = parse("use xlink:href=\"#icon\" :show=\"visible\"");
         let el = first_element(&doc);
-        assert_eq!(el.classes, vec!["container"]);
+        assert_eq!(el.attributes[0].name, "xlink:href");
+        assert_eq!(el.attributes[0].prefix, None);
+        assert_eq!(el.attributes[1].name, "show");
+        assert_eq!(el.attributes[1].prefix, Some(AttributePrefix::State));
     }
 
     #[test]
-    fn test_multiple_classes() {
-        let doc = parse("div .flex items-center gap-4");
+    fn test_conditional_class() {
+        let doc = parse("li .item .active:isActive .done:\"todo.done && !editing\"");
         let el = first_element(&doc);
-        assert_eq!(el.classes, vec!["flex", "items-center", "gap-4"]);
+        assert_eq!(el.classes, vec!["item"]);
+        assert!(el.attributes.is_empty());
+        let toggled: Vec<&str> = el.class_toggles.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(toggled, vec!["active", "done"]);
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (element li (class item) (toggle active isActive) (toggle done (&& (. todo done) (! editing)))))"
+        );
     }
 
     #[test]
-    fn test_implicit_div() {
-        let doc = parse(".container");
+    fn test_conditional_class_beside_class_directive() {
+        let doc = parse("div .active:on :class=\"x\"");
         let el = first_element(&doc);
-        assert_eq!(el.tag, "div");
-        assert_eq!(el.classes, vec!["container"]);
+        assert_eq!(el.class_toggles.len(), 1);
+        assert_eq!(el.attributes.len(), 1);
+        assert_eq!(el.attributes[0].name, "class");
+        assert!(el.attributes[0].modifiers.is_empty());
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (element div (toggle active on) (attr :class x)))"
+        );
     }
 
     #[test]
-    fn test_classes_with_hyphen() {
-        let doc = parse("div .text-2xl bg-blue-500");
-        let el = first_element(&doc);
-        assert_eq!(el.classes, vec!["text-2xl", "bg-blue-500"]);
+    fn test_class_directive_modifier_is_error() {
+        let err = Parser::parse("div :class.active=\"on\"").unwrap_err();
+        assert!(
+            err.message.contains("`.active:condition`"),
+            "{}",
+            err.message
+        );
     }
 
-    // =========================================================================
-    // Attributes
-    // =========================================================================
+    #[test]
+    fn test_class_before_directive_is_not_conditional() {
+        let doc = parse("li .active :show=\"visible\"");
+        let el = first_element(&doc);
+        assert_eq!(el.classes, vec!["active"]);
+        assert_eq!(el.attributes[0].name, "show");
+        assert!(el.attributes[0].modifiers.is_empty());
+    }
 
     #[test]
-    fn test_attribute_with_string() {
-        let doc = parse("a href=\"/about\"");
+    fn test_boolean_attribute_before_class() {
+        let doc = parse("div hidden .box \"x\"");
         let el = first_element(&doc);
+        assert_eq!(doc.nodes.len(), 1);
+        assert_eq!(el.classes, vec!["box"]);
         assert_eq!(el.attributes.len(), 1);
-        assert_eq!(el.attributes[0].name, "href");
+        assert_eq!(el.attributes[0].name, "hidden");
+        assert!(el.attributes[0].value.is_none());
         assert!(el.attributes[0].prefix.is_none());
+        assert!(matches!(&el.children[0], Node::Text(t) if t == "x"));
     }
 
     #[test]
-    fn test_multiple_attributes() {
-        let doc = parse("img src=\"logo.png\" alt=\"Logo\"");
+    fn test_bare_identifier_after_class_is_a_class() {
+        let doc = parse("div .box hidden");
         let el = first_element(&doc);
-        assert_eq!(el.attributes.len(), 2);
-        assert_eq!(el.attributes[0].name, "src");
-        assert_eq!(el.attributes[1].name, "alt");
+        assert_eq!(el.classes, vec!["box", "hidden"]);
+        assert!(el.attributes.is_empty());
     }
 
     #[test]
-    fn test_class_then_attribute() {
-        let doc = parse("input .form-input type=\"text\" name=\"email\"");
+    fn test_event_debounce_with_milliseconds() {
+        let doc = parse("input @input.debounce.300.prevent=\"search()\"");
         let el = first_element(&doc);
-        assert_eq!(el.classes, vec!["form-input"]);
-        assert_eq!(el.attributes.len(), 2);
+        assert_eq!(
+            el.attributes[0].modifiers,
+            vec![
+                Modifier {
+                    name: "debounce".into(),
+                    arg: Some("300".into()),
+                },
+                Modifier::new("prevent"),
+            ]
+        );
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (element input (attr @input.debounce.300.prevent (call search))))"
+        );
     }
 
-    // =========================================================================
-    // State directives
-    // =========================================================================
+    #[test]
+    fn test_modifier_argument_without_a_name_is_error() {
+        let err = Parser::parse("input @input.300=\"search()\"").unwrap_err();
+        assert!(err.message.contains("follows its name"), "{}", err.message);
+    }
 
     #[test]
-    fn test_show_directive() {
-        let doc = parse("div :show=\"visible\"");
-        let el = first_element(&doc);
-        assert_eq!(el.attributes.len(), 1);
-        assert_eq!(el.attributes[0].name, "show");
-        assert_eq!(el.attributes[0].prefix, Some(AttributePrefix::State));
+    fn test_modifier_argument_is_validated() {
+        let err = Parser::parse("input @input.debounce.1.5=\"search()\"").unwrap_err();
+        assert_eq!(
+            err.message,
+            "`.debounce` takes a whole number of milliseconds, not 1.5"
+        );
+        let err = Parser::parse("form @submit.prevent.300=\"save()\"").unwrap_err();
+        assert!(err.message.contains("`.prevent` takes no argument"));
+        let err = Parser::parse("nav :show.hidden.2=\"open\"").unwrap_err();
+        assert!(err.message.contains("`.hidden` takes no argument"));
     }
 
     #[test]
-    fn test_model_directive() {
-        let doc = parse("input :model=\"name\"");
+    fn test_directive_modifier() {
+        let doc = parse("nav :show.hidden=\"open\"");
         let el = first_element(&doc);
-        assert_eq!(el.attributes[0].name, "model");
-        assert_eq!(el.attributes[0].prefix, Some(AttributePrefix::State));
+        assert_eq!(el.attributes[0].name, "show");
+        assert_eq!(el.attributes[0].modifiers, vec![Modifier::new("hidden")]);
     }
 
     #[test]
-    fn test_if_directive() {
-        let doc = parse("div :if=\"count > 0\"");
+    fn test_class_after_valueless_directive_is_not_a_modifier() {
+        let doc = parse("input :checked .toggle");
         let el = first_element(&doc);
-        assert_eq!(el.attributes[0].name, "if");
+        assert_eq!(el.classes, vec!["toggle"]);
+        assert!(el.attributes[0].modifiers.is_empty());
     }
 
-    // =========================================================================
-    // Event handlers
-    // =========================================================================
-
     #[test]
     fn test_click_event() {
         let doc = parse("button @click=\"count++\"");
@@ -769,7 +1780,15 @@ mod tests {
         let doc = parse("form @submit.prevent=\"save()\"");
         let el = first_element(&doc);
         assert_eq!(el.attributes[0].name, "submit");
-        assert_eq!(el.attributes[0].modifiers, vec!["prevent"]);
+        assert_eq!(el.attributes[0].modifiers, vec![Modifier::new("prevent")]);
+    }
+
+    #[test]
+    fn test_event_multiple_statements() {
+        let doc = parse("button @click=\"a = 1; b = 2\"");
+        let el = first_element(&doc);
+        let value = el.attributes[0].value.as_ref().unwrap();
+        assert!(matches!(&value.kind, ExprKind::Sequence(stmts) if stmts.len() == 2));
     }
 
     #[test]
@@ -777,7 +1796,10 @@ mod tests {
         let doc = parse("input @keydown.ctrl.enter=\"submit()\"");
         let el = first_element(&doc);
         assert_eq!(el.attributes[0].name, "keydown");
-        assert_eq!(el.attributes[0].modifiers, vec!["ctrl", "enter"]);
+        assert_eq!(
+            el.attributes[0].modifiers,
+            vec![Modifier::new("ctrl"), Modifier::new("enter")]
+        );
     }
 
     // =========================================================================
@@ -799,6 +1821,48 @@ mod tests {
         assert!(matches!(&el.children[0], Node::Text(t) if t == "Count: {count}"));
     }
 
+    #[test]
+    fn test_bare_interpolation_child() {
+        let doc = parse("div\n  {count}");
+        let el = first_element(&doc);
+        assert_eq!(el.children, vec![Node::Text("{count}".into())]);
+    }
+
+    #[test]
+    fn test_bare_interpolation_inline() {
+        let doc = parse("span { count + 1 }");
+        let el = first_element(&doc);
+        assert_eq!(el.children, vec![Node::Text("{count + 1}".into())]);
+    }
+
+    #[test]
+    fn test_double_brace_interpolation_is_object_literal() {
+        let doc = parse("span {{ a: 1 }}");
+        let el = first_element(&doc);
+        assert_eq!(el.children, vec![Node::Text("{{ a: 1 }}".into())]);
+
+        // The scanner strips one level of braces; the rest is an object
+        let expr = ExprParser::parse_interpolation("{ a: 1 }").unwrap();
+        assert!(matches!(expr.kind, ExprKind::Object(_)));
+        assert_eq!(expr.to_sexpr(), "(object (a 1))");
+    }
+
+    #[test]
+    fn test_attribute_spread() {
+        let doc = parse("state\n  attrs: {}\n\na {...attrs} .link \"x\"");
+        let Node::Element(el) = &doc.nodes[1] else {
+            panic!("Expected Element, got {:?}", doc.nodes[1]);
+        };
+        assert_eq!(el.spreads.len(), 1);
+        assert_eq!(el.spreads[0].kind, ExprKind::Identifier("attrs".into()));
+        assert_eq!(el.classes, vec!["link"]);
+        assert_eq!(el.children, vec![Node::Text("x".into())]);
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (state (attrs (object)))\n  (element a (class link) (... attrs)\n    (text \"x\")))"
+        );
+    }
+
     // =========================================================================
     // Nesting (indentation)
     // =========================================================================
@@ -810,6 +1874,25 @@ mod tests {
         assert_eq!(el.children.len(), 2);
     }
 
+    #[test]
+    fn test_text_child_after_element() {
+        let doc = parse("div\n  span \"a\"\n  \"tail text\"");
+        let el = first_element(&doc);
+        assert_eq!(el.children.len(), 2);
+        assert!(matches!(&el.children[0], Node::Element(span) if span.tag == "span"));
+        assert_eq!(el.children[1], Node::Text("tail text".into()));
+    }
+
+    #[test]
+    fn test_escaped_prefix_text_child() {
+        let doc = parse("p\n  \\@mention\n  \\:colon");
+        let el = first_element(&doc);
+        assert_eq!(
+            el.children,
+            vec![Node::Text("@mention".into()), Node::Text(":colon".into())]
+        );
+    }
+
     #[test]
     fn test_deep_nesting() {
         let doc = parse("div\n  ul\n    li \"Item\"");
@@ -830,6 +1913,17 @@ mod tests {
         assert_eq!(doc.nodes.len(), 2); // div and button
     }
 
+    #[test]
+    fn test_sibling_with_children_after_two_level_dedent() {
+        let doc = parse("a\n  b\n    c\nd\n  e");
+        assert_eq!(doc.nodes.len(), 2);
+        let Node::Element(d) = &doc.nodes[1] else {
+            panic!("Expected Element, got {:?}", doc.nodes[1]);
+        };
+        assert_eq!(d.tag, "d");
+        assert!(matches!(&d.children[..], [Node::Element(e)] if e.tag == "e"));
+    }
+
     // =========================================================================
     // State blocks
     // =========================================================================
@@ -858,6 +1952,50 @@ mod tests {
         assert!(matches!(sb.fields[0].value.kind, ExprKind::Null));
     }
 
+    #[test]
+    fn test_comments_between_state_fields_are_skipped() {
+        let doc =
+            parse("state\n  // counters\n  a: 1 // first\n\n  // second\n  b: 2\n// page\ndiv");
+        let sb = first_state(&doc);
+        assert_eq!(sb.fields.len(), 2);
+        assert_eq!(sb.fields[0].name, "a");
+        assert_eq!(sb.fields[1].name, "b");
+        assert!(matches!(&doc.nodes[1], Node::Element(el) if el.tag == "div"));
+    }
+
+    #[test]
+    fn test_state_array_and_object_values() {
+        let doc = parse("state\n  items: []\n  tags: ['a', 'b']\n  user: { name: \"Ada\" }");
+        let sb = first_state(&doc);
+        assert_eq!(sb.fields.len(), 3);
+        assert_eq!(sb.fields[0].value.to_sexpr(), "(array)");
+        assert_eq!(sb.fields[1].value.to_sexpr(), "(array \"a\" \"b\")");
+        assert_eq!(sb.fields[2].value.to_sexpr(), "(object (name \"Ada\"))");
+    }
+
+    #[test]
+    fn test_state_array_error_position() {
+        let err = Parser::parse("state\n  tags: [1, *]").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.column, 13);
+    }
+
+    #[test]
+    fn test_const_block() {
+        let doc = parse("const\n  MAX: 100\n  TAGS: ['a', 'b']\n\nstate\n  count: 0");
+        match &doc.nodes[0] {
+            Node::ConstBlock(cb) => {
+                assert_eq!(cb.fields.len(), 2);
+                assert_eq!(cb.fields[0].name, "MAX");
+                assert_eq!(cb.fields[1].value.to_sexpr(), "(array \"a\" \"b\")");
+            }
+            other => panic!("Expected ConstBlock, got {other:?}"),
+        }
+        assert!(matches!(doc.nodes[1], Node::StateBlock(_)));
+        let sexpr = doc.to_sexpr();
+        assert!(sexpr.contains("(const (MAX 100) (TAGS (array \"a\" \"b\")))"));
+    }
+
     // =========================================================================
     // Computed blocks
     // =========================================================================
@@ -874,16 +2012,247 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_computed_uses_full_expression_grammar() {
+        let doc = parse("computed\n  double: count * 2\n  empty: !items.length || total <= 0");
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (computed (double (* count 2)) (empty (|| (! (. items length)) (<= total 0)))))"
+        );
+    }
+
+    #[test]
+    fn test_unquoted_computed_comparison_and_logic() {
+        let doc =
+            parse("state\n  count: 0\n  ready: true\n\ncomputed\n  active: count > 0 && ready");
+        let Node::ComputedBlock(cb) = &doc.nodes[1] else {
+            panic!("Expected ComputedBlock");
+        };
+        assert_eq!(cb.fields[0].body.to_sexpr(), "(&& (> count 0) ready)");
+    }
+
+    #[test]
+    fn test_quoted_computed_is_an_expression() {
+        let doc = parse("computed\n  positive: \"count > 0 && !hidden\"\n  sep: ' - '");
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (computed (positive (&& (> count 0) (! hidden))) (sep \" - \")))"
+        );
+        let err = Parser::parse("computed\n  bad: \"count >\"").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 16));
+    }
+
+    #[test]
+    fn test_effect_block() {
+        let doc = parse("effect\n  document.title = count + \" items\"\n  a = 1; b = a * 2");
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (effect (= (. document title) (+ count \" items\")) (= a 1) (= b (* a 2))))"
+        );
+    }
+
+    #[test]
+    fn test_block_keywords_as_field_and_attribute_names() {
+        let doc = parse("state\n  effect: \"fade\"\n  const: 1\n  raw: 2\n\ndiv effect const raw");
+        let Node::StateBlock(state) = &doc.nodes[0] else {
+            panic!("Expected state block");
+        };
+        let names: Vec<&str> = state.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["effect", "const", "raw"]);
+        let Node::Element(div) = &doc.nodes[1] else {
+            panic!("Expected element");
+        };
+        let attrs: Vec<&str> = div.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(attrs, vec!["effect", "const", "raw"]);
+    }
+
+    #[test]
+    fn test_empty_effect_block_is_error() {
+        let err = Parser::parse("effect\np \"x\"").unwrap_err();
+        assert!(err.message.contains("at least one statement"));
+    }
+
+    #[test]
+    fn test_computed_error_points_into_the_value() {
+        let err = Parser::parse("computed\n  double: count * * 2").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 19));
+    }
+
     // =========================================================================
     // Comments
     // =========================================================================
 
+    #[test]
+    fn test_page_block() {
+        let doc = parse("page\n  lang: en\n  viewport: \"width=device-width\"\n  theme-color: \"#222\"\n\ndiv");
+        let Node::PageBlock(page) = &doc.nodes[0] else {
+            panic!("Expected page block");
+        };
+        let fields: Vec<(&str, &str)> = page
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.value.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![("lang", "en"), ("viewport", "width=device-width"), ("theme-color", "#222")]
+        );
+        assert!(matches!(doc.nodes[1], Node::Element(_)));
+    }
+
+    #[test]
+    fn test_config_block() {
+        let doc = parse("config\n  minify: true\n  runtime: external\n\ndiv");
+        let Node::ConfigBlock(config) = &doc.nodes[0] else {
+            panic!("Expected config block");
+        };
+        let fields: Vec<(&str, &str)> = config
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.value.as_str()))
+            .collect();
+        assert_eq!(fields, vec![("minify", "true"), ("runtime", "external")]);
+        assert!(matches!(doc.nodes[1], Node::Element(_)));
+    }
+
+    #[test]
+    fn test_config_field_must_be_static() {
+        let err = Parser::parse("config\n  minify: (true)").unwrap_err();
+        assert!(err.message.contains("Config field 'minify' must be a static value"));
+    }
+
+    #[test]
+    fn test_page_field_must_be_static() {
+        let err = Parser::parse("page\n  title: (a)").unwrap_err();
+        assert!(err.message.contains("must be a static value"));
+    }
+
+    #[test]
+    fn test_page_field_name_with_colon_is_quoted() {
+        let doc = parse("page\n  \"og:title\": \"Docs\"\n\ndiv");
+        let Node::PageBlock(page) = &doc.nodes[0] else {
+            panic!("Expected page block");
+        };
+        assert_eq!(page.fields[0].name, "og:title");
+        assert_eq!(page.fields[0].value, "Docs");
+
+        let err = Parser::parse("page\n  og:title: \"Docs\"").unwrap_err();
+        assert!(err.message.contains("as in \"og:title\""));
+    }
+
+    #[test]
+    fn test_whitespace_only_document() {
+        assert!(parse("   \n\t\n  \n").nodes.is_empty());
+    }
+
+    #[test]
+    fn test_comment_only_document() {
+        let doc = parse("  // one\n\n    // two\n");
+        assert_eq!(
+            doc.nodes,
+            vec![Node::Comment("one".into()), Node::Comment("two".into())]
+        );
+    }
+
     #[test]
     fn test_comment() {
         let doc = parse("// This is a comment");
         assert!(matches!(&doc.nodes[0], Node::Comment(t) if t == "This is a comment"));
     }
 
+    // =========================================================================
+    // Raw blocks
+    // =========================================================================
+
+    #[test]
+    fn test_raw_block() {
+        let doc = parse("raw\n  <svg width=\"10\">\n    <circle r=\"5\"/>\n  </svg>");
+        assert_eq!(
+            doc.nodes[0],
+            Node::Raw("<svg width=\"10\">\n  <circle r=\"5\"/>\n</svg>".into())
+        );
+    }
+
+    #[test]
+    fn test_raw_inline() {
+        let doc = parse("raw \"<hr>\"");
+        assert_eq!(doc.nodes[0], Node::Raw("<hr>".into()));
+    }
+
+    #[test]
+    fn test_raw_as_child() {
+        let doc = parse("div .icon\n  raw\n    <svg></svg>\n  span \"Label\"");
+        let el = first_element(&doc);
+        assert_eq!(el.children.len(), 2);
+        assert_eq!(el.children[0], Node::Raw("<svg></svg>".into()));
+    }
+
+    #[test]
+    fn test_raw_without_content_is_error() {
+        assert!(Parser::parse("raw").is_err());
+    }
+
+    // =========================================================================
+    // Fragments
+    // =========================================================================
+
+    #[test]
+    fn test_fragment_groups_children() {
+        let doc = parse("fragment\n  h1 \"A\"\n  p \"B\"");
+        let Node::Fragment(children) = &doc.nodes[0] else {
+            panic!("Expected Fragment, got {:?}", doc.nodes[0]);
+        };
+        assert_eq!(children.len(), 2);
+        assert!(matches!(&children[0], Node::Element(el) if el.tag == "h1"));
+        assert!(matches!(&children[1], Node::Element(el) if el.tag == "p"));
+    }
+
+    #[test]
+    fn test_fragment_condition_copied_to_children() {
+        let doc = parse("fragment :if=\"show\"\n  h1 \"A\"\n  p \"B\"");
+        let Node::Fragment(children) = &doc.nodes[0] else {
+            panic!("Expected Fragment, got {:?}", doc.nodes[0]);
+        };
+        for child in children {
+            let Node::Element(el) = child else {
+                panic!("Expected Element, got {child:?}");
+            };
+            assert_eq!(el.attributes[0].name, "if");
+            assert_eq!(el.attributes[0].prefix, Some(AttributePrefix::State));
+        }
+    }
+
+    #[test]
+    fn test_fragment_rejects_plain_attributes() {
+        let err = Parser::parse("fragment .box\n  p \"B\"").unwrap_err();
+        assert!(err.message.contains("only accepts ':if' and ':show'"));
+    }
+
+    #[test]
+    fn test_conditional_fragment_rejects_bare_text() {
+        let err = Parser::parse("fragment :if=\"show\"\n  \"text\"").unwrap_err();
+        assert!(err.message.contains("must be wrapped in an element"));
+    }
+
+    #[test]
+    fn test_parse_fragment_snippet() {
+        let nodes = Parser::parse_fragment("div\n  span \"x\"").unwrap();
+        assert_eq!(nodes.len(), 1);
+        let Node::Element(div) = &nodes[0] else {
+            panic!("Expected Element, got {:?}", nodes[0]);
+        };
+        assert_eq!(div.tag, "div");
+        assert!(matches!(&div.children[0], Node::Element(el) if el.tag == "span"));
+
+        let err = Parser::parse_fragment("state\n  count: 0\np \"x\"").unwrap_err();
+        assert!(err.message.contains("`state` block"), "{}", err.message);
+        let err = Parser::parse_fragment("computed\n  double: n * 2").unwrap_err();
+        assert_eq!(
+            err.message,
+            "A `computed` block belongs to a full document, not a fragment"
+        );
+    }
+
     // =========================================================================
     // Full examples (the 3 prototype examples)
     // =========================================================================
@@ -956,6 +2325,34 @@ mod tests {
         assert_eq!(header.children.len(), 1);
     }
 
+    /// `depth` elements, each nested one level inside the previous.
+    fn nested_source(depth: usize) -> String {
+        (0..depth)
+            .map(|level| format!("{}div\n", "  ".repeat(level)))
+            .collect()
+    }
+
+    #[test]
+    fn test_nesting_within_max_depth() {
+        let tokens = hrml_lexer::Scanner::tokenize(&nested_source(8)).unwrap();
+        let doc = Parser::new(tokens).with_max_depth(8).parse_tokens().unwrap();
+        assert_eq!(doc.nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_nesting_beyond_max_depth_is_error() {
+        let tokens = hrml_lexer::Scanner::tokenize(&nested_source(9)).unwrap();
+        let err = Parser::new(tokens).with_max_depth(8).parse_tokens().unwrap_err();
+        assert!(err.message.contains("more than 8 levels"));
+        assert_eq!(err.line, 9);
+    }
+
+    #[test]
+    fn test_pathological_nesting_errors_cleanly() {
+        let err = Parser::parse(&nested_source(1_000)).unwrap_err();
+        assert!(err.message.contains("more than 256 levels"));
+    }
+
     #[test]
     fn test_state_multiple_types() {
         let doc = parse("state\n  count: 42\n  name: \"Alice\"\n  active: true\n  data: null");
@@ -1053,8 +2450,10 @@ mod tests {
         assert_eq!(el.attributes.len(), 1);
         assert_eq!(el.attributes[0].name, "submit");
         assert_eq!(el.attributes[0].modifiers.len(), 2);
-        assert!(el.attributes[0].modifiers.contains(&"prevent".to_string()));
-        assert!(el.attributes[0].modifiers.contains(&"stop".to_string()));
+        assert!(el.attributes[0]
+            .modifiers
+            .contains(&Modifier::new("prevent")));
+        assert!(el.attributes[0].modifiers.contains(&Modifier::new("stop")));
     }
 
     #[test]
@@ -1063,7 +2462,7 @@ mod tests {
         let el = first_element(&doc);
 
         // Should have type and checked attributes
-        assert!(el.attributes.len() >= 1);
+        assert!(!el.attributes.is_empty());
         let has_type = el.attributes.iter().any(|a| a.name == "type");
         assert!(has_type, "Should have type attribute");
     }
@@ -1113,4 +2512,41 @@ mod tests {
             _ => panic!("Expected element"),
         }
     }
+
+    // =========================================================================
+    // Robustness
+    // =========================================================================
+
+    /// Fragments of HRML syntax, so random inputs reach deeper than the lexer.
+    const FUZZ_PIECES: &[&str] = &[
+        "div", "state", "computed", "page", "config", "fragment", "raw", "fn", "async",
+        "component", "\n", "\n  ", "\n    ", "\t", " ", ".", ":", "@", "$", "=", "\"", "'",
+        "{", "}", "(", ")", "[", "]", "=>", "++", "-", "!", "?", "//", "\\", "0", "1.5e",
+        "x", ":for=\"i in ", "@click=\"", ":show=\"", "\"{a}\"", "é", "\u{1F600}", "\r\n",
+    ];
+
+    /// xorshift64*: deterministic, so a failure reproduces.
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state >> 12;
+        *state ^= *state << 25;
+        *state ^= *state >> 27;
+        state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    #[test]
+    fn test_random_input_never_panics() {
+        let mut seed = 0x9E37_79B9_7F4A_7C15_u64;
+        for _ in 0..3000 {
+            let len = (next_random(&mut seed) % 64) as usize;
+
+            let bytes: Vec<u8> = (0..len).map(|_| next_random(&mut seed) as u8).collect();
+            let source = String::from_utf8_lossy(&bytes);
+            let _ = Parser::parse(&source);
+
+            let source: String = (0..len)
+                .map(|_| FUZZ_PIECES[next_random(&mut seed) as usize % FUZZ_PIECES.len()])
+                .collect();
+            let _ = Parser::parse(&source);
+        }
+    }
 }
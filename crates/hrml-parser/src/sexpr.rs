@@ -0,0 +1,335 @@
+//! S-expression rendering of the AST.
+//!
+//! A compact, stable text form for snapshot tests and tooling. Spans are
+//! left out, so the output only changes when the tree itself does.
+//!
+//! ```text
+//! (document
+//!   (state (count 0))
+//!   (element button (attr @click (post++ count))
+//!     (text "+")))
+//! ```
+
+use crate::ast::{
+    AssignOp, Attribute, AttributePrefix, BinaryOp, Document, ExprKind, Expression, Node,
+    PostfixOp, UnaryOp,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+
+impl Document {
+    /// Render the document as an indented s-expression, one node per line.
+    pub fn to_sexpr(&self) -> String {
+        let mut out = String::from("(document");
+        for node in &self.nodes {
+            write_node(node, 1, &mut out);
+        }
+        out.push(')');
+        out
+    }
+}
+
+impl Expression {
+    /// Render the expression as a single-line s-expression.
+    pub fn to_sexpr(&self) -> String {
+        let mut out = String::new();
+        write_expr(self, &mut out);
+        out
+    }
+}
+
+fn write_node(node: &Node, depth: usize, out: &mut String) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(depth));
+    match node {
+        Node::Element(el) => {
+            out.push_str(&format!("(element {}", el.tag));
+            if !el.classes.is_empty() {
+                out.push_str(&format!(" (class {})", el.classes.join(" ")));
+            }
+            if let Some(for_loop) = &el.for_loop {
+                let names = match &for_loop.index {
+                    Some(index) => format!("({} {index})", for_loop.item),
+                    None => for_loop.item.clone(),
+                };
+                out.push_str(&format!(" (for {names} {})", for_loop.iterable.to_sexpr()));
+            }
+            for (class, condition) in &el.class_toggles {
+                out.push_str(&format!(" (toggle {class} {})", condition.to_sexpr()));
+            }
+            for attr in &el.attributes {
+                write_attr(attr, out);
+            }
+            for spread in &el.spreads {
+                out.push(' ');
+                write_list("...", &[spread], out);
+            }
+            write_children(&el.children, depth, out);
+        }
+        Node::StateBlock(block) => {
+            out.push_str("(state");
+            for field in &block.fields {
+                out.push_str(&format!(" ({} {})", field.name, field.value.to_sexpr()));
+            }
+            out.push(')');
+        }
+        Node::ConstBlock(block) => {
+            out.push_str("(const");
+            for field in &block.fields {
+                out.push_str(&format!(" ({} {})", field.name, field.value.to_sexpr()));
+            }
+            out.push(')');
+        }
+        Node::ComputedBlock(block) => {
+            out.push_str("(computed");
+            for field in &block.fields {
+                out.push_str(&format!(" ({} {})", field.name, field.body.to_sexpr()));
+            }
+            out.push(')');
+        }
+        Node::EffectBlock(block) => {
+            out.push_str("(effect");
+            for statement in &block.body {
+                out.push(' ');
+                write_expr(statement, out);
+            }
+            out.push(')');
+        }
+        Node::PageBlock(block) => {
+            out.push_str("(page");
+            for field in &block.fields {
+                out.push_str(&format!(" ({} {:?})", field.name, field.value));
+            }
+            out.push(')');
+        }
+        Node::ConfigBlock(block) => {
+            out.push_str("(config");
+            for field in &block.fields {
+                out.push_str(&format!(" ({} {:?})", field.name, field.value));
+            }
+            out.push(')');
+        }
+        Node::FnDecl(decl) => {
+            let keyword = if decl.is_async { "async-fn" } else { "fn" };
+            out.push_str(&format!(
+                "({keyword} {} ({})",
+                decl.name,
+                decl.params.join(" ")
+            ));
+            for statement in &decl.body {
+                out.push(' ');
+                write_expr(statement, out);
+            }
+            out.push(')');
+        }
+        Node::Component(component) => {
+            out.push_str(&format!("(component {}", component.name));
+            for prop in &component.props {
+                match &prop.default {
+                    Some(default) => {
+                        out.push_str(&format!(" (prop {} {})", prop.name, default.to_sexpr()))
+                    }
+                    None => out.push_str(&format!(" (prop {})", prop.name)),
+                }
+            }
+            write_children(&component.children, depth, out);
+        }
+        Node::Fragment(children) => {
+            out.push_str("(fragment");
+            write_children(children, depth, out);
+        }
+        Node::Text(text) => out.push_str(&format!("(text {text:?})")),
+        Node::Raw(markup) => out.push_str(&format!("(raw {markup:?})")),
+        Node::Comment(text) => out.push_str(&format!("(comment {text:?})")),
+    }
+}
+
+/// Children on their own lines, then the closing paren of the parent.
+fn write_children(children: &[Node], depth: usize, out: &mut String) {
+    for child in children {
+        write_node(child, depth + 1, out);
+    }
+    out.push(')');
+}
+
+fn write_attr(attr: &Attribute, out: &mut String) {
+    let prefix = match attr.prefix {
+        Some(AttributePrefix::State) => ":",
+        Some(AttributePrefix::Event) => "@",
+        Some(AttributePrefix::Server) => "$",
+        None => "",
+    };
+    out.push_str(&format!(" (attr {prefix}{}", attr.name));
+    for modifier in &attr.modifiers {
+        out.push('.');
+        out.push_str(&modifier.name);
+        if let Some(arg) = &modifier.arg {
+            out.push('.');
+            out.push_str(arg);
+        }
+    }
+    if let Some(value) = &attr.value {
+        out.push(' ');
+        write_expr(value, out);
+    }
+    out.push(')');
+}
+
+fn write_expr(expr: &Expression, out: &mut String) {
+    match &expr.kind {
+        ExprKind::Number(n) => out.push_str(&n.to_string()),
+        ExprKind::String(s) => out.push_str(&format!("{s:?}")),
+        ExprKind::Boolean(b) => out.push_str(&b.to_string()),
+        ExprKind::Null => out.push_str("null"),
+        ExprKind::Undefined => out.push_str("undefined"),
+        ExprKind::Identifier(name) => out.push_str(name),
+        ExprKind::Binary { left, op, right } => write_list(binary_op(*op), &[left, right], out),
+        ExprKind::Unary { op, operand } => {
+            let op = match op {
+                UnaryOp::Not => "!",
+                UnaryOp::Neg => "neg",
+                UnaryOp::Typeof => "typeof",
+                UnaryOp::Await => "await",
+            };
+            write_list(op, &[operand], out);
+        }
+        ExprKind::Postfix { operand, op } => {
+            let op = match op {
+                PostfixOp::Increment => "post++",
+                PostfixOp::Decrement => "post--",
+            };
+            write_list(op, &[operand], out);
+        }
+        ExprKind::Member {
+            object,
+            property,
+            computed,
+        } => write_list(if *computed { "[]" } else { "." }, &[object, property], out),
+        ExprKind::Call { callee, arguments } => {
+            out.push_str("(call ");
+            write_expr(callee, out);
+            for argument in arguments {
+                out.push(' ');
+                write_expr(argument, out);
+            }
+            out.push(')');
+        }
+        ExprKind::Ternary {
+            condition,
+            consequent,
+            alternate,
+        } => write_list("?", &[condition, consequent, alternate], out),
+        ExprKind::Object(properties) => {
+            out.push_str("(object");
+            for property in properties {
+                if property.shorthand {
+                    out.push_str(&format!(" ({})", property.key));
+                } else {
+                    out.push_str(&format!(" ({} ", property.key));
+                    write_expr(&property.value, out);
+                    out.push(')');
+                }
+            }
+            out.push(')');
+        }
+        ExprKind::Array(items) => {
+            out.push_str("(array");
+            for item in items {
+                out.push(' ');
+                write_expr(item, out);
+            }
+            out.push(')');
+        }
+        ExprKind::Arrow {
+            params,
+            body,
+            is_async,
+        } => {
+            let head = if *is_async { "async=>" } else { "=>" };
+            out.push_str(&format!("({head} ({}) ", params.join(" ")));
+            write_expr(body, out);
+            out.push(')');
+        }
+        ExprKind::Assignment { target, op, value } => {
+            let op = match op {
+                AssignOp::Assign => "=",
+                AssignOp::AddAssign => "+=",
+                AssignOp::SubAssign => "-=",
+                AssignOp::MulAssign => "*=",
+                AssignOp::DivAssign => "/=",
+            };
+            write_list(op, &[target, value], out);
+        }
+        ExprKind::Interpolation(inner) => write_list("interp", &[inner], out),
+        ExprKind::Filter { input, name } => {
+            out.push_str("(| ");
+            write_expr(input, out);
+            out.push_str(&format!(" {name})"));
+        }
+        ExprKind::Sequence(statements) => {
+            out.push_str("(seq");
+            for statement in statements {
+                out.push(' ');
+                write_expr(statement, out);
+            }
+            out.push(')');
+        }
+    }
+}
+
+/// `(head a b ...)`
+fn write_list(head: &str, items: &[&Expression], out: &mut String) {
+    out.push('(');
+    out.push_str(head);
+    for item in items {
+        out.push(' ');
+        write_expr(item, out);
+    }
+    out.push(')');
+}
+
+fn binary_op(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Eq => "==",
+        BinaryOp::Neq => "!=",
+        BinaryOp::StrictEq => "===",
+        BinaryOp::StrictNeq => "!==",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Lte => "<=",
+        BinaryOp::Gte => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+        BinaryOp::NullishCoalescing => "??",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parser;
+
+    #[test]
+    fn test_counter_sexpr() {
+        let doc = Parser::parse(
+            "state\n  count: 0\n\ndiv .counter\n  button @click=\"count++\" \"+\"\n  span :show=\"count > 0\" \"{count}\"",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.to_sexpr(),
+            "(document\n  (state (count 0))\n  (element div (class counter)\n    (element button (attr @click (post++ count))\n      (text \"+\"))\n    (element span (attr :show (> count 0))\n      (text \"{count}\"))))"
+        );
+    }
+
+    #[test]
+    fn test_sexpr_ignores_spans() {
+        let a = Parser::parse("p :show=\"a && b\"").unwrap();
+        let b = Parser::parse("p :show=\"a   &&   b\"").unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a.to_sexpr(), b.to_sexpr());
+    }
+}
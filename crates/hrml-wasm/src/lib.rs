@@ -1,13 +1,13 @@
 //! WASM bindings for the HRML compiler.
 //!
 //! Exposes `compile()` to JavaScript via wasm-bindgen.
-//! Returns a JS object `{ html, css, js }` or throws on error.
+//! Returns a JS object `{ html, css, js, warnings }` or throws on error.
 
 use wasm_bindgen::prelude::*;
 
 /// Compile HRML source to HTML + CSS + JS.
 ///
-/// Returns a JS object with `{ html: string, css: string, js: string }`.
+/// Returns a JS object with `{ html: string, css: string, js: string, warnings: string[] }`.
 /// Throws a JS error if parsing or code generation fails.
 #[wasm_bindgen]
 pub fn compile(source: &str) -> Result<JsValue, JsError> {
@@ -16,7 +16,7 @@ pub fn compile(source: &str) -> Result<JsValue, JsError> {
     let output =
         hrml_codegen::compile(&doc).map_err(|e| JsError::new(&e.to_string()))?;
 
-    // Serialize to a plain JS object { html, css, js }
+    // Serialize to a plain JS object { html, css, js, warnings }
     let js_obj = js_sys::Object::new();
     js_sys::Reflect::set(&js_obj, &"html".into(), &output.html.into())
         .map_err(|_| JsError::new("Failed to set html property"))?;
@@ -24,6 +24,9 @@ pub fn compile(source: &str) -> Result<JsValue, JsError> {
         .map_err(|_| JsError::new("Failed to set css property"))?;
     js_sys::Reflect::set(&js_obj, &"js".into(), &output.js.into())
         .map_err(|_| JsError::new("Failed to set js property"))?;
+    let warnings: js_sys::Array = output.warnings.iter().map(JsValue::from).collect();
+    js_sys::Reflect::set(&js_obj, &"warnings".into(), &warnings.into())
+        .map_err(|_| JsError::new("Failed to set warnings property"))?;
 
     Ok(js_obj.into())
 }
@@ -0,0 +1,81 @@
+//! HRML
+//!
+//! The lexer, parser, and code generator behind one dependency, with an
+//! [`Error`] that any stage's error converts into, so a whole compile can
+//! `?` through `tokenize` → `parse` → `compile`.
+//!
+//! # Example
+//!
+//! ```
+//! fn build(source: &str) -> Result<String, hrml::Error> {
+//!     let tokens = hrml::lexer::Scanner::tokenize(source)?;
+//!     let doc = hrml::parser::Parser::new(tokens).parse_tokens()?;
+//!     Ok(hrml::codegen::compile(&doc)?.html)
+//! }
+//!
+//! assert_eq!(build("p \"Hi\"").unwrap(), "<p>Hi</p>\n");
+//! ```
+
+pub use hrml_codegen as codegen;
+pub use hrml_lexer as lexer;
+pub use hrml_parser as parser;
+
+use hrml_codegen::CodegenError;
+use hrml_lexer::LexerError;
+use hrml_parser::ParseError;
+
+/// An error from any stage of compilation.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Lex(#[from] LexerError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error(transparent)]
+    Codegen(#[from] CodegenError),
+}
+
+impl Error {
+    /// The 1-based line and column of the error in the source, if it has one.
+    /// Code generation errors refer to the document as a whole.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Lex(e) => Some((e.line, e.column)),
+            Error::Parse(e) => Some((e.line, e.column)),
+            Error::Codegen(_) => None,
+        }
+    }
+}
+
+/// Compile HRML source into HTML + CSS + JS.
+pub fn compile(source: &str) -> Result<codegen::CompilerOutput, Error> {
+    let tokens = lexer::Scanner::tokenize(source)?;
+    let doc = parser::Parser::new(tokens).parse_tokens()?;
+    Ok(codegen::compile(&doc)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lexer_error_keeps_position() {
+        let lex_err = lexer::Scanner::tokenize("div\n  p \"open").unwrap_err();
+        let err = Error::from(lex_err.clone());
+        assert_eq!(err, Error::Lex(lex_err.clone()));
+        assert_eq!(err.position(), Some((lex_err.line, lex_err.column)));
+        assert_eq!(err.position(), Some((2, 5)));
+        assert_eq!(err.to_string(), lex_err.to_string());
+    }
+
+    #[test]
+    fn test_compile_reports_each_stage() {
+        assert!(matches!(compile("p \"open"), Err(Error::Lex(_))));
+        assert!(matches!(compile("div\n  state"), Err(Error::Parse(_))));
+        assert!(matches!(
+            compile("img\n  span \"x\""),
+            Err(Error::Codegen(_))
+        ));
+        assert_eq!(compile("p \"Hi\"").unwrap().html, "<p>Hi</p>\n");
+    }
+}